@@ -0,0 +1,59 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+use crate::config;
+use crate::events::EmitEvent;
+use crate::idle;
+
+// Oldest entries are dropped past this; the log is for this session only
+const MAX_ENTRIES: usize = 200;
+
+/// One step the agent took, emitted as `activity` and kept for `get_session_log`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityEntry {
+    // Unix time in milliseconds
+    pub at: u64,
+    // shortcut, recording, transcription, llm, hook, error or cancelled
+    pub kind: &'static str,
+    pub message: String,
+    pub duration_ms: Option<u64>,
+    // `request_tags` at the time, so a shared log can be grouped by user or team
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+}
+
+/// In-memory ring of this session's activity, never written to disk
+#[derive(Default)]
+pub struct ActivityState {
+    entries: Mutex<VecDeque<ActivityEntry>>,
+}
+
+/// Log a step, which also counts as activity for the idle quit timer
+pub fn record(app: &AppHandle, kind: &'static str, message: impl Into<String>, duration: Option<Duration>) {
+    idle::touch(app);
+    let entry = ActivityEntry {
+        at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+        kind,
+        message: message.into(),
+        duration_ms: duration.map(|d| d.as_millis() as u64),
+        tags: config::get(app).request_tags,
+    };
+    let _ = app.emit_event("activity", &entry);
+
+    let state = app.state::<ActivityState>();
+    let mut entries = state.entries.lock().unwrap();
+    if entries.len() == MAX_ENTRIES {
+        entries.pop_front();
+    }
+    entries.push_back(entry);
+}
+
+/// Everything recorded this session, oldest first
+#[tauri::command]
+pub fn get_session_log(app: AppHandle) -> Vec<ActivityEntry> {
+    app.state::<ActivityState>().entries.lock().unwrap().iter().cloned().collect()
+}
@@ -0,0 +1,37 @@
+use base64::Engine;
+use std::path::Path;
+
+use crate::error::AppError;
+
+// Anthropic's per-image limit, the strictest of the supported providers
+const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// An image sent alongside a prompt, already base64-encoded
+#[derive(Debug, Clone)]
+pub struct ImageAttachment {
+    pub media_type: &'static str,
+    pub data: String,
+}
+
+/// Detect the format from the file's magic bytes rather than trusting the extension
+fn media_type(bytes: &[u8]) -> Option<&'static str> {
+    match bytes {
+        [0x89, b'P', b'N', b'G', ..] => Some("image/png"),
+        [0xFF, 0xD8, 0xFF, ..] => Some("image/jpeg"),
+        [b'G', b'I', b'F', b'8', ..] => Some("image/gif"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// Read and encode an image, rejecting anything the providers won't take
+pub async fn load_image(path: &Path) -> Result<ImageAttachment, AppError> {
+    let bytes = tokio::fs::read(path).await?;
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    if bytes.len() > MAX_IMAGE_BYTES {
+        return Err(AppError::Config(format!("{name} is larger than {} MB", MAX_IMAGE_BYTES / (1024 * 1024))));
+    }
+    let media_type =
+        media_type(&bytes).ok_or_else(|| AppError::Config(format!("{name} is not a PNG, JPEG, GIF or WebP image")))?;
+    Ok(ImageAttachment { media_type, data: base64::engine::general_purpose::STANDARD.encode(&bytes) })
+}
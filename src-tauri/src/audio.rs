@@ -0,0 +1,1139 @@
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, Sample, SampleFormat, SizedSample};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::Cursor;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tauri::async_runtime::JoinHandle as TaskHandle;
+use tauri::{AppHandle, Manager};
+use tokio::sync::mpsc::{self as channel, UnboundedSender};
+
+use crate::activity;
+use crate::cancel::CancelState;
+use crate::config::{self, AppConfig};
+use crate::error::{self, AppError};
+use crate::events::{EmitEvent, Empty};
+use crate::http::{self, HttpState};
+use crate::permission::{self, PermissionState};
+use crate::readiness;
+use crate::realtime;
+use crate::stats;
+use crate::tray::{self, TrayState};
+use crate::upload::{self, UploadFormat};
+use crate::whisper::{self, Transcript};
+
+// Peak level normalization aims for, leaving a little headroom
+const NORMALIZE_TARGET_PEAK: f32 = 0.89;
+// Windows quieter than this RMS count as silence when trimming
+const SILENCE_THRESHOLD_RMS: f32 = 0.01;
+// Bounds on a threshold derived from the noise floor, so a dead-quiet room
+// doesn't count hiss as speech and a loud one doesn't swallow it
+const MIN_ADAPTIVE_THRESHOLD_RMS: f32 = 0.002;
+const MAX_ADAPTIVE_THRESHOLD_RMS: f32 = 0.1;
+// Analysis window and padding kept around speech when trimming
+const SILENCE_WINDOW_MS: u32 = 10;
+const SILENCE_PADDING_MS: u32 = 200;
+// Samples at or above this count as clipped
+const CLIPPING_LEVEL: f32 = 0.99;
+// Points in the waveform envelope returned with each recording
+const WAVEFORM_POINTS: usize = 200;
+// How often the mic monitor emits `audio-level`
+const MONITOR_INTERVAL_MS: u64 = 50;
+// Longest rolling buffer set_passive_buffer accepts, and how often it is trimmed
+const MAX_PASSIVE_SECS: u32 = 300;
+const PASSIVE_DRAIN_MS: u64 = 100;
+// Calibration listens this long and aims speech at this RMS (about -20 dBFS)
+const CALIBRATION_SECS: u64 = 3;
+const CALIBRATION_TARGET_RMS: f32 = 0.1;
+// Calibrated gain never goes beyond this in either direction
+const CALIBRATION_MAX_GAIN_DB: f32 = 24.0;
+// Rate speech-to-text models work at; anything else gets resampled somewhere
+const TARGET_SAMPLE_RATE: u32 = 16_000;
+// Rates `probe_input_device` checks against the device's supported ranges
+const COMMON_SAMPLE_RATES: &[u32] = &[8_000, 11_025, 16_000, 22_050, 32_000, 44_100, 48_000, 88_200, 96_000, 192_000];
+
+/// Peak and RMS of a buffer, both in linear 0.0..=1.0 amplitude
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct Levels {
+    pub peak: f32,
+    pub rms: f32,
+}
+
+/// Returned by `calibrate_input` and emitted as `calibration-result`
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Calibration {
+    pub levels: Levels,
+    pub gain_db: f32,
+}
+
+/// Returned by `stop_recording`: the encoded clip plus input levels measured
+/// before gain, so the UI can suggest enabling gain for quiet microphones
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StopResult {
+    pub wav: Vec<u8>,
+    pub duration_ms: u64,
+    pub sample_rate: u32,
+    pub levels: Levels,
+    // Per-bucket peak amplitude across the clip, for drawing a static waveform
+    pub waveform: Vec<f32>,
+    // RMS below which audio counted as silence, fixed or from `noise_calibration_ms`
+    pub silence_threshold: f32,
+}
+
+/// Returned by `probe_input_device`: what an input device can capture
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceCaps {
+    pub name: String,
+    // Common rates that fall inside the device's supported ranges
+    pub sample_rates: Vec<u32>,
+    pub channels: Vec<u16>,
+    pub sample_formats: Vec<String>,
+    // What a recording actually uses
+    pub default_sample_rate: u32,
+    pub default_channels: u16,
+    pub default_sample_format: String,
+    pub supports_target_rate: bool,
+    // Recordings are captured at the default rate, so this is set when that
+    // isn't the 16 kHz the transcription models expect
+    pub needs_resampling: bool,
+}
+
+/// Why a recording is likely to transcribe badly
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityIssue {
+    TooQuiet,
+    Clipping,
+    MostlySilence,
+}
+
+impl QualityIssue {
+    fn suggestion(self) -> &'static str {
+        match self {
+            QualityIssue::TooQuiet => "Enable input gain or calibrate the input, or move closer to the microphone",
+            QualityIssue::Clipping => "Lower the input gain or move further from the microphone",
+            QualityIssue::MostlySilence => "Check that the right input device is selected and not muted",
+        }
+    }
+}
+
+/// Payload of `audio-quality-warning`
+#[derive(Debug, Clone, Serialize)]
+pub struct QualityWarning {
+    pub reason: QualityIssue,
+    pub suggestion: &'static str,
+}
+
+/// Payload of `recording-too-short`
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TooShort {
+    duration_ms: u64,
+    min_record_ms: u64,
+}
+
+/// A running capture. Dropping it stops the stream and waits for the device
+/// to be released, so an early return or panic can't leave the mic on.
+struct Recording {
+    stop_tx: mpsc::Sender<()>,
+    // Taken once the thread has been joined
+    thread: Option<JoinHandle<()>>,
+    samples: Arc<Mutex<Vec<f32>>>,
+    // Incoming audio is dropped while set
+    paused: Arc<AtomicBool>,
+    sample_rate: u32,
+    started: Instant,
+}
+
+impl Recording {
+    fn release(&mut self) {
+        let _ = self.stop_tx.send(());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+
+    /// Stop the stream thread and hand back everything captured
+    fn finish(mut self) -> Vec<f32> {
+        self.release();
+        std::mem::take(&mut *self.samples.lock().unwrap())
+    }
+}
+
+impl Drop for Recording {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+/// Audio of a stopped recording; the device has been released by the time one exists
+struct Clip {
+    samples: Vec<f32>,
+    sample_rate: u32,
+    duration: Duration,
+}
+
+/// Take the native recording out of `state` and stop its capture, so the
+/// microphone is released whatever happens to the audio afterwards
+fn take_clip(state: &AudioState) -> Result<Clip, AppError> {
+    let recording = state.recording.lock().unwrap().take().ok_or_else(|| AppError::Audio("Not recording".into()))?;
+    let sample_rate = recording.sample_rate;
+    let duration = recording.started.elapsed();
+    Ok(Clip { samples: recording.finish(), sample_rate, duration })
+}
+
+/// Live level meter: a capture whose buffer is drained and measured instead of
+/// kept. Dropping it stops the meter thread, then the capture.
+struct Monitor {
+    // Dropped after the meter has stopped reading from it
+    _capture: Recording,
+    running: Arc<AtomicBool>,
+    meter: Option<JoinHandle<()>>,
+}
+
+impl Monitor {
+    fn stop(self) {
+        drop(self);
+    }
+}
+
+impl Drop for Monitor {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(meter) = self.meter.take() {
+            let _ = meter.join();
+        }
+    }
+}
+
+/// Rolling buffer of the last `seconds` of microphone audio, kept outside of
+/// any recording. Dropping it stops the drain thread, then the capture.
+struct PassiveBuffer {
+    // Dropped after the drain thread has stopped reading from it
+    _capture: Recording,
+    running: Arc<AtomicBool>,
+    drain: Option<JoinHandle<()>>,
+    ring: Arc<Mutex<VecDeque<f32>>>,
+    sample_rate: u32,
+    seconds: u32,
+}
+
+impl Drop for PassiveBuffer {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(drain) = self.drain.take() {
+            let _ = drain.join();
+        }
+    }
+}
+
+/// Transcript of a recording uploaded while it was captured (`stream_upload`),
+/// ready once the endpoint has the last chunk
+pub type StreamedUpload = TaskHandle<Result<Transcript, AppError>>;
+
+#[derive(Default)]
+pub struct AudioState {
+    recording: Mutex<Option<Recording>>,
+    monitor: Mutex<Option<Monitor>>,
+    upload: Mutex<Option<StreamedUpload>>,
+    passive: Mutex<Option<PassiveBuffer>>,
+}
+
+pub fn measure(samples: &[f32]) -> Levels {
+    if samples.is_empty() {
+        return Levels::default();
+    }
+    let peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+    let sum_squares: f64 = samples.iter().map(|s| (*s as f64) * (*s as f64)).sum();
+    let rms = (sum_squares / samples.len() as f64).sqrt() as f32;
+    Levels { peak, rms }
+}
+
+/// Downsample to `points` peak values in a single pass over the buffer
+pub fn envelope(samples: &[f32], points: usize) -> Vec<f32> {
+    if samples.is_empty() || points == 0 {
+        return Vec::new();
+    }
+    let points = points.min(samples.len());
+    let mut buckets = vec![0.0f32; points];
+    for (i, sample) in samples.iter().enumerate() {
+        let bucket = i * points / samples.len();
+        buckets[bucket] = buckets[bucket].max(sample.abs());
+    }
+    buckets
+}
+
+/// The level below which this recording counts as silence. With
+/// `noise_calibration_ms` set, that is `noise_margin_db` above the noise floor
+/// of the recording's first moments, taken from its quieter windows so that
+/// speaking straight away doesn't raise it.
+fn silence_threshold(samples: &[f32], sample_rate: u32, config: &AppConfig) -> f32 {
+    if config.noise_calibration_ms == 0 {
+        return SILENCE_THRESHOLD_RMS;
+    }
+    let window = (sample_rate * SILENCE_WINDOW_MS / 1000).max(1) as usize;
+    let calibration = (sample_rate as u64 * config.noise_calibration_ms as u64 / 1000) as usize;
+    let mut levels: Vec<f32> = samples[..calibration.min(samples.len())].chunks(window).map(|chunk| measure(chunk).rms).collect();
+    if levels.is_empty() {
+        return SILENCE_THRESHOLD_RMS;
+    }
+    levels.sort_by(f32::total_cmp);
+    let floor = levels[levels.len() / 5];
+    (floor * 10f32.powf(config.noise_margin_db / 20.0)).clamp(MIN_ADAPTIVE_THRESHOLD_RMS, MAX_ADAPTIVE_THRESHOLD_RMS)
+}
+
+/// Strip leading and trailing silence, keeping a little padding around speech.
+/// A clip that is silent throughout is returned untouched.
+pub fn trim_silence(samples: &[f32], sample_rate: u32, threshold: f32) -> &[f32] {
+    let window = (sample_rate * SILENCE_WINDOW_MS / 1000).max(1) as usize;
+    let padding = (sample_rate * SILENCE_PADDING_MS / 1000) as usize;
+    let is_loud = |chunk: &[f32]| measure(chunk).rms >= threshold;
+
+    let Some(first) = samples.chunks(window).position(is_loud) else {
+        return samples;
+    };
+    let last = samples.chunks(window).rposition(is_loud).unwrap_or(first);
+
+    let start = (first * window).saturating_sub(padding);
+    let end = ((last + 1) * window + padding).min(samples.len());
+    &samples[start..end]
+}
+
+/// Boost (or cut) by `gain_db`, clamping so a boost can't push samples past full scale
+pub fn apply_gain(samples: &mut [f32], gain_db: f32) {
+    if gain_db == 0.0 {
+        return;
+    }
+    let factor = 10f32.powf(gain_db / 20.0);
+    for sample in samples.iter_mut() {
+        *sample = (*sample * factor).clamp(-1.0, 1.0);
+    }
+}
+
+/// Problems with a raw (pre-gain) recording, judged against the configured thresholds
+fn quality_issues(samples: &[f32], sample_rate: u32, levels: Levels, threshold: f32, config: &AppConfig) -> Vec<QualityIssue> {
+    if samples.is_empty() {
+        return Vec::new();
+    }
+    let window = (sample_rate * SILENCE_WINDOW_MS / 1000).max(1) as usize;
+    let windows = samples.len().div_ceil(window);
+    let loud = samples.chunks(window).filter(|chunk| measure(chunk).rms >= threshold).count();
+    let clipped = samples.iter().filter(|s| s.abs() >= CLIPPING_LEVEL).count();
+
+    let mut issues = Vec::new();
+    if (loud as f32 / windows as f32) < config.min_speech_ratio {
+        issues.push(QualityIssue::MostlySilence);
+    } else if levels.peak < config.quiet_peak {
+        issues.push(QualityIssue::TooQuiet);
+    }
+    if (clipped as f32 / samples.len() as f32) > config.clipping_ratio {
+        issues.push(QualityIssue::Clipping);
+    }
+    issues
+}
+
+/// Scale the buffer so its peak hits `NORMALIZE_TARGET_PEAK`
+pub fn normalize_peak(samples: &mut [f32]) {
+    let peak = measure(samples).peak;
+    if peak <= f32::EPSILON {
+        return; // Pure silence - nothing to scale
+    }
+    let factor = NORMALIZE_TARGET_PEAK / peak;
+    for sample in samples.iter_mut() {
+        *sample = (*sample * factor).clamp(-1.0, 1.0);
+    }
+}
+
+/// Sample format of encoded recordings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WavBitDepth {
+    #[default]
+    #[serde(rename = "16")]
+    Pcm16,
+    #[serde(rename = "24")]
+    Pcm24,
+    #[serde(rename = "32f")]
+    Float32,
+}
+
+/// Tiny xorshift generator for dither noise; quality barely matters here
+struct Dither(u32);
+
+impl Dither {
+    fn next(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as f32 / u32::MAX as f32
+    }
+
+    /// Triangular noise spanning ±1 LSB, which decorrelates quantization error from the signal
+    fn triangular(&mut self) -> f32 {
+        self.next() - self.next()
+    }
+}
+
+/// Encode mono f32 samples as WAV at `depth`. 16-bit output is dithered;
+/// 24-bit has enough resolution to round directly.
+pub fn encode_wav(samples: &[f32], sample_rate: u32, depth: WavBitDepth) -> Result<Vec<u8>, AppError> {
+    let (bits_per_sample, sample_format) = match depth {
+        WavBitDepth::Pcm16 => (16, hound::SampleFormat::Int),
+        WavBitDepth::Pcm24 => (24, hound::SampleFormat::Int),
+        WavBitDepth::Float32 => (32, hound::SampleFormat::Float),
+    };
+    // hound divides by the rate while writing the header
+    if sample_rate == 0 {
+        return Err(AppError::Audio("The input device reported a sample rate of 0".into()));
+    }
+    let spec = hound::WavSpec { channels: 1, sample_rate, bits_per_sample, sample_format };
+    let audio_err = |e: hound::Error| AppError::Audio(e.to_string());
+
+    let mut cursor = Cursor::new(Vec::new());
+    {
+        let mut writer = hound::WavWriter::new(&mut cursor, spec).map_err(audio_err)?;
+        match depth {
+            WavBitDepth::Pcm16 => {
+                let mut dither = Dither(0x9E37_79B9);
+                let max = i16::MAX as f32;
+                for sample in samples {
+                    let scaled = (sample.clamp(-1.0, 1.0) * max + dither.triangular()).round();
+                    writer.write_sample(scaled.clamp(i16::MIN as f32, max) as i16).map_err(audio_err)?;
+                }
+            }
+            WavBitDepth::Pcm24 => {
+                const MAX_24: f32 = 8_388_607.0;
+                for sample in samples {
+                    writer.write_sample((sample.clamp(-1.0, 1.0) * MAX_24).round() as i32).map_err(audio_err)?;
+                }
+            }
+            WavBitDepth::Float32 => {
+                for sample in samples {
+                    writer.write_sample(sample.clamp(-1.0, 1.0)).map_err(audio_err)?;
+                }
+            }
+        }
+        writer.finalize().map_err(audio_err)?;
+    }
+    Ok(cursor.into_inner())
+}
+
+/// Payload of `audio-format-warning`
+#[derive(Debug, Clone, Serialize)]
+pub struct FormatWarning {
+    pub message: &'static str,
+}
+
+/// Why `wav_bit_depth` may not do what the user expects with the rest of the
+/// config. Only a hint: nothing is known for sure about a custom endpoint.
+pub fn bit_depth_warning(config: &AppConfig) -> Option<FormatWarning> {
+    if config.wav_bit_depth == WavBitDepth::Pcm16 {
+        return None;
+    }
+    let message = if config.upload_format == UploadFormat::Opus {
+        "Recordings are re-encoded to Opus before upload, so the WAV bit depth has no effect"
+    } else if config.stream_upload {
+        "Streamed uploads are always 16-bit; the bit depth only applies to buffered recordings"
+    } else if !config.whisper_url.starts_with("https://api.openai.com/") {
+        "Many self-hosted transcription servers (such as whisper.cpp) only accept 16-bit WAV"
+    } else {
+        return None;
+    };
+    Some(FormatWarning { message })
+}
+
+pub fn find_input_device(name: Option<&str>) -> Result<cpal::Device, AppError> {
+    let host = cpal::default_host();
+    match name {
+        Some(name) => host
+            .input_devices()
+            .map_err(|e| AppError::Audio(e.to_string()))?
+            .find(|device| device.name().is_ok_and(|n| n == name))
+            .ok_or_else(|| AppError::Audio(format!("Input device \"{name}\" not found"))),
+        None => host
+            .default_input_device()
+            .ok_or_else(|| AppError::Audio("No input device available".into())),
+    }
+}
+
+/// Report the sample rates, channel counts and formats the named input device
+/// (or the default one) supports, so incompatible settings can be disabled
+#[tauri::command]
+pub fn probe_input_device(name: Option<String>) -> Result<DeviceCaps, AppError> {
+    let device = find_input_device(name.as_deref())?;
+    let default = device.default_input_config().map_err(|e| AppError::Audio(e.to_string()))?;
+    let ranges: Vec<cpal::SupportedStreamConfigRange> = device
+        .supported_input_configs()
+        .map_err(|e| AppError::Audio(e.to_string()))?
+        .collect();
+
+    let sample_rates: Vec<u32> = COMMON_SAMPLE_RATES
+        .iter()
+        .copied()
+        .filter(|&rate| ranges.iter().any(|range| (range.min_sample_rate().0..=range.max_sample_rate().0).contains(&rate)))
+        .collect();
+    let mut channels: Vec<u16> = ranges.iter().map(|range| range.channels()).collect();
+    channels.sort_unstable();
+    channels.dedup();
+    let mut sample_formats: Vec<String> = ranges.iter().map(|range| range.sample_format().to_string()).collect();
+    sample_formats.sort();
+    sample_formats.dedup();
+
+    Ok(DeviceCaps {
+        name: device.name().unwrap_or_default(),
+        supports_target_rate: sample_rates.contains(&TARGET_SAMPLE_RATE),
+        needs_resampling: default.sample_rate().0 != TARGET_SAMPLE_RATE,
+        sample_rates,
+        channels,
+        sample_formats,
+        default_sample_rate: default.sample_rate().0,
+        default_channels: default.channels(),
+        default_sample_format: default.sample_format().to_string(),
+    })
+}
+
+/// Receives every mono chunk as it's captured, before it reaches the buffer
+pub type SampleTap = UnboundedSender<Vec<f32>>;
+
+/// Build an input stream that downmixes every callback to mono f32 into
+/// `samples`, skipping callbacks while `paused` is set
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    samples: Arc<Mutex<Vec<f32>>>,
+    paused: Arc<AtomicBool>,
+    tap: Option<SampleTap>,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels as usize;
+    device.build_input_stream(
+        config,
+        move |data: &[T], _| {
+            if paused.load(Ordering::Relaxed) {
+                return;
+            }
+            let mono: Vec<f32> = data
+                .chunks(channels)
+                .map(|frame| frame.iter().map(|s| f32::from_sample(*s)).sum::<f32>() / frame.len() as f32)
+                .collect();
+            if let Some(tap) = &tap {
+                let _ = tap.send(mono.clone());
+            }
+            samples.lock().unwrap().extend(mono);
+        },
+        |e| tracing::error!("Audio stream error: {e}"),
+        None,
+    )
+}
+
+fn open_stream(
+    device_name: Option<&str>,
+    samples: Arc<Mutex<Vec<f32>>>,
+    paused: Arc<AtomicBool>,
+    tap: Option<SampleTap>,
+) -> Result<(cpal::Stream, u32), AppError> {
+    let device = find_input_device(device_name)?;
+    let supported = device.default_input_config().map_err(|e| AppError::Audio(e.to_string()))?;
+    let config: cpal::StreamConfig = supported.config();
+
+    let stream = match supported.sample_format() {
+        SampleFormat::F32 => build_stream::<f32>(&device, &config, samples, paused, tap),
+        SampleFormat::I16 => build_stream::<i16>(&device, &config, samples, paused, tap),
+        SampleFormat::U16 => build_stream::<u16>(&device, &config, samples, paused, tap),
+        other => return Err(AppError::Audio(format!("Unsupported sample format {other}"))),
+    }
+    .map_err(|e| AppError::Audio(e.to_string()))?;
+
+    stream.play().map_err(|e| AppError::Audio(e.to_string()))?;
+    Ok((stream, config.sample_rate.0))
+}
+
+/// cpal streams aren't `Send`, so each recording owns a thread that keeps the
+/// stream alive until it's told to stop
+fn start_capture(config: &AppConfig, tap: Option<SampleTap>) -> Result<Recording, AppError> {
+    let samples = Arc::new(Mutex::new(Vec::new()));
+    let (ready_tx, ready_rx) = mpsc::channel();
+    let (stop_tx, stop_rx) = mpsc::channel::<()>();
+
+    let paused = Arc::new(AtomicBool::new(false));
+    let buffer = samples.clone();
+    let stream_paused = paused.clone();
+    let device_name = config.input_device.clone();
+    let thread = thread::spawn(move || match open_stream(device_name.as_deref(), buffer, stream_paused, tap) {
+        Ok((stream, sample_rate)) => {
+            let _ = ready_tx.send(Ok(sample_rate));
+            // Blocks until stop is requested or the sender is dropped
+            let _ = stop_rx.recv();
+            drop(stream);
+        }
+        Err(e) => {
+            let _ = ready_tx.send(Err(e));
+        }
+    });
+
+    let sample_rate = ready_rx
+        .recv()
+        .map_err(|_| AppError::Audio("Audio thread exited unexpectedly".into()))??;
+
+    Ok(Recording { stop_tx, thread: Some(thread), samples, paused, sample_rate, started: Instant::now() })
+}
+
+/// Gain in dB that brings `levels` to the calibration target without pushing
+/// the peak past the normalization ceiling
+fn calibration_gain_db(levels: Levels) -> f32 {
+    let to_target = 20.0 * (CALIBRATION_TARGET_RMS / levels.rms).log10();
+    let to_ceiling = 20.0 * (NORMALIZE_TARGET_PEAK / levels.peak).log10();
+    to_target.min(to_ceiling).clamp(-CALIBRATION_MAX_GAIN_DB, CALIBRATION_MAX_GAIN_DB)
+}
+
+/// Pause or resume a native recording as the window loses or regains focus,
+/// when `pause_recording_on_blur` is on. Otherwise focus doesn't affect capture.
+pub fn on_focus_changed(app: &AppHandle, focused: bool) {
+    if !config::get(app).pause_recording_on_blur {
+        return;
+    }
+    let state = app.state::<AudioState>();
+    let recording = state.recording.lock().unwrap();
+    let Some(recording) = recording.as_ref() else {
+        return;
+    };
+    let paused = !focused;
+    if recording.paused.swap(paused, Ordering::SeqCst) != paused {
+        let _ = app.emit_event(if paused { "recording-paused" } else { "recording-resumed" }, Empty {});
+    }
+}
+
+/// Whether the backend is capturing audio (as opposed to the webview recorder)
+pub fn is_recording(app: &AppHandle) -> bool {
+    app.state::<AudioState>().recording.lock().unwrap().is_some()
+}
+
+/// Stop capturing and throw the audio away
+pub fn discard(app: &AppHandle) {
+    let state = app.state::<AudioState>();
+    let Some(recording) = state.recording.lock().unwrap().take() else {
+        return;
+    };
+    if let Some(upload) = state.upload.lock().unwrap().take() {
+        upload.abort();
+    }
+    recording.finish();
+    tray::set_state(app, TrayState::Idle);
+}
+
+/// Manual recovery for a microphone that stayed open: stop any native
+/// recording, the level meter and the passive buffer, and reset the tray. Returns whether
+/// anything was still holding the device.
+#[tauri::command]
+pub fn force_release_audio(app: AppHandle) -> bool {
+    let state = app.state::<AudioState>();
+    let recording = state.recording.lock().unwrap().take();
+    let monitor = state.monitor.lock().unwrap().take();
+    if let Some(upload) = state.upload.lock().unwrap().take() {
+        upload.abort();
+    }
+    let passive = state.passive.lock().unwrap().take();
+    let released = recording.is_some() || monitor.is_some() || passive.is_some();
+    let passive_was_on = passive.is_some();
+    drop(recording);
+    drop(monitor);
+    drop(passive);
+    if passive_was_on {
+        passive_changed(&app);
+    }
+    realtime::abandon(&app);
+
+    tray::set_state(&app, TrayState::Idle);
+    if released {
+        tracing::warn!("Audio device was force-released");
+        activity::record(&app, "recording", "Audio device released", None);
+    }
+    let _ = app.emit_event("audio-released", Empty {});
+    released
+}
+
+/// Payload of `audio-reset`
+#[derive(Debug, Clone, Serialize)]
+struct AudioReset<'a> {
+    device: &'a str,
+}
+
+/// Recover from a device switch or driver hiccup without a restart: stop the
+/// level meter, look the configured device up again and open it once to make
+/// sure it works. A meter that was running is started again on the new
+/// stream. Returns the device name and emits it as `audio-reset`.
+#[tauri::command]
+pub fn reset_audio(app: AppHandle) -> Result<String, AppError> {
+    if is_recording(&app) || tray::current_state(&app) == TrayState::Recording {
+        return Err(AppError::Audio("Stop recording before resetting audio".into()));
+    }
+    let monitoring = app.state::<AudioState>().monitor.lock().unwrap().take().map(Monitor::stop).is_some();
+
+    let config = config::get(&app);
+    let device = find_input_device(config.input_device.as_deref())?.name().map_err(|e| AppError::Audio(e.to_string()))?;
+    start_capture(&config, None)?.finish();
+    tracing::info!("Audio reset, using input device \"{device}\"");
+
+    if monitoring {
+        start_mic_monitor(app.clone())?;
+    }
+    let _ = app.emit_event("audio-reset", AudioReset { device: &device });
+    readiness::refresh(&app);
+    Ok(device)
+}
+
+#[tauri::command]
+pub fn start_recording(app: AppHandle) -> Result<(), AppError> {
+    begin_recording(&app, None).map(|_| ())
+}
+
+/// Start a native recording that also feeds every captured chunk to `tap`.
+/// Returns the capture sample rate.
+pub fn start_tapped_recording(app: &AppHandle, tap: SampleTap) -> Result<u32, AppError> {
+    begin_recording(app, Some(tap))
+}
+
+fn begin_recording(app: &AppHandle, tap: Option<SampleTap>) -> Result<u32, AppError> {
+    let result = open_recording(app, tap);
+    error::record_outcome(app, "recording", &result);
+    result
+}
+
+fn open_recording(app: &AppHandle, tap: Option<SampleTap>) -> Result<u32, AppError> {
+    let state = app.state::<AudioState>();
+    let mut recording = state.recording.lock().unwrap();
+    if recording.is_some() {
+        return Err(AppError::Audio("Already recording".into()));
+    }
+
+    // A refused permission on macOS records silence forever, so fail loudly instead
+    if permission::check() == PermissionState::Denied {
+        permission::notify_denied(app);
+        return Err(AppError::Audio("Microphone access was denied".into()));
+    }
+
+    // Recording takes the device over from the settings meter
+    if let Some(monitor) = state.monitor.lock().unwrap().take() {
+        monitor.stop();
+        let _ = app.emit_event("mic-monitor-stopped", Empty {});
+    }
+
+    let config = config::get(app);
+    let capture = match tap {
+        None if config.stream_upload => {
+            let (tap, chunks) = channel::unbounded_channel();
+            let capture = start_capture(&config, Some(tap))?;
+            *state.upload.lock().unwrap() = Some(start_upload(app, &config, chunks, capture.sample_rate));
+            capture
+        }
+        tap => start_capture(&config, tap)?,
+    };
+    let sample_rate = capture.sample_rate;
+    *recording = Some(capture);
+    tray::set_state(app, TrayState::Recording);
+    Ok(sample_rate)
+}
+
+/// Start uploading the recording to the transcription endpoint as it is
+/// captured. Gain is applied on the way; trimming and normalization need the
+/// whole clip, so only the buffered fallback gets them.
+fn start_upload(app: &AppHandle, config: &AppConfig, chunks: channel::UnboundedReceiver<Vec<f32>>, sample_rate: u32) -> StreamedUpload {
+    let app = app.clone();
+    let config = config.clone();
+    let token = app.state::<CancelState>().token();
+    tauri::async_runtime::spawn(async move {
+        http::ensure_online(&app, &config, &config.whisper_url).await?;
+        let wav = upload::wav_body(chunks, sample_rate, config.input_gain_db);
+        whisper::transcribe_stream(&app.state::<HttpState>(), &config, wav, &token).await
+    })
+}
+
+/// Stop the native recording. The upload that was streaming it, if any, is
+/// stopped too since the caller takes the buffered clip instead.
+#[tauri::command]
+pub fn stop_recording(app: AppHandle) -> Result<StopResult, AppError> {
+    let (result, upload) = stop_with_upload(&app)?;
+    if let Some(upload) = upload {
+        upload.abort();
+    }
+    Ok(result)
+}
+
+/// Stop the native recording, handing back the streamed upload next to the
+/// buffered clip. The clip is kept so an endpoint that refused the stream can
+/// still get the recording. Recordings shorter than `min_record_ms` are
+/// dropped and reported as cancelled.
+pub fn stop_with_upload(app: &AppHandle) -> Result<(StopResult, Option<StreamedUpload>), AppError> {
+    let state = app.state::<AudioState>();
+    let clip = take_clip(&state)?;
+    let upload = state.upload.lock().unwrap().take();
+
+    // An accidental tap of the shortcut isn't worth an API call
+    let duration_ms = clip.duration.as_millis() as u64;
+    let min_record_ms = config::get(app).min_record_ms;
+    if duration_ms < min_record_ms {
+        if let Some(upload) = upload {
+            upload.abort();
+        }
+        tray::set_state(app, TrayState::Idle);
+        tracing::info!("Discarded a {duration_ms}ms recording");
+        let _ = app.emit_event("recording-too-short", TooShort { duration_ms, min_record_ms });
+        return Err(AppError::Cancelled);
+    }
+
+    let result = finish_recording(app, clip);
+    error::record_outcome(app, "recording", &result);
+    Ok((result?, upload))
+}
+
+fn finish_recording(app: &AppHandle, clip: Clip) -> Result<StopResult, AppError> {
+    tray::set_state(app, TrayState::Idle);
+    activity::record(app, "recording", format!("Recorded {:.1}s", clip.duration.as_secs_f32()), Some(clip.duration));
+    stats::recorded(app, clip.duration);
+
+    let levels = measure(&clip.samples);
+
+    let config = config::get(app);
+    let threshold = silence_threshold(&clip.samples, clip.sample_rate, &config);
+    if config.audio_quality_warnings {
+        for reason in quality_issues(&clip.samples, clip.sample_rate, levels, threshold, &config) {
+            let _ = app.emit_event("audio-quality-warning", QualityWarning { reason, suggestion: reason.suggestion() });
+        }
+    }
+    process_clip(clip, levels, threshold, &config)
+}
+
+/// Trimming, gain, normalization and encoding for a stopped recording
+fn process_clip(clip: Clip, levels: Levels, threshold: f32, config: &AppConfig) -> Result<StopResult, AppError> {
+    let Clip { mut samples, sample_rate, duration } = clip;
+    if config.trim_silence {
+        samples = trim_silence(&samples, sample_rate, threshold).to_vec();
+    }
+    apply_gain(&mut samples, config.input_gain_db);
+    if config.normalize_audio {
+        normalize_peak(&mut samples);
+    }
+
+    Ok(StopResult {
+        wav: encode_wav(&samples, sample_rate, config.wav_bit_depth)?,
+        duration_ms: duration.as_millis() as u64,
+        sample_rate,
+        levels,
+        waveform: envelope(&samples, WAVEFORM_POINTS),
+        silence_threshold: threshold,
+    })
+}
+
+/// Record a few seconds of speech and store the gain that brings it to a
+/// comfortable level as `input_gain_db`, which later recordings apply
+#[tauri::command]
+pub async fn calibrate_input(app: AppHandle) -> Result<Calibration, AppError> {
+    {
+        let state = app.state::<AudioState>();
+        if state.recording.lock().unwrap().is_some() || state.monitor.lock().unwrap().is_some() {
+            return Err(AppError::Audio("Stop recording and the level meter before calibrating".into()));
+        }
+    }
+    if permission::check() == PermissionState::Denied {
+        permission::notify_denied(&app);
+        return Err(AppError::Audio("Microphone access was denied".into()));
+    }
+
+    let capture = start_capture(&config::get(&app), None)?;
+    tokio::time::sleep(Duration::from_secs(CALIBRATION_SECS)).await;
+    let levels = measure(&capture.finish());
+    if levels.rms < SILENCE_THRESHOLD_RMS {
+        return Err(AppError::Audio("No speech was picked up - check the microphone and try again".into()));
+    }
+
+    let calibration = Calibration { levels, gain_db: calibration_gain_db(levels) };
+    config::modify(&app, |config| config.input_gain_db = calibration.gain_db).map_err(AppError::Config)?;
+    let _ = app.emit_event("calibration-result", calibration);
+    Ok(calibration)
+}
+
+/// Open the input device and emit `audio-level` until stopped, without keeping
+/// any audio. Fails while recording; starting a recording stops the monitor.
+#[tauri::command]
+pub fn start_mic_monitor(app: AppHandle) -> Result<(), AppError> {
+    let state = app.state::<AudioState>();
+    if state.recording.lock().unwrap().is_some() {
+        return Err(AppError::Audio("Cannot monitor the microphone while recording".into()));
+    }
+    let mut monitor = state.monitor.lock().unwrap();
+    if monitor.is_some() {
+        return Ok(());
+    }
+    if permission::check() == PermissionState::Denied {
+        permission::notify_denied(&app);
+        return Err(AppError::Audio("Microphone access was denied".into()));
+    }
+
+    let config = config::get(&app);
+    let capture = start_capture(&config, None)?;
+    let running = Arc::new(AtomicBool::new(true));
+
+    let samples = capture.samples.clone();
+    let meter_running = running.clone();
+    let meter_app = app.clone();
+    let meter = thread::spawn(move || {
+        while meter_running.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(MONITOR_INTERVAL_MS));
+            let chunk = std::mem::take(&mut *samples.lock().unwrap());
+            let _ = meter_app.emit_event("audio-level", measure(&chunk));
+        }
+    });
+
+    *monitor = Some(Monitor { _capture: capture, running, meter: Some(meter) });
+    Ok(())
+}
+
+#[tauri::command]
+pub fn stop_mic_monitor(app: AppHandle) {
+    if let Some(monitor) = app.state::<AudioState>().monitor.lock().unwrap().take() {
+        monitor.stop();
+    }
+}
+
+/// Returned by `get_passive_buffer` and emitted as `passive-buffer-changed`
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PassiveStatus {
+    pub enabled: bool,
+    pub seconds: u32,
+    // How much audio is held right now, up to `seconds`
+    pub buffered_ms: u64,
+}
+
+pub fn passive_status(app: &AppHandle) -> PassiveStatus {
+    match app.state::<AudioState>().passive.lock().unwrap().as_ref() {
+        Some(passive) => PassiveStatus {
+            enabled: true,
+            seconds: passive.seconds,
+            buffered_ms: passive.ring.lock().unwrap().len() as u64 * 1000 / passive.sample_rate as u64,
+        },
+        None => PassiveStatus { enabled: false, seconds: 0, buffered_ms: 0 },
+    }
+}
+
+fn passive_changed(app: &AppHandle) {
+    let _ = app.emit_event("passive-buffer-changed", passive_status(app));
+    tray::refresh_tooltip(app);
+    tray::rebuild_menu(app);
+}
+
+/// Keep the last `seconds` of microphone audio in memory at all times, or stop
+/// doing so. The microphone stays open the whole time and the tray says so.
+/// Nothing is written to disk or sent anywhere unless `save_passive_buffer`
+/// is called, and the buffer is not kept across restarts.
+#[tauri::command]
+pub fn set_passive_buffer(app: AppHandle, enabled: bool, seconds: Option<u32>) -> Result<(), AppError> {
+    let state = app.state::<AudioState>();
+    if !enabled {
+        let previous = state.passive.lock().unwrap().take();
+        if previous.is_some() {
+            drop(previous);
+            passive_changed(&app);
+        }
+        return Ok(());
+    }
+    // Checked before touching a running buffer, which a bad request leaves as it is
+    let current_seconds = state.passive.lock().unwrap().as_ref().map(|passive| passive.seconds);
+    let seconds = seconds.or(current_seconds).unwrap_or(30);
+    if !(1..=MAX_PASSIVE_SECS).contains(&seconds) {
+        return Err(AppError::Config(format!("The passive buffer must be 1-{MAX_PASSIVE_SECS} seconds")));
+    }
+    if permission::check() == PermissionState::Denied {
+        permission::notify_denied(&app);
+        return Err(AppError::Audio("Microphone access was denied".into()));
+    }
+
+    // The old capture lets go of the device before the new one opens it
+    let previous = state.passive.lock().unwrap().take();
+    let replaced = previous.is_some();
+    drop(previous);
+    let capture = match start_capture(&config::get(&app), None) {
+        Ok(capture) => capture,
+        Err(e) => {
+            if replaced {
+                passive_changed(&app);
+            }
+            return Err(e);
+        }
+    };
+    let sample_rate = capture.sample_rate;
+    let capacity = (sample_rate * seconds) as usize;
+    let ring = Arc::new(Mutex::new(VecDeque::with_capacity(capacity)));
+    let running = Arc::new(AtomicBool::new(true));
+
+    let samples = capture.samples.clone();
+    let drain_ring = ring.clone();
+    let drain_running = running.clone();
+    let drain = thread::spawn(move || {
+        while drain_running.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(PASSIVE_DRAIN_MS));
+            let chunk = std::mem::take(&mut *samples.lock().unwrap());
+            let mut ring = drain_ring.lock().unwrap();
+            ring.extend(chunk);
+            let excess = ring.len().saturating_sub(capacity);
+            ring.drain(..excess);
+        }
+    });
+
+    *state.passive.lock().unwrap() = Some(PassiveBuffer { _capture: capture, running, drain: Some(drain), ring, sample_rate, seconds });
+    passive_changed(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_passive_buffer(app: AppHandle) -> PassiveStatus {
+    passive_status(&app)
+}
+
+/// The passive buffer's audio as a WAV file, with the recording gain applied.
+/// The buffer keeps running.
+#[tauri::command]
+pub fn save_passive_buffer(app: AppHandle) -> Result<Vec<u8>, AppError> {
+    let state = app.state::<AudioState>();
+    let (mut samples, sample_rate) = {
+        let passive = state.passive.lock().unwrap();
+        let passive = passive.as_ref().ok_or_else(|| AppError::Audio("The passive buffer is off".into()))?;
+        let samples: Vec<f32> = passive.ring.lock().unwrap().iter().copied().collect();
+        (samples, passive.sample_rate)
+    };
+    let config = config::get(&app);
+    apply_gain(&mut samples, config.input_gain_db);
+    encode_wav(&samples, sample_rate, config.wav_bit_depth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A recording whose capture thread sets `released` once it has been told to stop
+    fn fake_recording(released: Arc<AtomicBool>, sample_rate: u32) -> Recording {
+        let (stop_tx, stop_rx) = mpsc::channel::<()>();
+        let thread = thread::spawn(move || {
+            let _ = stop_rx.recv();
+            released.store(true, Ordering::SeqCst);
+        });
+        Recording {
+            stop_tx,
+            thread: Some(thread),
+            samples: Arc::new(Mutex::new(vec![0.25; 1600])),
+            paused: Arc::new(AtomicBool::new(false)),
+            sample_rate,
+            started: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn failed_processing_leaves_the_device_released() {
+        let state = AudioState::default();
+        let released = Arc::new(AtomicBool::new(false));
+        *state.recording.lock().unwrap() = Some(fake_recording(released.clone(), 0));
+
+        let result = take_clip(&state).and_then(|clip| process_clip(clip, Levels::default(), 0.0, &AppConfig::default()));
+
+        assert!(matches!(result, Err(AppError::Audio(_))));
+        assert!(state.recording.lock().unwrap().is_none());
+        assert!(released.load(Ordering::SeqCst), "capture thread was not joined");
+    }
+
+    #[test]
+    fn dropping_a_recording_joins_its_thread() {
+        let released = Arc::new(AtomicBool::new(false));
+        drop(fake_recording(released.clone(), 16_000));
+        assert!(released.load(Ordering::SeqCst));
+    }
+
+    /// `before` seconds of silence, `tone` seconds of a 0.5-amplitude sine, `after` of silence
+    fn padded_tone(sample_rate: u32, before: f32, tone: f32, after: f32) -> Vec<f32> {
+        let len = |secs: f32| (secs * sample_rate as f32) as usize;
+        let mut samples = vec![0.0; len(before)];
+        samples.extend((0..len(tone)).map(|i| (i as f32 * 0.1).sin() * 0.5));
+        samples.extend(vec![0.0; len(after)]);
+        samples
+    }
+
+    #[test]
+    fn trim_silence_keeps_speech_and_padding() {
+        let samples = padded_tone(16_000, 1.0, 0.5, 1.0);
+        let trimmed = trim_silence(&samples, 16_000, SILENCE_THRESHOLD_RMS);
+
+        // Tone at 16000..24000; 200ms (3200 samples) of padding either side
+        let start = trimmed.as_ptr() as usize - samples.as_ptr() as usize;
+        assert_eq!(start / size_of::<f32>(), 12_800);
+        assert_eq!(trimmed.len(), 27_200 - 12_800);
+    }
+
+    #[test]
+    fn trim_silence_keeps_padding_inside_the_clip() {
+        let samples = padded_tone(16_000, 0.05, 0.5, 0.05);
+        assert_eq!(trim_silence(&samples, 16_000, SILENCE_THRESHOLD_RMS).len(), samples.len());
+    }
+
+    #[test]
+    fn trim_silence_leaves_silent_clips_alone() {
+        let silent = vec![0.001; 16_000];
+        assert_eq!(trim_silence(&silent, 16_000, SILENCE_THRESHOLD_RMS).len(), silent.len());
+        assert!(trim_silence(&[], 16_000, SILENCE_THRESHOLD_RMS).is_empty());
+    }
+
+    /// -1.5 to 1.5 in even steps, so the ends test clamping
+    fn ramp() -> Vec<f32> {
+        (0..=300).map(|i| i as f32 / 100.0 - 1.5).collect()
+    }
+
+    fn decode(wav: Vec<u8>) -> hound::WavReader<Cursor<Vec<u8>>> {
+        hound::WavReader::new(Cursor::new(wav)).expect("valid WAV")
+    }
+
+    #[test]
+    fn encode_wav_pcm16_scales_with_dither() {
+        let mut reader = decode(encode_wav(&ramp(), 16_000, WavBitDepth::Pcm16).unwrap());
+        assert_eq!(reader.spec().bits_per_sample, 16);
+        assert_eq!(reader.spec().sample_format, hound::SampleFormat::Int);
+        assert_eq!(reader.spec().sample_rate, 16_000);
+        let decoded: Vec<i16> = reader.samples::<i16>().map(Result::unwrap).collect();
+        assert_eq!(decoded.len(), ramp().len());
+        for (sample, expected) in decoded.iter().zip(ramp()) {
+            let expected = expected.clamp(-1.0, 1.0) * i16::MAX as f32;
+            // Dither adds at most one step either way
+            assert!((*sample as f32 - expected).abs() <= 1.5, "{sample} vs {expected}");
+        }
+        assert!(decoded[0] <= -i16::MAX + 1, "-1.5 wrapped instead of clamping");
+        assert!(decoded[decoded.len() - 1] >= i16::MAX - 1, "+1.5 wrapped instead of clamping");
+    }
+
+    #[test]
+    fn encode_wav_pcm24_rounds_exactly() {
+        let mut reader = decode(encode_wav(&ramp(), 16_000, WavBitDepth::Pcm24).unwrap());
+        assert_eq!(reader.spec().bits_per_sample, 24);
+        assert_eq!(reader.spec().sample_format, hound::SampleFormat::Int);
+        let decoded: Vec<i32> = reader.samples::<i32>().map(Result::unwrap).collect();
+        for (sample, expected) in decoded.iter().zip(ramp()) {
+            assert_eq!(*sample, (expected.clamp(-1.0, 1.0) * 8_388_607.0).round() as i32);
+        }
+        assert_eq!(decoded[0], -8_388_607);
+        assert_eq!(decoded[decoded.len() - 1], 8_388_607);
+    }
+
+    #[test]
+    fn encode_wav_float32_keeps_samples() {
+        let mut reader = decode(encode_wav(&ramp(), 16_000, WavBitDepth::Float32).unwrap());
+        assert_eq!(reader.spec().bits_per_sample, 32);
+        assert_eq!(reader.spec().sample_format, hound::SampleFormat::Float);
+        let decoded: Vec<f32> = reader.samples::<f32>().map(Result::unwrap).collect();
+        let expected: Vec<f32> = ramp().into_iter().map(|sample| sample.clamp(-1.0, 1.0)).collect();
+        assert_eq!(decoded, expected);
+    }
+}
@@ -0,0 +1,89 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::config;
+use crate::idle;
+use crate::tray::TrayState;
+use crate::window;
+
+/// Pending auto-hide after a result is shown. Bumping `generation` invalidates
+/// any timer that is already sleeping.
+#[derive(Default)]
+pub struct AutoHideState {
+    result_pending: AtomicBool,
+    generation: AtomicU64,
+}
+
+impl AutoHideState {
+    fn disarm(&self) -> u64 {
+        self.generation.fetch_add(1, Ordering::SeqCst) + 1
+    }
+}
+
+/// Start the hide timer if a result is waiting and auto-hide is enabled
+fn arm(app: &AppHandle) {
+    let delay_ms = config::get(app).auto_hide_after_ms;
+    if delay_ms == 0 {
+        return;
+    }
+
+    let state = app.state::<AutoHideState>();
+    if !state.result_pending.load(Ordering::SeqCst) {
+        return;
+    }
+    let generation = state.disarm();
+
+    let app = app.clone();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(delay_ms));
+
+        let state = app.state::<AutoHideState>();
+        if state.generation.load(Ordering::SeqCst) != generation || !state.result_pending.load(Ordering::SeqCst) {
+            return; // Timer was cancelled or superseded
+        }
+        // Never hide while recording or a request is in flight
+        if *app.state::<Mutex<TrayState>>().lock().unwrap() != TrayState::Idle || window::is_main_focused(&app) {
+            return;
+        }
+        let _ = window::hide_main(&app);
+    });
+}
+
+/// Drop any pending result so nothing hides until the next one is delivered
+pub fn clear(app: &AppHandle) {
+    let state = app.state::<AutoHideState>();
+    state.result_pending.store(false, Ordering::SeqCst);
+    state.disarm();
+}
+
+/// Focus cancels the timer; losing focus with a result on screen re-arms it
+pub fn on_focus_changed(app: &AppHandle, focused: bool) {
+    if focused {
+        app.state::<AutoHideState>().disarm();
+    } else {
+        arm(app);
+    }
+}
+
+/// Frontend reports that a result is ready. A window kept hidden while
+/// recording in the background is shown now.
+#[tauri::command]
+pub fn result_delivered(app: AppHandle) {
+    if !window::is_main_visible(&app) {
+        window::show_main(&app);
+    }
+    app.state::<AutoHideState>().result_pending.store(true, Ordering::SeqCst);
+    if !window::is_main_focused(&app) {
+        arm(&app);
+    }
+}
+
+/// Frontend reports user interaction (keypress, click) to cancel a pending hide
+#[tauri::command]
+pub fn user_activity(app: AppHandle) {
+    app.state::<AutoHideState>().disarm();
+    idle::touch(&app);
+}
@@ -0,0 +1,169 @@
+use futures_util::stream::{self, StreamExt};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use tokio_util::sync::CancellationToken;
+
+use crate::cancel::CancelState;
+use crate::config::{self, AppConfig};
+use crate::error::AppError;
+use crate::events::{DroppedFiles, EmitEvent};
+use crate::http::HttpState;
+use crate::tray::{self, TrayState};
+use crate::whisper;
+
+#[derive(Debug, Clone, Serialize)]
+struct BatchProgress {
+    index: usize,
+    total: usize,
+    file: String,
+    // One of: started, done, skipped, failed, cancelled
+    status: &'static str,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct BatchSummary {
+    pub transcribed: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub cancelled: bool,
+}
+
+fn is_supported_audio(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| whisper::SUPPORTED_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Supported audio files directly inside `dir`, in name order
+fn list_audio_files(dir: &Path) -> Result<Vec<PathBuf>, AppError> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && is_supported_audio(path))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Transcribe every audio file in a folder, writing `<name>.txt` next to each
+#[tauri::command]
+pub async fn transcribe_batch(app: AppHandle, dir: String, overwrite: Option<bool>) -> Result<BatchSummary, AppError> {
+    let files = list_audio_files(Path::new(&dir))?;
+    Ok(transcribe_files(&app, &files, overwrite.unwrap_or(false)).await)
+}
+
+/// Files dropped onto the window: audio is queued through the batch path
+/// (`file-dropped`, then `batch-progress`), anything else is reported as
+/// `unsupported-drop`
+pub fn on_drop(app: &AppHandle, paths: Vec<PathBuf>) {
+    let (audio, other): (Vec<PathBuf>, Vec<PathBuf>) =
+        paths.into_iter().partition(|path| path.is_file() && is_supported_audio(path));
+    let names = |paths: &[PathBuf]| paths.iter().map(|path| path.to_string_lossy().to_string()).collect::<Vec<_>>();
+    if !other.is_empty() {
+        let _ = app.emit_event("unsupported-drop", DroppedFiles { files: names(&other) });
+    }
+    if audio.is_empty() {
+        return;
+    }
+
+    let _ = app.emit_event("file-dropped", DroppedFiles { files: names(&audio) });
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        // Dropping a file is an explicit request, so an older transcript is replaced
+        let summary = transcribe_files(&app, &audio, true).await;
+        tracing::info!("Dropped files: {} transcribed, {} failed", summary.transcribed, summary.failed);
+    });
+}
+
+/// How one file of a batch ended
+enum Outcome {
+    Transcribed,
+    Skipped,
+    Failed,
+    Cancelled,
+}
+
+async fn transcribe_file(
+    app: &AppHandle,
+    config: &AppConfig,
+    token: &CancellationToken,
+    path: &Path,
+    (index, total): (usize, usize),
+    overwrite: bool,
+) -> Outcome {
+    let file = path.to_string_lossy().to_string();
+    let emit = |status| {
+        let _ = app.emit_event("batch-progress", BatchProgress { index, total, file: file.clone(), status });
+    };
+
+    if token.is_cancelled() {
+        emit("cancelled");
+        return Outcome::Cancelled;
+    }
+
+    let transcript_path = path.with_extension("txt");
+    if transcript_path.exists() && !overwrite {
+        emit("skipped");
+        return Outcome::Skipped;
+    }
+
+    emit("started");
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let result = match tokio::fs::read(path).await {
+        Ok(audio) => whisper::transcribe(&app.state::<HttpState>(), config, audio, &file_name, token).await,
+        Err(e) => Err(e.into()),
+    };
+
+    match result {
+        Ok(text) => match tokio::fs::write(&transcript_path, text).await {
+            Ok(()) => {
+                emit("done");
+                Outcome::Transcribed
+            }
+            Err(e) => {
+                tracing::error!("Failed to write {}: {e}", transcript_path.display());
+                emit("failed");
+                Outcome::Failed
+            }
+        },
+        Err(AppError::Cancelled) => {
+            emit("cancelled");
+            Outcome::Cancelled
+        }
+        Err(e) => {
+            tracing::error!("Failed to transcribe {file}: {e}");
+            emit("failed");
+            Outcome::Failed
+        }
+    }
+}
+
+/// Transcribe `files`, up to `batch_concurrency` at a time. `batch-progress`
+/// events carry each file's index, so they can be told apart when they
+/// interleave.
+async fn transcribe_files(app: &AppHandle, files: &[PathBuf], overwrite: bool) -> BatchSummary {
+    let config = config::get(app);
+    let total = files.len();
+
+    // One token for the whole batch so a cancel stops the remaining files too
+    let token = app.state::<CancelState>().token();
+    let mut summary = BatchSummary::default();
+
+    tray::set_state(app, TrayState::Processing);
+
+    // Futures do nothing until polled, so only `batch_concurrency` run at a time
+    let files: Vec<_> =
+        files.iter().enumerate().map(|(index, path)| transcribe_file(app, &config, &token, path, (index, total), overwrite)).collect();
+    let mut outcomes = stream::iter(files).buffer_unordered(config.batch_concurrency.max(1));
+    while let Some(outcome) = outcomes.next().await {
+        match outcome {
+            Outcome::Transcribed => summary.transcribed += 1,
+            Outcome::Skipped => summary.skipped += 1,
+            Outcome::Failed => summary.failed += 1,
+            Outcome::Cancelled => summary.cancelled = true,
+        }
+    }
+
+    tray::set_state(app, TrayState::Idle);
+    summary
+}
@@ -0,0 +1,124 @@
+use serde::Serialize;
+use std::time::Instant;
+use tauri::{AppHandle, Manager};
+
+use crate::audio::{self, WavBitDepth};
+use crate::cancel::CancelState;
+use crate::config;
+use crate::error::AppError;
+use crate::events::EmitEvent;
+use crate::http::HttpState;
+use crate::llm;
+use crate::whisper;
+
+// Built-in sample: two seconds of a 440 Hz tone at 16 kHz
+const SAMPLE_RATE: u32 = 16_000;
+const SAMPLE_SECONDS: u32 = 2;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelBenchmark {
+    pub model: String,
+    pub latency_ms: Option<u64>,
+    pub error: Option<AppError>,
+}
+
+/// Outcome of a settings "test" button; errors only carry the safe summary
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestResult {
+    pub ok: bool,
+    pub latency_ms: u64,
+    pub model: Option<String>,
+    pub error: Option<String>,
+}
+
+impl TestResult {
+    fn from_result<T>(result: Result<T, AppError>, started: Instant, model: Option<String>) -> Self {
+        let latency_ms = started.elapsed().as_millis() as u64;
+        match result {
+            Ok(_) => TestResult { ok: true, latency_ms, model, error: None },
+            Err(e) => TestResult { ok: false, latency_ms, model: None, error: Some(e.summary().to_string()) },
+        }
+    }
+}
+
+fn builtin_sample() -> Result<Vec<u8>, AppError> {
+    let samples: Vec<f32> = (0..SAMPLE_RATE * SAMPLE_SECONDS)
+        .map(|i| (i as f32 * 440.0 * std::f32::consts::TAU / SAMPLE_RATE as f32).sin() * 0.3)
+        .collect();
+    audio::encode_wav(&samples, SAMPLE_RATE, WavBitDepth::Pcm16)
+}
+
+/// Run the same audio through each model and report latency, fastest first.
+/// Failed models sort last with their error.
+#[tauri::command]
+pub async fn benchmark_models(app: AppHandle, models: Vec<String>, sample: Option<Vec<u8>>) -> Result<Vec<ModelBenchmark>, AppError> {
+    let base_config = config::get(&app);
+    let audio = match sample {
+        Some(sample) => sample,
+        None => builtin_sample()?,
+    };
+    let http = app.state::<HttpState>();
+    let token = app.state::<CancelState>().token();
+
+    let mut report = Vec::with_capacity(models.len());
+    for model in models {
+        let config = config::AppConfig { whisper_model: model.clone(), ..base_config.clone() };
+        let started = Instant::now();
+        let result = whisper::transcribe(&http, &config, audio.clone(), "sample.wav", &token).await;
+
+        report.push(match result {
+            Ok(_) => ModelBenchmark { model, latency_ms: Some(started.elapsed().as_millis() as u64), error: None },
+            Err(AppError::Cancelled) => return Err(AppError::Cancelled),
+            Err(e) => ModelBenchmark { model, latency_ms: None, error: Some(e) },
+        });
+    }
+
+    report.sort_by_key(|entry| entry.latency_ms.unwrap_or(u64::MAX));
+    Ok(report)
+}
+
+/// Send a trivial prompt to the configured LLM to check credentials and
+/// endpoint. Never answered from the response cache.
+#[tauri::command]
+pub async fn test_llm(app: AppHandle) -> TestResult {
+    let config = config::get(&app);
+    let token = app.state::<CancelState>().token();
+
+    let started = Instant::now();
+    let result = llm::query_uncached(&app, &config, "ping", &token).await;
+    let model = result.as_ref().ok().map(|answer| answer.model.clone());
+    TestResult::from_result(result, started, model)
+}
+
+/// Open the connection to the LLM provider ahead of the first query so DNS and
+/// TLS are already done. Every current provider is remote and keeps its models
+/// loaded, so no prompt is sent. Emits `llm-warm` with the outcome.
+#[tauri::command]
+pub async fn warm_llm(app: AppHandle) -> TestResult {
+    let config = config::get(&app);
+    let started = Instant::now();
+    let result = match llm::Provider::from_name(&config.llm_provider) {
+        // Any response means the connection is up and pooled
+        Ok(provider) => app.state::<HttpState>().client().head(provider.origin()).send().await.map_err(AppError::from),
+        Err(e) => Err(e),
+    };
+    let result = TestResult::from_result(result, started, None);
+    let _ = app.emit_event("llm-warm", &result);
+    result
+}
+
+/// Transcribe the built-in sample to check the Whisper settings
+#[tauri::command]
+pub async fn test_whisper(app: AppHandle) -> TestResult {
+    let config = config::get(&app);
+    let token = app.state::<CancelState>().token();
+
+    let started = Instant::now();
+    let result = match builtin_sample() {
+        Ok(audio) => whisper::transcribe(&app.state::<HttpState>(), &config, audio, "sample.wav", &token).await,
+        Err(e) => Err(e),
+    };
+    TestResult::from_result(result, started, Some(config.whisper_model.clone()))
+}
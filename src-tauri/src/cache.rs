@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+
+use crate::config::{self, AppConfig};
+use crate::events::EmitEvent;
+use crate::llm::LlmAnswer;
+
+/// A cached answer in `cache/<key>.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheEntry {
+    stored_at_secs: u64,
+    text: String,
+    provider: String,
+    model: String,
+}
+
+/// Payload of `cache-hit`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheHit<'a> {
+    provider: &'a str,
+    model: &'a str,
+    age_secs: u64,
+}
+
+fn cache_dir() -> Result<PathBuf, String> {
+    Ok(config::config_dir()?.join("cache"))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Whether answers for `config` may be cached. An unset temperature means the
+/// provider default, which is usually above zero, so it only counts as
+/// deterministic when set to 0 (or `cache_force` is on).
+fn enabled(config: &AppConfig) -> bool {
+    config.cache_responses && (config.cache_force || matches!(config.llm_temperature, Some(t) if t <= 0.0))
+}
+
+/// Hex SHA-256 over everything that shapes the request
+fn key(config: &AppConfig, prompt: &str) -> String {
+    let input = json!({
+        "provider": config.llm_provider,
+        "model": config.llm_model,
+        "temperature": config.llm_temperature,
+        "systemPrompt": config.system_prompt,
+        "extraParams": config.llm_extra_params,
+        "prompt": prompt,
+    });
+    Sha256::digest(input.to_string().as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// The cached answer to `prompt`, if caching applies and one is still fresh.
+/// Emits `cache-hit` when it serves one.
+pub fn lookup(app: &AppHandle, config: &AppConfig, prompt: &str) -> Option<LlmAnswer> {
+    if !enabled(config) {
+        return None;
+    }
+    let path = cache_dir().ok()?.join(format!("{}.json", key(config, prompt)));
+    let entry: CacheEntry = serde_json::from_str(&std::fs::read_to_string(&path).ok()?).ok()?;
+    let age_secs = now_secs().saturating_sub(entry.stored_at_secs);
+    if age_secs > config.cache_ttl_secs {
+        let _ = std::fs::remove_file(&path);
+        return None;
+    }
+
+    let _ = app.emit_event("cache-hit", CacheHit { provider: &entry.provider, model: &entry.model, age_secs });
+    Some(LlmAnswer { text: entry.text, provider: entry.provider, model: entry.model, fallback_used: false })
+}
+
+/// Remember `answer` for `prompt` when caching applies. Failures are only logged.
+pub fn store(config: &AppConfig, prompt: &str, answer: &LlmAnswer) {
+    if !enabled(config) {
+        return;
+    }
+    let entry = CacheEntry {
+        stored_at_secs: now_secs(),
+        text: answer.text.clone(),
+        provider: answer.provider.clone(),
+        model: answer.model.clone(),
+    };
+    let result = cache_dir().and_then(|dir| {
+        let content = serde_json::to_string(&entry).map_err(|e| e.to_string())?;
+        std::fs::write(dir.join(format!("{}.json", key(config, prompt))), content).map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        tracing::warn!("Failed to cache LLM response: {e}");
+    }
+}
+
+/// Delete every cached response, returning how many there were
+#[tauri::command]
+pub fn clear_response_cache() -> Result<usize, String> {
+    let dir = cache_dir()?;
+    if !dir.exists() {
+        return Ok(0);
+    }
+    let mut removed = 0;
+    for entry in std::fs::read_dir(&dir).map_err(|e| e.to_string())? {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            std::fs::remove_file(&path).map_err(|e| format!("Could not remove {}: {e}", path.display()))?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
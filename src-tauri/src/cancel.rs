@@ -0,0 +1,52 @@
+use std::future::Future;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tokio_util::sync::CancellationToken;
+
+use crate::error::AppError;
+use crate::events::{EmitEvent, Empty};
+use crate::tray::{self, TrayState};
+
+/// Shared cancellation token handed to every backend network operation.
+/// Cancelling swaps in a fresh token so later requests start clean.
+#[derive(Default)]
+pub struct CancelState {
+    token: Mutex<CancellationToken>,
+}
+
+impl CancelState {
+    /// Token for a new operation, triggered by the next `cancel_all`
+    pub fn token(&self) -> CancellationToken {
+        self.token.lock().unwrap().clone()
+    }
+
+    fn cancel_and_reset(&self) {
+        let mut token = self.token.lock().unwrap();
+        token.cancel();
+        *token = CancellationToken::new();
+    }
+}
+
+/// Run `fut` until it finishes or `token` is cancelled; dropping the future
+/// aborts any request it has in flight
+pub async fn run<T, F>(token: &CancellationToken, fut: F) -> Result<T, AppError>
+where
+    F: Future<Output = Result<T, AppError>>,
+{
+    token.run_until_cancelled(fut).await.unwrap_or(Err(AppError::Cancelled))
+}
+
+/// Cancel everything in flight and return the tray to Idle
+#[tauri::command]
+pub fn cancel_all(app: AppHandle) {
+    app.state::<CancelState>().cancel_and_reset();
+    tray::set_state(&app, TrayState::Idle);
+    let _ = app.emit_event("requests-cancelled", Empty {});
+}
+
+/// Cancel the requests in flight but leave the tray alone, for when a native
+/// recording carries on
+pub fn cancel_requests(app: &AppHandle) {
+    app.state::<CancelState>().cancel_and_reset();
+    let _ = app.emit_event("requests-cancelled", Empty {});
+}
@@ -0,0 +1,49 @@
+use cpal::traits::HostTrait;
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::config;
+use crate::headless;
+use crate::llm::Provider;
+
+/// What this build can do on this machine, so the UI can hide controls for
+/// anything missing instead of offering a button that fails
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Capabilities {
+    // API keys are stored in the config file; no OS keychain integration yet
+    pub keychain_available: bool,
+    // An input device cpal can capture from is present
+    pub native_audio: bool,
+    pub autostart_supported: bool,
+    pub tray_supported: bool,
+    pub realtime_supported: bool,
+    // The configured provider and model accept image attachments
+    pub multimodal: bool,
+    pub opus_upload: bool,
+    // Full-screen detection has a real implementation (Windows, X11)
+    pub fullscreen_detection: bool,
+    // The OS asks before apps can use the microphone (macOS)
+    pub mic_permission_prompt: bool,
+    pub headless: bool,
+}
+
+#[tauri::command]
+pub fn get_capabilities(app: AppHandle) -> Capabilities {
+    let config = config::get(&app);
+    let multimodal = Provider::from_name(&config.llm_provider).is_ok_and(|provider| {
+        provider.supports_images(config.llm_model.as_deref().unwrap_or(provider.default_model()))
+    });
+    Capabilities {
+        keychain_available: false,
+        native_audio: cpal::default_host().default_input_device().is_some(),
+        autostart_supported: false,
+        tray_supported: !headless::active(),
+        realtime_supported: true,
+        multimodal,
+        opus_upload: cfg!(feature = "opus"),
+        fullscreen_detection: !cfg!(target_os = "macos"),
+        mic_permission_prompt: cfg!(target_os = "macos"),
+        headless: headless::active(),
+    }
+}
@@ -0,0 +1,201 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::events::EmitEvent;
+
+#[derive(Debug, Default)]
+struct Breaker {
+    // Consecutive failures, counted from the first one in the current window
+    failures: u32,
+    window_start: Option<Instant>,
+    open_until: Option<Instant>,
+    // Cooldown the circuit last opened for
+    cooldown: Duration,
+    // Set while the one probe allowed after the cooldown is out. Expires after
+    // another cooldown in case the probe never reports back (e.g. cancelled).
+    probe_until: Option<Instant>,
+}
+
+impl Breaker {
+    /// Whether a request may go through at `now`, or how long until one may.
+    /// Once the cooldown is over the first caller gets the probe slot.
+    fn allow(&mut self, now: Instant) -> Result<(), Duration> {
+        let Some(until) = self.open_until else {
+            return Ok(());
+        };
+        if until > now {
+            return Err(until - now);
+        }
+        if let Some(probe_until) = self.probe_until.filter(|probe_until| *probe_until > now) {
+            return Err(probe_until - now);
+        }
+        self.probe_until = Some(now + self.cooldown);
+        Ok(())
+    }
+
+    /// Count a failure at `now`; true when that opens the circuit
+    fn fail(&mut self, now: Instant, threshold: u32, window: Duration, cooldown: Duration) -> bool {
+        let probing = self.open_until.is_some();
+        if self.window_start.is_none_or(|start| now.duration_since(start) > window) {
+            self.failures = 0;
+            self.window_start = Some(now);
+        }
+        self.failures += 1;
+
+        if !probing && self.failures < threshold {
+            return false;
+        }
+        self.open_until = Some(now + cooldown);
+        self.cooldown = cooldown;
+        self.probe_until = None;
+        true
+    }
+}
+
+/// Per-provider circuit breakers, so a provider that keeps failing is skipped
+/// for a while instead of making every request wait it out
+#[derive(Default)]
+pub struct CircuitState {
+    breakers: Mutex<HashMap<String, Breaker>>,
+}
+
+/// `provider-circuit-open` and `provider-circuit-closed`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CircuitChange<'a> {
+    provider: &'a str,
+    // Seconds until a probe is allowed again; 0 once closed
+    retry_in_secs: u64,
+}
+
+/// Fail fast while `provider`'s circuit is open. Once the cooldown is over a
+/// single request goes through as a probe; the rest keep failing fast until it
+/// reports back through `record_success` or `record_failure`.
+pub fn check(app: &AppHandle, provider: &str) -> Result<(), AppError> {
+    let state = app.state::<CircuitState>();
+    let mut breakers = state.breakers.lock().unwrap();
+    let Some(breaker) = breakers.get_mut(provider) else {
+        return Ok(());
+    };
+    breaker.allow(Instant::now()).map_err(|wait| {
+        AppError::Network(format!("{provider} is failing repeatedly; retrying in {}s", wait.as_secs().max(1)))
+    })
+}
+
+/// Count a failed request, opening the circuit once `circuit_failure_threshold`
+/// failures land within `circuit_window_secs`. A failed probe reopens it straight away.
+pub fn record_failure(app: &AppHandle, config: &AppConfig, provider: &str) {
+    if config.circuit_failure_threshold == 0 {
+        return;
+    }
+    let cooldown = Duration::from_secs(config.circuit_cooldown_secs);
+    let window = Duration::from_secs(config.circuit_window_secs);
+
+    let state = app.state::<CircuitState>();
+    let mut breakers = state.breakers.lock().unwrap();
+    let breaker = breakers.entry(provider.to_string()).or_default();
+    if breaker.fail(Instant::now(), config.circuit_failure_threshold, window, cooldown) {
+        drop(breakers);
+        tracing::warn!("Circuit open for {provider} for {}s", cooldown.as_secs());
+        let _ = app.emit_event("provider-circuit-open", CircuitChange { provider, retry_in_secs: cooldown.as_secs() });
+    }
+}
+
+/// A request succeeded: forget past failures and close the circuit if it was open
+pub fn record_success(app: &AppHandle, provider: &str) {
+    let state = app.state::<CircuitState>();
+    let was_open = state.breakers.lock().unwrap().remove(provider).is_some_and(|breaker| breaker.open_until.is_some());
+    if was_open {
+        let _ = app.emit_event("provider-circuit-closed", CircuitChange { provider, retry_in_secs: 0 });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{emitted, SCHEMA_VERSION};
+    use serde_json::json;
+
+    const WINDOW: Duration = Duration::from_secs(60);
+    const COOLDOWN: Duration = Duration::from_secs(30);
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    /// A breaker opened by three failures at `start`
+    fn opened(start: Instant) -> Breaker {
+        let mut breaker = Breaker::default();
+        for _ in 0..3 {
+            breaker.fail(start, 3, WINDOW, COOLDOWN);
+        }
+        breaker
+    }
+
+    #[test]
+    fn opens_once_the_threshold_is_reached_within_the_window() {
+        let start = Instant::now();
+        let mut breaker = Breaker::default();
+        assert!(!breaker.fail(start, 3, WINDOW, COOLDOWN));
+        assert!(!breaker.fail(start + secs(10), 3, WINDOW, COOLDOWN));
+        assert!(breaker.allow(start + secs(10)).is_ok());
+        assert!(breaker.fail(start + secs(20), 3, WINDOW, COOLDOWN));
+        assert_eq!(breaker.allow(start + secs(20)), Err(COOLDOWN));
+    }
+
+    #[test]
+    fn failures_outside_the_window_start_over() {
+        let start = Instant::now();
+        let mut breaker = Breaker::default();
+        assert!(!breaker.fail(start, 3, WINDOW, COOLDOWN));
+        assert!(!breaker.fail(start + secs(30), 3, WINDOW, COOLDOWN));
+        // Past the window: this one counts as the first of a new run
+        assert!(!breaker.fail(start + secs(61), 3, WINDOW, COOLDOWN));
+        assert!(!breaker.fail(start + secs(62), 3, WINDOW, COOLDOWN));
+        assert!(breaker.fail(start + secs(63), 3, WINDOW, COOLDOWN));
+    }
+
+    #[test]
+    fn lets_a_single_probe_through_after_the_cooldown() {
+        let start = Instant::now();
+        let mut breaker = opened(start);
+        assert_eq!(breaker.allow(start + secs(29)), Err(secs(1)));
+        assert!(breaker.allow(start + secs(30)).is_ok());
+        // Concurrent requests wait for the probe
+        assert!(breaker.allow(start + secs(31)).is_err());
+        assert!(breaker.allow(start + secs(45)).is_err());
+    }
+
+    #[test]
+    fn a_failed_probe_reopens_the_circuit_at_once() {
+        let start = Instant::now();
+        let mut breaker = opened(start);
+        assert!(breaker.allow(start + secs(30)).is_ok());
+        assert!(breaker.fail(start + secs(31), 3, WINDOW, COOLDOWN));
+        assert_eq!(breaker.allow(start + secs(31)), Err(COOLDOWN));
+        // And the next cooldown allows a new probe
+        assert!(breaker.allow(start + secs(61)).is_ok());
+    }
+
+    #[test]
+    fn a_probe_that_never_reports_back_frees_its_slot() {
+        let start = Instant::now();
+        let mut breaker = opened(start);
+        assert!(breaker.allow(start + secs(30)).is_ok());
+        assert!(breaker.allow(start + secs(59)).is_err());
+        assert!(breaker.allow(start + secs(60)).is_ok());
+    }
+
+    #[test]
+    fn circuit_change_serializes_camel_case() {
+        assert_eq!(
+            emitted(CircuitChange { provider: "openai", retry_in_secs: 30 }),
+            json!({ "schemaVersion": SCHEMA_VERSION, "provider": "openai", "retryInSecs": 30 })
+        );
+    }
+}
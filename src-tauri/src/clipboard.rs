@@ -0,0 +1,49 @@
+use tauri::{AppHandle, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::cancel::CancelState;
+use crate::config;
+use crate::error::{self, AppError};
+use crate::events::{EmitEvent, Empty, Text};
+use crate::llm;
+use crate::notify;
+use crate::repeat::{self, LastAction};
+use crate::template;
+use crate::tray::{self, TrayState};
+
+/// Run the clipboard text through the active template and LLM.
+/// Returns `None` (and emits `clipboard-empty`) when there's no text to use.
+#[tauri::command]
+pub async fn query_from_clipboard(app: AppHandle) -> Result<Option<String>, AppError> {
+    let text = app.clipboard().read_text().unwrap_or_default();
+    if text.trim().is_empty() {
+        let _ = app.emit_event("clipboard-empty", Empty {});
+        return Ok(None);
+    }
+
+    repeat::record(&app, LastAction::Clipboard);
+    let config = config::get(&app);
+    let prompt = template::apply_template(template::active(&config), text.trim());
+    let token = app.state::<CancelState>().token();
+
+    tray::set_state(&app, TrayState::Processing);
+    let result = llm::query(&app, &config, &prompt, &token).await;
+    error::record_outcome(&app, "llm", &result);
+    tray::set_state(&app, TrayState::Idle);
+
+    result.map(Some)
+}
+
+/// Shortcut entry point: results and errors are delivered as events
+pub async fn process_clipboard(app: AppHandle) {
+    match query_from_clipboard(app.clone()).await {
+        Ok(Some(answer)) => {
+            let _ = app.emit_event("clipboard-result", Text { text: &answer });
+        }
+        Ok(None) | Err(AppError::Cancelled) => {}
+        Err(e) => {
+            notify::error(&app, &e);
+            let _ = app.emit_event("clipboard-error", e);
+        }
+    }
+}
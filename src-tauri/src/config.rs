@@ -0,0 +1,1009 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::audio::{self, WavBitDepth};
+use crate::events::EmitEvent;
+use crate::handoff;
+use crate::headless;
+use crate::http;
+use crate::llm::{self, FallbackTarget};
+use crate::onboarding;
+use crate::pipeline::PipelineMode;
+use crate::preset::{self, RecordingPreset};
+use crate::profile::{self, AppProfile, ProfileMode};
+use crate::readiness;
+use crate::replace::{self, Replacement};
+use crate::shortcut::{self, RecordingReentry};
+use crate::sink::ResultSink;
+use crate::template::PromptTemplate;
+use crate::tray::{self, TrayIconTheme};
+use crate::upload::UploadFormat;
+use crate::whisper::{self, WhisperFallback};
+
+/// Persisted settings (`config.json`). Keys the backend doesn't model are kept
+/// in `extra` so frontend-only preferences survive a round trip.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AppConfig {
+    // Sent on every provider request; empty = "ama-agent/<version> (<os>)"
+    pub user_agent: String,
+    // PEM file with extra root certificates, for gateways behind a private CA
+    pub ca_cert_path: String,
+    // Skip TLS certificate checks entirely. Only for lab setups: anyone on the
+    // network can then read and change provider traffic, API keys included.
+    pub danger_accept_invalid_certs: bool,
+    // Keep the last provider response body, secrets scrubbed, for get_last_raw_response
+    pub debug_capture_responses: bool,
+    pub whisper_url: String,
+    pub whisper_api_key: String,
+    pub whisper_model: String,
+    // JSON pointer to the transcript in the endpoint's response
+    pub whisper_response_path: String,
+    // Sampling temperature, 0.0-1.0; higher can help on noisy audio
+    pub whisper_temperature: f32,
+    // Context passed as Whisper's `prompt`. Best used for spelling hints such
+    // as names and jargon, not instructions; empty = not sent.
+    pub whisper_prompt: String,
+    // Tried when the transcription endpoint is unreachable or failing, e.g. a
+    // local whisper.cpp server for when the internet is down
+    pub whisper_fallback: Option<WhisperFallback>,
+    // Transcribe once more with this model when the transcript comes back
+    // empty; empty = no retry
+    pub empty_retry_model: String,
+    // Ask for `verbose_json`, which carries per-segment confidence and the
+    // detected language (whisper-1 and most self-hosted servers; newer OpenAI
+    // models reject it)
+    pub whisper_verbose_json: bool,
+    // Emit `low-confidence-transcript` below this average segment confidence
+    // (0.0-1.0; 0 = never), and optionally don't send such transcripts to the LLM
+    pub min_transcript_confidence: f32,
+    pub skip_llm_on_low_confidence: bool,
+    // A transcript this similar (0.0-1.0 word overlap; 1.0 = same words) to
+    // the previous recording's within `duplicate_window_secs` is not processed
+    // again; `duplicate-transcript` lets the UI offer to. 0 secs = off.
+    pub duplicate_window_secs: u64,
+    pub duplicate_similarity: f32,
+    // WebSocket endpoint for realtime transcription; empty = batch only
+    pub realtime_url: String,
+    // Continuous dictation also types words before the endpoint finalizes
+    // them, backspacing over any it later revises
+    pub dictation_type_partials: bool,
+    // ISO-639-1 code sent to Whisper; None or "auto" lets it detect
+    pub transcription_language: Option<String>,
+    // Mode, template and model for recordings a shortcut starts while a
+    // given app is in front; the first matching rule wins
+    pub app_profiles: Vec<AppProfile>,
+    // What recordings turn into when no app profile applies: an LLM answer
+    // (agent) or the transcript itself (transcribe)
+    pub recording_mode: ProfileMode,
+    // Named bundles of capture settings, mode, template and model
+    pub presets: Vec<RecordingPreset>,
+    // Find/replace rules applied to every transcript
+    pub replacements: Vec<Replacement>,
+    pub llm_provider: String,
+    pub llm_api_key: String,
+    // Provider default when unset
+    pub llm_model: Option<String>,
+    // Provider default when unset
+    pub llm_temperature: Option<f32>,
+    // Save the model passed to query_llm as the new default once it succeeds
+    pub remember_last_model: bool,
+    // Tried in order when the primary provider is unreachable or failing
+    pub llm_fallback: Vec<FallbackTarget>,
+    // Open the LLM connection at startup so the first query is faster
+    pub prewarm_llm: bool,
+    // Whether the LLM may start before realtime transcription has finished
+    pub pipeline_mode: PipelineMode,
+    // Skip a provider for `circuit_cooldown_secs` after this many outage-type
+    // failures within `circuit_window_secs`; 0 = never
+    pub circuit_failure_threshold: u32,
+    pub circuit_window_secs: u64,
+    pub circuit_cooldown_secs: u64,
+    // Merged into every LLM request body (e.g. `top_p`, `seed`); fields the app
+    // sets itself take precedence
+    pub llm_extra_params: serde_json::Value,
+    // Attribution tags (e.g. team, user) sent with every LLM request as
+    // OpenAI `metadata` and an `X-Request-Tags` header for gateways
+    pub request_tags: BTreeMap<String, String>,
+    // Check that a provider's host is reachable before each request and fail
+    // at once with an `offline` event if not; local endpoints are not checked
+    pub fast_fail_offline: bool,
+    // Files transcribe_batch works on at once (1-8). Requests still share the
+    // app-wide limit of two in flight; higher values mostly help with many
+    // short files, and can run into the provider's rate limits.
+    pub batch_concurrency: usize,
+    // Cap on a whole provider request, response body included; the per-stage
+    // settings override it when set (long audio can need more than an LLM call)
+    pub http_timeout_secs: u64,
+    pub whisper_timeout_secs: Option<u64>,
+    pub llm_timeout_secs: Option<u64>,
+    // Give up on a streamed answer after this long without a chunk; 0 = wait forever
+    pub stream_idle_timeout_secs: u64,
+    // Sent as the system message when non-empty
+    pub system_prompt: String,
+    // Serve repeated identical LLM queries from disk. Only applies at
+    // temperature 0 unless `cache_force` is set.
+    pub cache_responses: bool,
+    pub cache_force: bool,
+    // How long a cached response stays valid
+    pub cache_ttl_secs: u64,
+    pub openai_api_key: String,
+    pub gemini_api_key: String,
+    pub anthropic_api_key: String,
+    pub templates: Vec<PromptTemplate>,
+    pub active_template: Option<String>,
+    // Entries `compact_history` keeps, newest first; 0 = all
+    pub history_max_entries: usize,
+    // Hide to tray this long after a result is shown (0 = never)
+    pub auto_hide_after_ms: u64,
+    // Input device name; None uses the system default
+    pub input_device: Option<String>,
+    // Gain applied to captured audio before encoding, in dB
+    pub input_gain_db: f32,
+    // Scale each recording so its peak is just below full scale
+    pub normalize_audio: bool,
+    // Drop leading/trailing silence before encoding
+    pub trim_silence: bool,
+    // Judge silence against the noise floor of each recording's first
+    // milliseconds plus `noise_margin_db`, instead of a fixed level; 0 = fixed
+    pub noise_calibration_ms: u32,
+    pub noise_margin_db: f32,
+    // Emit `audio-quality-warning` after recordings that are too quiet (peak
+    // below `quiet_peak`), clip (more than `clipping_ratio` of samples at full
+    // scale) or are mostly silence (less than `min_speech_ratio` has sound)
+    pub audio_quality_warnings: bool,
+    pub quiet_peak: f32,
+    pub clipping_ratio: f32,
+    pub min_speech_ratio: f32,
+    // Native recordings stopped sooner than this are discarded as accidental
+    // presses, with `recording-too-short`, instead of being transcribed; 0 = off
+    pub min_record_ms: u64,
+    // Stop capturing while the window is unfocused instead of recording through it
+    pub pause_recording_on_blur: bool,
+    // Recordings are re-encoded to Ogg/Opus before upload when set to opus
+    pub upload_format: UploadFormat,
+    // Sample format of WAV recordings: "16" (dithered), "24" or "32f"
+    pub wav_bit_depth: WavBitDepth,
+    // Upload native recordings while they are captured, so long dictation
+    // doesn't wait for one big upload at the end. Endpoints that refuse a
+    // chunked upload get the buffered recording instead.
+    pub stream_upload: bool,
+    pub always_on_top: bool,
+    // Without a tray the app behaves like a normal window: closing quits, hiding minimizes
+    pub show_tray: bool,
+    // Taskbar theme the tray icon is drawn for: auto, light or dark
+    pub tray_icon_theme: TrayIconTheme,
+    // No window or tray, only shortcuts; results go to the clipboard and file
+    // sinks. Also enabled with `--headless`. Takes effect on restart.
+    pub headless: bool,
+    // Quit after this long without shortcuts, recordings or requests; 0 = never
+    pub idle_quit_minutes: u64,
+    // Shortcut opens the window next to the mouse pointer
+    pub summon_at_cursor: bool,
+    // Monitor name or index (from `list_monitors`) the window is always centered
+    // on when shown; takes precedence over `summon_at_cursor`
+    pub preferred_monitor: Option<String>,
+    // Ignore shortcuts while a full-screen app (game, presentation) is in front
+    pub suppress_in_fullscreen: bool,
+    // What the toggle shortcut does while already recording: stop, cancel or ignore
+    pub recording_reentry: RecordingReentry,
+    // Shortcut starts recording without showing the window until a result is ready
+    pub record_without_showing: bool,
+    // Refuse to hide the window mid-recording instead of leaving it to the tray
+    pub refuse_hide_while_recording: bool,
+    // Keep emitting `shortcut-action` and `window-shown` next to `shortcut-fired`
+    // until the UI has migrated; goes away next release
+    pub legacy_shortcut_events: bool,
+    // Pulse the tray icon during transcription and LLM requests while the window is hidden
+    pub animate_tray_while_processing: bool,
+    // Show a system notification when a request fails while the window is hidden
+    pub notify_on_error: bool,
+    // Show a notification naming the new template when a shortcut switches it
+    pub notify_on_template_change: bool,
+    // Where transcribe_and_process delivers its answer
+    pub result_sink: BTreeSet<ResultSink>,
+    // File sink path, `{timestamp}` is substituted; empty = <config>/results/{timestamp}.txt
+    pub result_file_path: String,
+    // Pause between keystrokes for the type sink, for apps that drop fast input
+    pub typing_delay_ms: u64,
+    // Longer results are copied to the clipboard instead of typed
+    pub typing_max_chars: usize,
+    // Shell command each pipeline result is piped into (stdin) before delivery.
+    // Runs with the user's permissions; stderr and failures go to the activity
+    // log. With `post_hook_replaces_result`, its stdout becomes the result.
+    pub post_hook_command: String,
+    pub post_hook_replaces_result: bool,
+    pub post_hook_timeout_secs: u64,
+    // Handler name -> URL with a `{text}` placeholder (e.g. "obsidian://new?content={text}")
+    // or command line (`{text}` substituted, otherwise piped to stdin), for open_result_in
+    pub open_in_handlers: BTreeMap<String, String>,
+    pub open_in_default: String,
+    // Action name -> accelerator, e.g. "toggle" -> "ctrl+shift+space"
+    pub shortcuts: BTreeMap<String, String>,
+    // Presses of the same action closer together than this are ignored (key
+    // repeat, bouncing switches); per-action values override the default
+    pub shortcut_debounce_ms: u64,
+    pub shortcut_debounce: BTreeMap<String, u64>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            user_agent: String::new(),
+            ca_cert_path: String::new(),
+            danger_accept_invalid_certs: false,
+            debug_capture_responses: false,
+            whisper_url: "https://api.openai.com/v1/audio/transcriptions".into(),
+            whisper_api_key: String::new(),
+            whisper_model: "whisper-1".into(),
+            empty_retry_model: String::new(),
+            whisper_response_path: "/text".into(),
+            whisper_temperature: 0.0,
+            whisper_prompt: String::new(),
+            whisper_fallback: None,
+            whisper_verbose_json: false,
+            min_transcript_confidence: 0.0,
+            skip_llm_on_low_confidence: false,
+            duplicate_window_secs: 0,
+            duplicate_similarity: 0.9,
+            realtime_url: String::new(),
+            dictation_type_partials: false,
+            transcription_language: None,
+            app_profiles: Vec::new(),
+            recording_mode: ProfileMode::Agent,
+            presets: Vec::new(),
+            replacements: Vec::new(),
+            llm_provider: "openai".into(),
+            llm_api_key: String::new(),
+            llm_model: None,
+            llm_temperature: None,
+            remember_last_model: false,
+            llm_fallback: Vec::new(),
+            prewarm_llm: false,
+            pipeline_mode: PipelineMode::Sequential,
+            circuit_failure_threshold: 3,
+            circuit_window_secs: 60,
+            circuit_cooldown_secs: 30,
+            batch_concurrency: 1,
+            fast_fail_offline: false,
+            http_timeout_secs: 120,
+            whisper_timeout_secs: None,
+            llm_timeout_secs: None,
+            stream_idle_timeout_secs: 30,
+            llm_extra_params: serde_json::Value::Object(serde_json::Map::new()),
+            request_tags: BTreeMap::new(),
+            system_prompt: String::new(),
+            cache_responses: false,
+            cache_force: false,
+            cache_ttl_secs: 24 * 60 * 60,
+            openai_api_key: String::new(),
+            gemini_api_key: String::new(),
+            anthropic_api_key: String::new(),
+            templates: Vec::new(),
+            active_template: None,
+            history_max_entries: 0,
+            auto_hide_after_ms: 0,
+            input_device: None,
+            input_gain_db: 0.0,
+            normalize_audio: false,
+            trim_silence: false,
+            noise_calibration_ms: 0,
+            noise_margin_db: 10.0,
+            audio_quality_warnings: true,
+            min_record_ms: 300,
+            quiet_peak: 0.1,
+            clipping_ratio: 0.001,
+            min_speech_ratio: 0.1,
+            pause_recording_on_blur: false,
+            upload_format: UploadFormat::Wav,
+            wav_bit_depth: WavBitDepth::Pcm16,
+            stream_upload: false,
+            always_on_top: true,
+            show_tray: true,
+            tray_icon_theme: TrayIconTheme::Auto,
+            headless: false,
+            idle_quit_minutes: 0,
+            summon_at_cursor: false,
+            preferred_monitor: None,
+            suppress_in_fullscreen: false,
+            recording_reentry: RecordingReentry::Stop,
+            record_without_showing: false,
+            refuse_hide_while_recording: false,
+            legacy_shortcut_events: true,
+            animate_tray_while_processing: true,
+            notify_on_error: true,
+            notify_on_template_change: true,
+            result_sink: BTreeSet::from([ResultSink::Ui]),
+            result_file_path: String::new(),
+            typing_delay_ms: 5,
+            typing_max_chars: 2000,
+            post_hook_command: String::new(),
+            post_hook_replaces_result: false,
+            post_hook_timeout_secs: 10,
+            open_in_handlers: BTreeMap::new(),
+            open_in_default: String::new(),
+            shortcuts: BTreeMap::from([
+                (shortcut::ACTION_TOGGLE.to_string(), "ctrl+shift+space".to_string()),
+                (shortcut::ACTION_CANCEL.to_string(), "ctrl+shift+backspace".to_string()),
+            ]),
+            shortcut_debounce_ms: 300,
+            shortcut_debounce: BTreeMap::new(),
+            extra: serde_json::Map::new(),
+        }
+    }
+}
+
+// Env var equivalent of the `--config <path>` flag
+const CONFIG_PATH_ENV: &str = "AMA_CONFIG_PATH";
+
+/// What picked the config file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigFileSource {
+    // The usual location in the user's config directory
+    Default,
+    // `--config`
+    Flag,
+    // `AMA_CONFIG_PATH`
+    Env,
+}
+
+// Config file chosen by `--config` or `AMA_CONFIG_PATH`, resolved once at startup
+static PATH_OVERRIDE: OnceLock<Option<(PathBuf, ConfigFileSource)>> = OnceLock::new();
+
+/// Resolve the config file override from the command line (taking precedence)
+/// or the environment
+pub fn init_path_override() {
+    let _ = PATH_OVERRIDE.set(resolve_path_override(std::env::args().skip(1), std::env::var(CONFIG_PATH_ENV).ok()));
+}
+
+/// The config file `--config` in `args` or else `env` points at, and which of
+/// the two it was. A directory means `config.json` inside it.
+fn resolve_path_override(args: impl IntoIterator<Item = String>, env: Option<String>) -> Option<(PathBuf, ConfigFileSource)> {
+    let mut args = args.into_iter();
+    let mut flag = None;
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            flag = args.next();
+        } else if let Some(path) = arg.strip_prefix("--config=") {
+            flag = Some(path.to_string());
+        }
+    }
+    let flag = flag.filter(|p| !p.is_empty()).map(|p| (p, ConfigFileSource::Flag));
+    let chosen = flag.or_else(|| env.filter(|p| !p.is_empty()).map(|p| (p, ConfigFileSource::Env)));
+
+    chosen.map(|(raw, source)| {
+        let path = std::path::absolute(&raw).unwrap_or_else(|_| PathBuf::from(&raw));
+        if path.is_dir() || raw.ends_with(['/', '\\']) {
+            (path.join("config.json"), source)
+        } else {
+            (path, source)
+        }
+    })
+}
+
+pub fn path_override() -> Option<&'static Path> {
+    PATH_OVERRIDE.get().and_then(|p| p.as_ref()).map(|(path, _)| path.as_path())
+}
+
+fn config_file_source() -> ConfigFileSource {
+    PATH_OVERRIDE.get().and_then(|p| p.as_ref()).map_or(ConfigFileSource::Default, |(_, source)| *source)
+}
+
+/// Directory holding the config file and everything the app writes next to it
+/// (history, logs, results). Follows the config path override when one is set.
+pub fn config_dir() -> Result<PathBuf, String> {
+    if let Some(path) = path_override() {
+        return Ok(path.parent().map(Path::to_path_buf).unwrap_or_default());
+    }
+    Ok(dirs::config_dir()
+        .ok_or("Could not find config directory")?
+        .join("ama-agent"))
+}
+
+pub fn config_path() -> Result<PathBuf, String> {
+    match path_override() {
+        Some(path) => Ok(path.to_path_buf()),
+        None => Ok(config_dir()?.join("config.json")),
+    }
+}
+
+// Anything bigger is corrupt or not ours; refuse to read it into memory
+const MAX_CONFIG_BYTES: u64 = 1024 * 1024;
+
+// Created under the config dir at startup so writers never have to
+const SUBDIRS: &[&str] = &["cache", "logs", "recordings", "results"];
+
+/// Create the config directory and its subdirectories. Fails early if the base
+/// directory can't be created; otherwise reports every subdirectory that failed.
+pub fn ensure_dirs() -> Result<(), String> {
+    let base = config_dir()?;
+    std::fs::create_dir_all(&base).map_err(|e| format!("Could not create {}: {e}", base.display()))?;
+
+    let failures: Vec<String> = SUBDIRS
+        .iter()
+        .map(|name| base.join(name))
+        .filter_map(|dir| std::fs::create_dir_all(&dir).err().map(|e| format!("{}: {e}", dir.display())))
+        .collect();
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Could not create directories: {}", failures.join("; ")))
+    }
+}
+
+/// In-memory copy of `config.json`, loaded once at startup. Reads clone it;
+/// writes go to disk and memory together under the write lock.
+pub struct ConfigState(RwLock<AppConfig>);
+
+impl ConfigState {
+    pub fn new(config: AppConfig) -> Self {
+        Self(RwLock::new(config))
+    }
+}
+
+/// Current settings
+pub fn get(app: &AppHandle) -> AppConfig {
+    app.state::<ConfigState>().0.read().unwrap().clone()
+}
+
+/// Payload of `config-field-changed`, with API keys redacted
+#[derive(Debug, Clone, Serialize)]
+struct FieldChanged {
+    field: String,
+    old: serde_json::Value,
+    new: serde_json::Value,
+}
+
+/// Hide non-empty API keys, keeping whether one is set visible. Keys nested
+/// in objects such as `whisperFallback` are hidden too.
+fn redact(field: &str, value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::String(secret) if (field.ends_with("ApiKey") || field == "apiKey") && !secret.is_empty() => {
+            "********".into()
+        }
+        serde_json::Value::Object(map) => map
+            .into_iter()
+            .map(|(key, value)| {
+                let value = redact(&key, value);
+                (key, value)
+            })
+            .collect(),
+        value => value,
+    }
+}
+
+/// Emit `config-field-changed` for every serialized field that differs
+fn emit_changes(app: &AppHandle, old: &AppConfig, new: &AppConfig) {
+    let (Ok(serde_json::Value::Object(old)), Ok(serde_json::Value::Object(new))) =
+        (serde_json::to_value(old), serde_json::to_value(new))
+    else {
+        return;
+    };
+    let fields: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    for field in fields {
+        let before = old.get(field).cloned().unwrap_or_default();
+        let after = new.get(field).cloned().unwrap_or_default();
+        if before != after {
+            let change = FieldChanged { field: field.clone(), old: redact(field, before), new: redact(field, after) };
+            let _ = app.emit_event("config-field-changed", change);
+        }
+    }
+}
+
+/// Replace the settings, writing them to disk first so memory never runs ahead
+pub fn update(app: &AppHandle, config: AppConfig) -> Result<(), String> {
+    modify(app, |current| *current = config)
+}
+
+/// Change part of the settings in place and persist the result. Emits
+/// `config-field-changed` per changed field once the new settings are in place.
+pub fn modify(app: &AppHandle, change: impl FnOnce(&mut AppConfig)) -> Result<(), String> {
+    apply(app, change, true)
+}
+
+/// Like `modify`, for session-only changes: the file keeps the old value
+/// until the settings are next saved
+pub fn modify_in_memory(app: &AppHandle, change: impl FnOnce(&mut AppConfig)) {
+    let _ = apply(app, change, false);
+}
+
+fn apply(app: &AppHandle, change: impl FnOnce(&mut AppConfig), persist: bool) -> Result<(), String> {
+    let state = app.state::<ConfigState>();
+    let mut current = state.0.write().unwrap();
+    let mut config = current.clone();
+    change(&mut config);
+    if persist {
+        save(&config)?;
+    }
+    let previous = std::mem::replace(&mut *current, config.clone());
+    drop(current);
+    emit_changes(app, &previous, &config);
+    Ok(())
+}
+
+/// The config file's contents, None when there is none yet. Every read of the
+/// file goes through here so none can get around `MAX_CONFIG_BYTES`.
+fn read_config_file(path: &Path) -> Result<Option<String>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    // Read at most one byte past the cap so a file growing underneath us is caught too
+    let file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    let mut content = String::new();
+    file.take(MAX_CONFIG_BYTES + 1).read_to_string(&mut content).map_err(|e| e.to_string())?;
+    if content.len() as u64 > MAX_CONFIG_BYTES {
+        return Err(format!("{} is larger than {} KB", path.display(), MAX_CONFIG_BYTES / 1024));
+    }
+    Ok(Some(content))
+}
+
+pub fn load() -> Result<AppConfig, String> {
+    load_from(&config_path()?)
+}
+
+fn load_from(path: &Path) -> Result<AppConfig, String> {
+    match read_config_file(path)? {
+        Some(content) => serde_json::from_str(&content).map_err(|e| e.to_string()),
+        None => Ok(AppConfig::default()),
+    }
+}
+
+// Quiet period before a remembered model is written, so rapid queries coalesce
+const REMEMBER_MODEL_DEBOUNCE_MS: u64 = 2000;
+
+/// Generation counter for pending `remember_model` writes
+#[derive(Default)]
+pub struct RememberState {
+    generation: AtomicU64,
+}
+
+/// Persist `model` as the default LLM model after a short debounce. Best-effort:
+/// failures are logged and superseded writes are dropped.
+pub fn remember_model(app: &AppHandle, model: String) {
+    let generation = app.state::<RememberState>().generation.fetch_add(1, Ordering::SeqCst) + 1;
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(REMEMBER_MODEL_DEBOUNCE_MS)).await;
+        if app.state::<RememberState>().generation.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        if get(&app).llm_model.as_deref() == Some(model.as_str()) {
+            return;
+        }
+        if let Err(e) = modify(&app, |config| config.llm_model = Some(model)) {
+            tracing::error!("Failed to remember last model: {e}");
+        }
+    });
+}
+
+/// Per-call settings that take precedence over the saved config for that call only
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Overrides {
+    pub model: Option<String>,
+    pub language: Option<String>,
+    pub temperature: Option<f32>,
+    pub system_prompt: Option<String>,
+    // Matched from the app in front when a shortcut started the recording;
+    // the fields above win over its model
+    #[serde(skip)]
+    pub profile: Option<AppProfile>,
+}
+
+impl Overrides {
+    /// Config for a transcription call; `model` is the Whisper model
+    pub fn for_transcription(&self, config: &AppConfig) -> AppConfig {
+        let mut config = config.clone();
+        if let Some(model) = &self.model {
+            config.whisper_model = model.clone();
+        }
+        if let Some(language) = &self.language {
+            config.transcription_language = Some(language.clone());
+        }
+        config
+    }
+
+    /// Config for an LLM call; `model` is the chat model
+    pub fn for_llm(&self, config: &AppConfig) -> AppConfig {
+        let mut config = config.clone();
+        if let Some(model) = self.profile.as_ref().and_then(|profile| profile.model.as_ref()) {
+            config.llm_model = Some(model.clone());
+        }
+        if let Some(model) = &self.model {
+            config.llm_model = Some(model.clone());
+        }
+        if let Some(temperature) = self.temperature {
+            config.llm_temperature = Some(temperature);
+        }
+        if let Some(system_prompt) = &self.system_prompt {
+            config.system_prompt = system_prompt.clone();
+        }
+        config
+    }
+}
+
+/// Language part of a locale like `es-AR` or `pt_BR`
+fn language_code(locale: &str) -> Option<String> {
+    let code = locale.split(['-', '_', '.']).next()?.to_lowercase();
+    (code.len() == 2 && code.chars().all(|c| c.is_ascii_alphabetic())).then_some(code)
+}
+
+/// On first run (no config file yet), seed the transcription language from the
+/// system locale. Returns the detected language so onboarding can confirm it.
+pub fn seed_first_run_defaults() -> Result<Option<String>, String> {
+    if config_path()?.exists() {
+        return Ok(None);
+    }
+
+    let config = AppConfig {
+        transcription_language: sys_locale::get_locale().as_deref().and_then(language_code),
+        ..AppConfig::default()
+    };
+    save(&config)?;
+
+    Ok(config.transcription_language)
+}
+
+pub fn save(config: &AppConfig) -> Result<(), String> {
+    save_to(&config_path()?, config)
+}
+
+fn save_to(path: &Path, config: &AppConfig) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
+    std::fs::write(path, content).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Where an effective setting came from
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigSource {
+    Default,
+    // The config file in its usual location
+    File,
+    // A config file picked with `--config` or `AMA_CONFIG_PATH`
+    Override,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveValue {
+    pub value: serde_json::Value,
+    pub source: ConfigSource,
+}
+
+/// The config file behind `get_effective_config`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigFileInfo {
+    pub path: String,
+    pub source: ConfigFileSource,
+    pub exists: bool,
+}
+
+/// Returned by `get_effective_config`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectiveConfig {
+    pub file: ConfigFileInfo,
+    pub values: BTreeMap<String, EffectiveValue>,
+}
+
+/// Keys in the stored config file, or none if it can't be read
+fn file_keys() -> BTreeSet<String> {
+    let Ok(path) = config_path() else {
+        return BTreeSet::new();
+    };
+    read_config_file(&path)
+        .ok()
+        .flatten()
+        .and_then(|content| serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&content).ok())
+        .map(|map| map.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// The settings actually in effect, with provider defaults filled in, API keys
+/// redacted and each value tagged with its source, next to which config file
+/// was read and what picked it. `get_config` stays the raw stored config for
+/// the settings editor.
+#[tauri::command]
+pub fn get_effective_config(app: AppHandle) -> Result<EffectiveConfig, String> {
+    let config = get(&app);
+    let from_file = file_keys();
+    let path = config_path()?;
+    let file = ConfigFileInfo { path: path.display().to_string(), source: config_file_source(), exists: path.exists() };
+    let file_source = if file.source == ConfigFileSource::Default { ConfigSource::File } else { ConfigSource::Override };
+    let serde_json::Value::Object(mut values) = serde_json::to_value(&config).map_err(|e| e.to_string())? else {
+        return Err("Config did not serialize to an object".into());
+    };
+
+    if config.llm_model.is_none() {
+        if let Ok(provider) = crate::llm::Provider::from_name(&config.llm_provider) {
+            values.insert("llmModel".into(), provider.default_model().into());
+        }
+    }
+
+    let values = values
+        .into_iter()
+        .map(|(key, value)| {
+            let value = redact(&key, value);
+            let source = if from_file.contains(&key) { file_source } else { ConfigSource::Default };
+            (key, EffectiveValue { value, source })
+        })
+        .collect();
+    Ok(EffectiveConfig { file, values })
+}
+
+#[tauri::command]
+pub fn get_config(app: AppHandle) -> AppConfig {
+    get(&app)
+}
+
+/// Strip whitespace and line breaks picked up when a key, URL or path was pasted
+fn normalize(config: &mut AppConfig) {
+    for key in api_keys(config) {
+        *key = key.trim().to_string();
+    }
+    let fallback_url = config.whisper_fallback.as_mut().map(|fallback| &mut fallback.url);
+    let fields = [&mut config.whisper_url, &mut config.realtime_url, &mut config.ca_cert_path];
+    for field in fields.into_iter().chain(fallback_url) {
+        *field = field.trim().to_string();
+    }
+}
+
+/// Every API key field, the fallback endpoint's included
+pub fn api_keys(config: &mut AppConfig) -> Vec<&mut String> {
+    let fallback_key = config.whisper_fallback.as_mut().map(|fallback| &mut fallback.api_key);
+    [
+        &mut config.whisper_api_key,
+        &mut config.llm_api_key,
+        &mut config.openai_api_key,
+        &mut config.gemini_api_key,
+        &mut config.anthropic_api_key,
+    ]
+    .into_iter()
+    .chain(fallback_key)
+    .collect()
+}
+
+// Longest request timeout accepted; anything above is a typo
+const MAX_TIMEOUT_SECS: u64 = 3600;
+// A week; past that `idle_quit_minutes` is a typo
+const MAX_IDLE_QUIT_MINUTES: u64 = 7 * 24 * 60;
+// Past this a batch only queues up behind the request limit and rate limits
+const MAX_BATCH_CONCURRENCY: usize = 8;
+
+/// One problem found by `validate_config`. `field` is the camelCase setting,
+/// or its settings group for checks that span several.
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub error: String,
+}
+
+/// Returned by `validate_config`, with the settings as they would be saved
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationReport {
+    pub valid: bool,
+    pub errors: Vec<FieldError>,
+    pub normalized: AppConfig,
+}
+
+fn check_url(errors: &mut Vec<FieldError>, field: &'static str, url: &str, schemes: &[&str]) {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if schemes.contains(&parsed.scheme()) => {}
+        Ok(parsed) => {
+            let error = format!("\"{url}\" must use {}, not {}", schemes.join(" or "), parsed.scheme());
+            errors.push(FieldError { field, error });
+        }
+        Err(e) => errors.push(FieldError { field, error: format!("\"{url}\" is not a valid URL: {e}") }),
+    }
+}
+
+/// Every problem with `config`, rather than just the first
+fn validate(config: &AppConfig) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    check_url(&mut errors, "whisperUrl", &config.whisper_url, &["http", "https"]);
+    if !config.realtime_url.is_empty() {
+        check_url(&mut errors, "realtimeUrl", &config.realtime_url, &["ws", "wss"]);
+    }
+    if let Some(fallback) = config.whisper_fallback.as_ref().filter(|fallback| !fallback.url.is_empty()) {
+        check_url(&mut errors, "whisperFallback", &fallback.url, &["http", "https"]);
+    }
+    if !config.ca_cert_path.is_empty() {
+        if let Err(error) = http::load_certificates(&config.ca_cert_path) {
+            errors.push(FieldError { field: "caCertPath", error });
+        }
+    }
+    if config.llm_temperature.is_some_and(|temperature| !(0.0..=2.0).contains(&temperature)) {
+        errors.push(FieldError { field: "llmTemperature", error: "LLM temperature must be between 0.0 and 2.0".into() });
+    }
+    if !(0.0..=1.0).contains(&config.duplicate_similarity) {
+        errors.push(FieldError { field: "duplicateSimilarity", error: "Duplicate similarity must be between 0.0 and 1.0".into() });
+    }
+    if !(1..=MAX_BATCH_CONCURRENCY).contains(&config.batch_concurrency) {
+        errors.push(FieldError {
+            field: "batchConcurrency",
+            error: format!("Batch concurrency must be between 1 and {MAX_BATCH_CONCURRENCY}"),
+        });
+    }
+    let timeouts = [
+        ("httpTimeoutSecs", Some(config.http_timeout_secs)),
+        ("whisperTimeoutSecs", config.whisper_timeout_secs),
+        ("llmTimeoutSecs", config.llm_timeout_secs),
+    ];
+    for (field, secs) in timeouts {
+        if secs.is_some_and(|secs| !(1..=MAX_TIMEOUT_SECS).contains(&secs)) {
+            errors.push(FieldError { field, error: format!("Timeout must be between 1 and {MAX_TIMEOUT_SECS} seconds") });
+        }
+    }
+    if config.idle_quit_minutes > MAX_IDLE_QUIT_MINUTES {
+        errors.push(FieldError {
+            field: "idleQuitMinutes",
+            error: format!("Idle quit must be at most {MAX_IDLE_QUIT_MINUTES} minutes (0 = never)"),
+        });
+    }
+    let checks = [
+        ("shortcuts", shortcut::validate(config)),
+        ("replacements", replace::validate(&config.replacements)),
+        ("whisper", whisper::validate(config)),
+        ("openInHandlers", handoff::validate(config)),
+        ("appProfiles", profile::validate(config)),
+        ("presets", preset::validate(config)),
+        ("llm", llm::validate(config)),
+    ];
+    for (field, result) in checks {
+        if let Err(error) = result {
+            errors.push(FieldError { field, error });
+        }
+    }
+    errors
+}
+
+/// Dry run of `save_config`: normalize and check the settings without saving
+#[tauri::command]
+pub fn validate_config(mut config: AppConfig) -> ValidationReport {
+    normalize(&mut config);
+    let errors = validate(&config);
+    ValidationReport { valid: errors.is_empty(), errors, normalized: config }
+}
+
+/// Validate and store the settings. Keys and URLs are trimmed first; a key
+/// that still looks wrong is saved anyway, with an `api-key-warning` for the
+/// UI the first time it is saved that way. Nothing is saved if the new
+/// shortcuts can't all be registered; the previous ones are put back.
+#[tauri::command]
+pub fn save_config(app: AppHandle, mut config: AppConfig) -> Result<(), String> {
+    normalize(&mut config);
+    let errors = validate(&config);
+    if !errors.is_empty() {
+        return Err(errors.into_iter().map(|e| e.error).collect::<Vec<_>>().join("\n"));
+    }
+    let previous = get(&app);
+    let known = llm::key_warnings(&previous);
+    // Bind first: a shortcut held by another app must not end up in the file
+    if let Err(e) = shortcut::register_all(&app, &config).and_then(|()| update(&app, config.clone())) {
+        if let Err(restore) = shortcut::register_all(&app, &previous) {
+            tracing::error!("Could not restore the previous shortcuts: {restore}");
+        }
+        return Err(e);
+    }
+    for warning in llm::key_warnings(&config) {
+        if known.iter().any(|old| old.field == warning.field && old.message == warning.message) {
+            continue;
+        }
+        tracing::warn!("Suspicious API key in {}: {}", warning.field, warning.message);
+        let _ = app.emit_event("api-key-warning", warning);
+    }
+    if let Some(warning) = audio::bit_depth_warning(&config) {
+        let _ = app.emit_event("audio-format-warning", warning);
+    }
+    llm::autoselect_provider(&app);
+    http::configure(&app, &config);
+    onboarding::refresh(&app);
+    readiness::refresh(&app);
+    tray::set_visible(&app, config.show_tray && !headless::active()).map_err(|e| e.to_string())?;
+    tray::rebuild_menu(&app);
+    tray::refresh_icon(&app);
+    tray::refresh_tooltip(&app);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh path under the system temp dir, removed first if a previous run left it
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("ama-agent-test-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn oversized_config_file_is_refused() {
+        let path = temp_path("oversized.json");
+        std::fs::write(&path, vec![b' '; MAX_CONFIG_BYTES as usize + 1]).unwrap();
+        let result = read_config_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.unwrap_err().contains("larger than"));
+    }
+
+    #[test]
+    fn config_file_at_the_cap_is_read() {
+        let path = temp_path("at-cap.json");
+        std::fs::write(&path, vec![b' '; MAX_CONFIG_BYTES as usize]).unwrap();
+        let result = read_config_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result.unwrap().map(|content| content.len()), Some(MAX_CONFIG_BYTES as usize));
+    }
+
+    #[test]
+    fn missing_config_file_reads_as_none() {
+        assert_eq!(read_config_file(&temp_path("missing.json")), Ok(None));
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn config_round_trips_through_an_override_directory() {
+        let dir = temp_path("override-dir");
+        std::fs::create_dir_all(&dir).unwrap();
+        let (path, source) = resolve_path_override(args(&["--config", dir.to_str().unwrap()]), None).unwrap();
+        assert_eq!(path, std::path::absolute(&dir).unwrap().join("config.json"));
+        assert_eq!(source, ConfigFileSource::Flag);
+
+        let config = AppConfig { whisper_model: "override-model".into(), ..AppConfig::default() };
+        save_to(&path, &config).unwrap();
+        let loaded = load_from(&path);
+        let written = path.exists();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(written);
+        assert_eq!(loaded.unwrap().whisper_model, "override-model");
+    }
+
+    #[test]
+    fn flag_takes_precedence_over_the_environment() {
+        let flag = temp_path("flag.json");
+        let env = temp_path("env.json");
+        let chosen = resolve_path_override(args(&[&format!("--config={}", flag.display())]), Some(env.display().to_string()));
+        assert_eq!(chosen, Some((flag, ConfigFileSource::Flag)));
+        let chosen = resolve_path_override(args(&["--verbose"]), Some(env.display().to_string()));
+        assert_eq!(chosen, Some((env, ConfigFileSource::Env)));
+    }
+
+    #[test]
+    fn empty_or_missing_override_means_the_default_location() {
+        assert_eq!(resolve_path_override(args(&[]), None), None);
+        assert_eq!(resolve_path_override(args(&[]), Some(String::new())), None);
+    }
+
+    #[test]
+    fn trailing_separator_names_a_directory() {
+        let dir = temp_path("not-yet-created");
+        let (path, _) = resolve_path_override(args(&["--config", &format!("{}/", dir.display())]), None).unwrap();
+        assert_eq!(path.file_name().unwrap(), "config.json");
+    }
+
+    #[test]
+    fn idle_quit_minutes_is_bounded() {
+        let config = AppConfig { idle_quit_minutes: u64::MAX, ..AppConfig::default() };
+        assert!(validate(&config).iter().any(|error| error.field == "idleQuitMinutes"));
+        let config = AppConfig { idle_quit_minutes: MAX_IDLE_QUIT_MINUTES, ..AppConfig::default() };
+        assert!(!validate(&config).iter().any(|error| error.field == "idleQuitMinutes"));
+    }
+}
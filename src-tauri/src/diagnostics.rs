@@ -0,0 +1,133 @@
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use crate::config;
+use crate::error::AppError;
+use crate::http::HttpState;
+use crate::llm::Provider;
+use crate::onboarding;
+use crate::shortcut;
+
+// Only the newest log files go into a bundle
+const MAX_LOG_FILES: usize = 5;
+// Each endpoint gets this long before it counts as unreachable
+const PROBE_TIMEOUT_SECS: u64 = 5;
+
+/// One row of the connectivity matrix returned by `run_diagnostics`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Connectivity {
+    // whisper, whisper-fallback or an LLM provider name
+    pub provider: String,
+    pub endpoint: String,
+    pub reachable: bool,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+fn zip_err(e: zip::result::ZipError) -> AppError {
+    AppError::Io(e.to_string())
+}
+
+/// App, OS and setup state: everything support asks for besides logs
+fn report(app: &AppHandle) -> serde_json::Value {
+    let package = app.package_info();
+    json!({
+        "app": package.name,
+        "version": package.version.to_string(),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "onboarding": onboarding::get_onboarding_status(app.clone()),
+        "registeredShortcuts": shortcut::list_registered_shortcuts(app.clone()),
+    })
+}
+
+/// Write a zip with recent logs, the redacted effective config and a version
+/// report to `path`. History and API keys are never included.
+#[tauri::command]
+pub fn export_diagnostics(app: AppHandle, path: String) -> Result<(), AppError> {
+    let effective = config::get_effective_config(app.clone()).map_err(AppError::Config)?;
+    let options = SimpleFileOptions::default();
+    let mut zip = ZipWriter::new(std::fs::File::create(&path)?);
+
+    zip.start_file("report.json", options).map_err(zip_err)?;
+    zip.write_all(serde_json::to_string_pretty(&report(&app)).unwrap_or_default().as_bytes())?;
+
+    zip.start_file("config.json", options).map_err(zip_err)?;
+    zip.write_all(serde_json::to_string_pretty(&effective).unwrap_or_default().as_bytes())?;
+
+    let logs_dir = config::config_dir().map_err(AppError::Config)?.join("logs");
+    let mut logs: Vec<_> = std::fs::read_dir(&logs_dir)
+        .map(|entries| entries.filter_map(Result::ok).filter(|entry| entry.path().is_file()).collect())
+        .unwrap_or_default();
+    logs.sort_by_key(|entry| std::cmp::Reverse(entry.metadata().and_then(|m| m.modified()).ok()));
+    for entry in logs.into_iter().take(MAX_LOG_FILES) {
+        zip.start_file(format!("logs/{}", entry.file_name().to_string_lossy()), options).map_err(zip_err)?;
+        zip.write_all(&std::fs::read(entry.path())?)?;
+    }
+
+    zip.finish().map_err(zip_err)?;
+    Ok(())
+}
+
+/// Every endpoint a request could go to: transcription and its fallback, then
+/// the LLM provider and its fallbacks. Duplicates are probed once.
+fn endpoints(app: &AppHandle) -> Vec<(String, String)> {
+    let config = config::get(app);
+    let mut endpoints = vec![("whisper".to_string(), config.whisper_url.clone())];
+    if let Some(fallback) = &config.whisper_fallback {
+        endpoints.push(("whisper-fallback".into(), fallback.url.clone()));
+    }
+    let llm = std::iter::once(config.llm_provider.as_str()).chain(config.llm_fallback.iter().map(|target| target.provider.as_str()));
+    for name in llm {
+        match Provider::from_name(name) {
+            Ok(provider) => endpoints.push((name.to_string(), provider.origin().to_string())),
+            Err(_) => endpoints.push((name.to_string(), String::new())),
+        }
+    }
+    let mut seen = BTreeSet::new();
+    endpoints.retain(|endpoint| seen.insert(endpoint.clone()));
+    endpoints
+}
+
+/// Any HTTP response counts as reachable; only connection failures and timeouts don't
+async fn probe(client: reqwest::Client, provider: String, endpoint: String) -> Connectivity {
+    let started = Instant::now();
+    let result = if endpoint.is_empty() {
+        Err(AppError::Config(format!("Unknown provider \"{provider}\"")))
+    } else {
+        client.head(&endpoint).timeout(Duration::from_secs(PROBE_TIMEOUT_SECS)).send().await.map_err(AppError::from)
+    };
+    Connectivity {
+        reachable: result.is_ok(),
+        latency_ms: started.elapsed().as_millis() as u64,
+        error: result.err().map(|e| e.to_string()),
+        provider,
+        endpoint,
+    }
+}
+
+/// Probe every configured transcription and LLM endpoint at once. Each probe
+/// times out on its own, so one dead endpoint can't hold up the rest.
+#[tauri::command]
+pub async fn run_diagnostics(app: AppHandle) -> Vec<Connectivity> {
+    let client = app.state::<HttpState>().client();
+    let probes: Vec<_> = endpoints(&app)
+        .into_iter()
+        .map(|(provider, endpoint)| tauri::async_runtime::spawn(probe(client.clone(), provider, endpoint)))
+        .collect();
+
+    let mut matrix = Vec::with_capacity(probes.len());
+    for probe in probes {
+        if let Ok(row) = probe.await {
+            matrix.push(row);
+        }
+    }
+    matrix
+}
@@ -0,0 +1,101 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::stats;
+
+/// Categorized backend error, serialized to the frontend as `{ kind, message }`
+#[derive(Debug, Clone, thiserror::Error, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum AppError {
+    #[error("Configuration error: {0}")]
+    Config(String),
+    #[error("Network error: {0}")]
+    Network(String),
+    #[error("Request timed out")]
+    Timeout,
+    #[error("Authentication failed: {0}")]
+    Auth(String),
+    #[error("Provider error ({status}): {message}")]
+    Provider { status: u16, message: String },
+    #[error("Audio error: {0}")]
+    Audio(String),
+    #[error("I/O error: {0}")]
+    Io(String),
+    #[error("Cancelled")]
+    Cancelled,
+}
+
+impl AppError {
+    /// Short description without the underlying message, safe to show anywhere
+    pub fn summary(&self) -> &'static str {
+        match self {
+            AppError::Config(_) => "Configuration problem - check your settings",
+            AppError::Network(_) => "Network error - the provider could not be reached",
+            AppError::Timeout => "The request timed out",
+            AppError::Auth(_) => "Authentication failed - check your API key",
+            AppError::Provider { .. } => "The provider returned an error",
+            AppError::Audio(_) => "Audio capture failed",
+            AppError::Io(_) => "A file could not be read or written",
+            AppError::Cancelled => "Cancelled",
+        }
+    }
+
+    /// Map a non-success HTTP response to an error category
+    pub fn from_status(status: reqwest::StatusCode, body: String) -> Self {
+        match status.as_u16() {
+            401 | 403 => AppError::Auth(body),
+            code => AppError::Provider { status: code, message: body },
+        }
+    }
+}
+
+impl From<reqwest::Error> for AppError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            AppError::Timeout
+        } else {
+            AppError::Network(e.to_string())
+        }
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        AppError::Io(e.to_string())
+    }
+}
+
+/// Most recent failure of each pipeline stage (recording, transcription,
+/// llm), kept until that stage next succeeds
+#[derive(Default)]
+pub struct LastErrorState {
+    errors: Mutex<HashMap<&'static str, AppError>>,
+}
+
+/// Remember a stage's failure, or forget it once the stage succeeds.
+/// Cancellation is neither, so it leaves the last error as it was.
+pub fn record_outcome<T>(app: &AppHandle, stage: &'static str, result: &Result<T, AppError>) {
+    stats::stage_finished(app, stage, result);
+    let state = app.state::<LastErrorState>();
+    let mut errors = state.errors.lock().unwrap();
+    match result {
+        Ok(_) => {
+            errors.remove(stage);
+        }
+        Err(AppError::Cancelled) => {}
+        Err(e) => {
+            errors.insert(stage, e.clone());
+        }
+    }
+}
+
+/// The last error of every stage that hasn't succeeded since, so the UI can
+/// show it even after missing the event
+#[tauri::command]
+pub fn get_last_errors(app: AppHandle) -> HashMap<String, AppError> {
+    let state = app.state::<LastErrorState>();
+    let errors = state.errors.lock().unwrap();
+    errors.iter().map(|(stage, error)| (stage.to_string(), error.clone())).collect()
+}
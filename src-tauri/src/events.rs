@@ -0,0 +1,115 @@
+use serde::Serialize;
+use tauri::{Emitter, Runtime};
+
+/// Bumped whenever an existing payload changes shape, so the frontend can
+/// tell which layout it is looking at
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Every event payload is an object carrying `schemaVersion` next to its own fields
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Versioned<T> {
+    schema_version: u32,
+    #[serde(flatten)]
+    payload: T,
+}
+
+fn versioned<T: Serialize>(payload: T) -> Versioned<T> {
+    Versioned { schema_version: SCHEMA_VERSION, payload }
+}
+
+/// The JSON `emit_event` sends for `payload`, for tests of the payload types
+#[cfg(test)]
+pub(crate) fn emitted(payload: impl Serialize) -> serde_json::Value {
+    serde_json::to_value(versioned(payload)).unwrap()
+}
+
+/// Emitting through here stamps the schema version. Payloads must serialize to
+/// a JSON object, so plain values are wrapped in one of the structs below.
+pub trait EmitEvent<R: Runtime> {
+    fn emit_event(&self, event: &str, payload: impl Serialize + Clone) -> tauri::Result<()>;
+}
+
+impl<R: Runtime, E: Emitter<R>> EmitEvent<R> for E {
+    fn emit_event(&self, event: &str, payload: impl Serialize + Clone) -> tauri::Result<()> {
+        self.emit(event, versioned(payload))
+    }
+}
+
+/// Events that only signal that something happened
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Empty {}
+
+/// A piece of text: `result`, `llm-delta`, `llm-stream-stalled`,
+/// `clipboard-result` and `repeat-result`
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Text<'a> {
+    pub text: &'a str,
+}
+
+/// `do-not-disturb-changed`
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct DoNotDisturbChanged {
+    pub enabled: bool,
+}
+
+/// `shortcuts-reregistered`; `resumed` is false when a lost binding triggered it
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Reregistered {
+    pub resumed: bool,
+}
+
+/// `default-language-detected`
+#[derive(Debug, Clone, Serialize)]
+pub struct LanguageDetected {
+    pub language: String,
+}
+
+/// `onboarding-step-complete`
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct StepComplete {
+    pub step: &'static str,
+}
+
+/// `config-imported`: camelCase settings `import_from_env` filled in
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigImported {
+    pub fields: Vec<&'static str>,
+}
+
+/// `provider-autoselected`
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ProviderAutoselected<'a> {
+    pub provider: &'a str,
+}
+
+/// `provider-choice-needed`: every provider that has a key
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ProviderChoiceNeeded<'a> {
+    pub providers: &'a [&'static str],
+}
+
+/// `file-dropped` and `unsupported-drop`
+#[derive(Debug, Clone, Serialize)]
+pub struct DroppedFiles {
+    pub files: Vec<String>,
+}
+
+/// `navigate`: the frontend view to switch to, e.g. `settings`
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Navigate<'a> {
+    pub route: &'a str,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn payloads_carry_the_schema_version_next_to_their_fields() {
+        assert_eq!(emitted(Text { text: "hello" }), json!({ "schemaVersion": SCHEMA_VERSION, "text": "hello" }));
+        assert_eq!(emitted(Reregistered { resumed: true }), json!({ "schemaVersion": SCHEMA_VERSION, "resumed": true }));
+        assert_eq!(emitted(Empty {}), json!({ "schemaVersion": SCHEMA_VERSION }));
+    }
+}
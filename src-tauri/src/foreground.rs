@@ -0,0 +1,76 @@
+/// Identifier of the app whose window has focus: the executable name on
+/// Windows (`code.exe`), the bundle id on macOS (`com.apple.Safari`) and the
+/// window class on Linux/X11 (`firefox`). None when it can't be told.
+pub use platform::foreground_app;
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::path::Path;
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+    pub fn foreground_app() -> Option<String> {
+        // SAFETY: plain Win32 queries on handles returned by the system; the
+        // name buffer's length is passed along and the process handle closed
+        unsafe {
+            let window = GetForegroundWindow();
+            if window.is_null() {
+                return None;
+            }
+            let mut pid = 0u32;
+            GetWindowThreadProcessId(window, &mut pid);
+            if pid == 0 {
+                return None;
+            }
+            let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+            if process.is_null() {
+                return None;
+            }
+            let mut buffer = [0u16; 1024];
+            let mut len = buffer.len() as u32;
+            let ok = QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, buffer.as_mut_ptr(), &mut len);
+            CloseHandle(process);
+            if ok == 0 {
+                return None;
+            }
+            let path = String::from_utf16_lossy(&buffer[..len as usize]);
+            Path::new(&path).file_name().map(|name| name.to_string_lossy().into_owned())
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::process::Command;
+
+    fn lsappinfo(args: &[&str]) -> Option<String> {
+        let output = Command::new("lsappinfo").args(args).output().ok()?;
+        output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Ask Launch Services for the front app's `CFBundleIdentifier`
+    pub fn foreground_app() -> Option<String> {
+        let front = lsappinfo(&["front"]).filter(|front| !front.is_empty())?;
+        let info = lsappinfo(&["info", "-only", "bundleid", &front])?;
+        // "CFBundleIdentifier"="com.apple.Safari"
+        let id = info.split_once('=')?.1.trim().trim_matches('"');
+        (!id.is_empty() && id != "[ NULL ]").then(|| id.to_string())
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use crate::x11;
+
+    /// Class part of the active window's `WM_CLASS`, from the X11 window manager
+    pub fn foreground_app() -> Option<String> {
+        let id = x11::active_window()?;
+        // WM_CLASS(STRING) = "instance", "Class"
+        let class = x11::xprop(&["-id", &id, "WM_CLASS"])?;
+        let class = class.split_once('=')?.1.split(',').next_back()?.trim().trim_matches('"');
+        (!class.is_empty()).then(|| class.to_string())
+    }
+}
@@ -0,0 +1,56 @@
+/// Whether the foreground window covers its whole monitor. Detection is
+/// best-effort; platforms without a reliable query report false.
+pub use platform::foreground_is_fullscreen;
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows_sys::Win32::Foundation::RECT;
+    use windows_sys::Win32::Graphics::Gdi::{GetMonitorInfoW, MonitorFromWindow, MONITORINFO, MONITOR_DEFAULTTONEAREST};
+    use windows_sys::Win32::UI::WindowsAndMessaging::{GetDesktopWindow, GetForegroundWindow, GetShellWindow, GetWindowRect};
+
+    pub fn foreground_is_fullscreen() -> bool {
+        // SAFETY: plain Win32 queries on handles returned by the system; the
+        // out-structs are zeroed and sized as the API requires
+        unsafe {
+            let window = GetForegroundWindow();
+            if window.is_null() || window == GetDesktopWindow() || window == GetShellWindow() {
+                return false;
+            }
+
+            let mut rect: RECT = std::mem::zeroed();
+            if GetWindowRect(window, &mut rect) == 0 {
+                return false;
+            }
+            let mut info: MONITORINFO = std::mem::zeroed();
+            info.cbSize = std::mem::size_of::<MONITORINFO>() as u32;
+            if GetMonitorInfoW(MonitorFromWindow(window, MONITOR_DEFAULTTONEAREST), &mut info) == 0 {
+                return false;
+            }
+
+            let monitor = info.rcMonitor;
+            rect.left <= monitor.left && rect.top <= monitor.top && rect.right >= monitor.right && rect.bottom >= monitor.bottom
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    // Full-screen Spaces aren't exposed to other apps in a reliable way; the
+    // manual do-not-disturb toggle covers macOS
+    pub fn foreground_is_fullscreen() -> bool {
+        false
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use crate::x11;
+
+    /// Ask the X11 window manager for the active window's `_NET_WM_STATE`
+    pub fn foreground_is_fullscreen() -> bool {
+        let Some(id) = x11::active_window() else {
+            return false;
+        };
+        x11::xprop(&["-id", &id, "_NET_WM_STATE"]).is_some_and(|state| state.contains("_NET_WM_STATE_FULLSCREEN"))
+    }
+}
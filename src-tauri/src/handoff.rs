@@ -0,0 +1,123 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+use tauri::AppHandle;
+use tauri_plugin_opener::OpenerExt;
+
+use crate::config::{self, AppConfig};
+use crate::error::AppError;
+
+const PLACEHOLDER: &str = "{text}";
+
+/// A template is a URL when it starts with a scheme such as `obsidian://`;
+/// anything else is a command line
+fn is_url(template: &str) -> bool {
+    template
+        .split_once("://")
+        .is_some_and(|(scheme, _)| !scheme.is_empty() && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c)))
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters, so the text
+/// can go anywhere in a URL
+fn encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+/// Reject handlers that couldn't be launched
+pub fn validate(config: &AppConfig) -> Result<(), String> {
+    for (name, template) in &config.open_in_handlers {
+        if name.trim().is_empty() {
+            return Err("\"Open in\" handlers need a name".into());
+        }
+        if template.trim().is_empty() {
+            return Err(format!("The \"{name}\" handler has no URL or command"));
+        }
+        if is_url(template) && !template.contains(PLACEHOLDER) {
+            return Err(format!("The \"{name}\" URL needs a {PLACEHOLDER} placeholder"));
+        }
+    }
+    if !config.open_in_default.is_empty() && !config.open_in_handlers.contains_key(&config.open_in_default) {
+        return Err(format!("Unknown default \"open in\" handler \"{}\"", config.open_in_default));
+    }
+    Ok(())
+}
+
+/// Run a command template without a shell, so the text can't be interpreted.
+/// Arguments are split on whitespace and `{text}` is substituted in each;
+/// without a placeholder the text is written to the command's stdin. The write
+/// and the wait happen on a thread of their own, so a handler that never reads
+/// stdin can't hold anything up and an exited one doesn't linger as a zombie.
+fn run(template: &str, text: &str) -> Result<(), AppError> {
+    let mut args = template.split_whitespace();
+    let program = args.next().ok_or_else(|| AppError::Config("The handler has no command".into()))?.to_string();
+    let piped = !template.contains(PLACEHOLDER);
+    let mut child = Command::new(&program)
+        .args(args.map(|arg| arg.replace(PLACEHOLDER, text)))
+        .stdin(if piped { Stdio::piped() } else { Stdio::null() })
+        .spawn()
+        .map_err(|e| AppError::Io(format!("{program}: {e}")))?;
+    let stdin = child.stdin.take().map(|stdin| (stdin, text.to_string()));
+    thread::spawn(move || {
+        if let Some((mut stdin, text)) = stdin {
+            // Dropped at the end of the block, closing the pipe so the handler sees EOF
+            if let Err(e) = stdin.write_all(text.as_bytes()) {
+                tracing::warn!("Could not write the result to {program}: {e}");
+            }
+        }
+        match child.wait() {
+            Ok(status) if !status.success() => tracing::warn!("{program} exited with {status}"),
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Could not wait for {program}: {e}"),
+        }
+    });
+    Ok(())
+}
+
+/// Hand a result to another application through the handler named `app`
+/// (`openInHandlers`), or the default handler when `app` is empty. URL
+/// handlers get the text percent-encoded and go to the system opener.
+#[tauri::command]
+pub async fn open_result_in(handle: AppHandle, app: String, text: String) -> Result<(), AppError> {
+    let config = config::get(&handle);
+    let name = if app.is_empty() { &config.open_in_default } else { &app };
+    if name.is_empty() {
+        return Err(AppError::Config("No default \"open in\" handler is set".into()));
+    }
+    let template = config
+        .open_in_handlers
+        .get(name)
+        .ok_or_else(|| AppError::Config(format!("Unknown \"open in\" handler \"{name}\"")))?;
+    if is_url(template) {
+        let url = template.replace(PLACEHOLDER, &encode(&text));
+        handle.opener().open_url(url, None::<&str>).map_err(|e| AppError::Io(e.to_string()))
+    } else {
+        run(template, &text)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn a_handler_that_ignores_stdin_does_not_block() {
+        // Far more than a pipe buffer holds
+        let text = "x".repeat(1024 * 1024);
+        let start = Instant::now();
+        run("sleep 2", &text).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn a_missing_program_is_reported() {
+        assert!(matches!(run("ama-agent-no-such-handler", "text"), Err(AppError::Io(_))));
+    }
+}
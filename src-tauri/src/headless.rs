@@ -0,0 +1,53 @@
+use std::sync::OnceLock;
+use tauri::{AppHandle, Manager};
+
+use crate::config::AppConfig;
+
+// Resolved once at startup; switching needs a restart since the window and
+// tray are only set up then
+static HEADLESS: OnceLock<bool> = OnceLock::new();
+
+/// Decide from `--headless` or the `headless` config whether this run has no
+/// window or tray
+pub fn init(config: &AppConfig) {
+    let flag = std::env::args().skip(1).any(|arg| arg == "--headless");
+    let _ = HEADLESS.set(flag || config.headless);
+}
+
+/// True when running as a shortcut-only background service
+pub fn active() -> bool {
+    HEADLESS.get().copied().unwrap_or(false)
+}
+
+/// Close the main window for good and quit cleanly on SIGINT/SIGTERM, since
+/// there is no tray to quit from
+pub fn start(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.destroy();
+    }
+
+    let interrupt_app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            tracing::info!("Interrupted, quitting");
+            interrupt_app.exit(0);
+        }
+    });
+
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let terminate_app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            match signal(SignalKind::terminate()) {
+                Ok(mut terminate) => {
+                    terminate.recv().await;
+                    tracing::info!("Terminated, quitting");
+                    terminate_app.exit(0);
+                }
+                Err(e) => tracing::error!("Failed to listen for SIGTERM: {e}"),
+            }
+        });
+    }
+    tracing::info!("Running headless: shortcuts only, results go to the clipboard and file sinks");
+}
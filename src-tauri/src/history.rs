@@ -0,0 +1,422 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State};
+
+use crate::config;
+use crate::events::EmitEvent;
+use crate::tray;
+
+/// One processed query in `history.jsonl`. `seq` is monotonic and decides
+/// ordering; `timestamp_ms` is wall-clock and may jump around. `seq` is also
+/// the entry's id for `delete_history_entry` and never gets reused.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub seq: u64,
+    pub timestamp_ms: u64,
+    pub transcript: String,
+    pub result: String,
+}
+
+/// A line of `history.jsonl`: an entry, or a tombstone appended when one is
+/// deleted. Tombstones are dropped by `compact_history`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Line {
+    Entry(HistoryEntry),
+    Deleted { deleted: u64 },
+}
+
+/// Returned by `compact_history`
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompactSummary {
+    pub kept: usize,
+    pub removed: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ClockSkew {
+    previous_timestamp_ms: u64,
+    timestamp_ms: u64,
+}
+
+/// `history-line-skipped`: a line of `history.jsonl` that isn't valid, from a
+/// partial write or a manual edit. `line` is 1-based.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SkippedLine {
+    line: usize,
+    error: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Cursor {
+    next_seq: u64,
+    last_timestamp_ms: u64,
+}
+
+/// Sequence cursor, loaded from the file on first append
+#[derive(Default)]
+pub struct HistoryState {
+    cursor: Mutex<Option<Cursor>>,
+}
+
+fn history_path() -> Result<PathBuf, String> {
+    Ok(config::config_dir()?.join("history.jsonl"))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+// Hex digits of the content hash in a backup's name
+const BACKUP_HASH_LEN: usize = 16;
+
+/// Every readable line, and the ones that had to be skipped. One bad line
+/// doesn't take the rest of the history down with it.
+fn parse_lines(content: &str) -> (Vec<Line>, Vec<SkippedLine>) {
+    let mut lines = Vec::new();
+    let mut skipped = Vec::new();
+    for (index, line) in content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        match serde_json::from_str(line) {
+            Ok(line) => lines.push(line),
+            Err(e) => skipped.push(SkippedLine { line: index + 1, error: e.to_string() }),
+        }
+    }
+    (lines, skipped)
+}
+
+fn read_content(path: &Path) -> Result<String, String> {
+    if !path.exists() {
+        return Ok(String::new());
+    }
+    std::fs::read_to_string(path).map_err(|e| e.to_string())
+}
+
+/// Copy a corrupt history aside to `history.jsonl.corrupt-<hash>`. Every
+/// distinct content gets its own backup, so a later corruption never
+/// overwrites an earlier one and rereading the same file adds nothing.
+fn back_up(path: &Path, content: &str) {
+    let hash: String = Sha256::digest(content.as_bytes()).iter().map(|byte| format!("{byte:02x}")).collect();
+    let backup = path.with_extension(format!("jsonl.corrupt-{}", &hash[..BACKUP_HASH_LEN]));
+    if backup.exists() {
+        return;
+    }
+    match std::fs::write(&backup, content) {
+        Ok(()) => tracing::warn!("Backed up corrupt history to {}", backup.display()),
+        Err(e) => tracing::error!("Could not back up corrupt history to {}: {e}", backup.display()),
+    }
+}
+
+/// Lines of the file at `path`, logging the ones that had to be skipped. A
+/// file with bad lines is first backed up, so nothing is lost once
+/// `compact_history` drops them.
+fn read_lines_checked(path: &Path) -> Result<(Vec<Line>, Vec<SkippedLine>), String> {
+    let content = read_content(path)?;
+    let (lines, skipped) = parse_lines(&content);
+    if !skipped.is_empty() {
+        for skipped in &skipped {
+            tracing::warn!("Skipping history line {}: {}", skipped.line, skipped.error);
+        }
+        back_up(path, &content);
+    }
+    Ok((lines, skipped))
+}
+
+/// Readable lines, quietly leaving out the rest; `get_history` reports them
+fn read_lines() -> Result<Vec<Line>, String> {
+    read_content(&history_path()?).map(|content| parse_lines(&content).0)
+}
+
+/// Entries that haven't been deleted, in file order
+fn live_entries(lines: Vec<Line>) -> Vec<HistoryEntry> {
+    let deleted: BTreeSet<u64> = lines
+        .iter()
+        .filter_map(|line| match line {
+            Line::Deleted { deleted } => Some(*deleted),
+            Line::Entry(_) => None,
+        })
+        .collect();
+    lines
+        .into_iter()
+        .filter_map(|line| match line {
+            Line::Entry(entry) if !deleted.contains(&entry.seq) => Some(entry),
+            _ => None,
+        })
+        .collect()
+}
+
+fn read_entries() -> Result<Vec<HistoryEntry>, String> {
+    read_lines().map(live_entries)
+}
+
+fn load_cursor() -> Result<Cursor, String> {
+    let lines = read_lines()?;
+    // Tombstones count too, so a deleted newest entry's id isn't handed out again
+    let max_seq = lines
+        .iter()
+        .map(|line| match line {
+            Line::Entry(entry) => entry.seq,
+            Line::Deleted { deleted } => *deleted,
+        })
+        .max()
+        .unwrap_or(0);
+    let last_timestamp_ms = lines
+        .iter()
+        .filter_map(|line| match line {
+            Line::Entry(entry) => Some(entry.timestamp_ms),
+            Line::Deleted { .. } => None,
+        })
+        .max()
+        .unwrap_or(0);
+    Ok(Cursor { next_seq: max_seq + 1, last_timestamp_ms })
+}
+
+fn append_line(line: &Line) -> Result<(), String> {
+    let path = history_path()?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| e.to_string())?;
+    let mut line = serde_json::to_string(line).map_err(|e| e.to_string())?;
+    // After a partial write the last line has no newline; start a fresh one
+    // rather than gluing this entry onto the broken one
+    if file.metadata().map_err(|e| e.to_string())?.len() > 0 {
+        let mut last = [0u8; 1];
+        file.seek(SeekFrom::End(-1)).and_then(|_| file.read_exact(&mut last)).map_err(|e| e.to_string())?;
+        if last[0] != b'\n' {
+            line.insert(0, '\n');
+        }
+    }
+    writeln!(file, "{line}").map_err(|e| e.to_string())
+}
+
+/// Append an entry, warning with `clock-skew` if the wall clock went backwards
+pub fn append(app: &AppHandle, transcript: &str, result: &str) -> Result<HistoryEntry, String> {
+    let state = app.state::<HistoryState>();
+    let mut cursor = state.cursor.lock().unwrap();
+    let current = match *cursor {
+        Some(current) => current,
+        None => load_cursor()?,
+    };
+
+    let timestamp_ms = now_ms();
+    if timestamp_ms < current.last_timestamp_ms {
+        tracing::warn!(
+            "clock-skew: history timestamp {timestamp_ms} is earlier than previous {}",
+            current.last_timestamp_ms
+        );
+        let _ = app.emit_event(
+            "clock-skew",
+            ClockSkew { previous_timestamp_ms: current.last_timestamp_ms, timestamp_ms },
+        );
+    }
+
+    let entry = HistoryEntry {
+        seq: current.next_seq,
+        timestamp_ms,
+        transcript: transcript.to_string(),
+        result: result.to_string(),
+    };
+
+    append_line(&Line::Entry(entry.clone()))?;
+
+    *cursor = Some(Cursor {
+        next_seq: entry.seq + 1,
+        last_timestamp_ms: timestamp_ms.max(current.last_timestamp_ms),
+    });
+    drop(cursor);
+
+    tray::rebuild_menu(app);
+    Ok(entry)
+}
+
+fn newest_first(mut entries: Vec<HistoryEntry>, limit: Option<usize>) -> Vec<HistoryEntry> {
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.seq));
+    if let Some(limit) = limit {
+        entries.truncate(limit);
+    }
+    entries
+}
+
+/// Newest entries, for the tray; unreadable lines are left out
+pub fn recent(limit: Option<usize>) -> Result<Vec<HistoryEntry>, String> {
+    read_entries().map(|entries| newest_first(entries, limit))
+}
+
+/// History newest first, ordered by sequence number rather than wall clock.
+/// Lines that can't be read are left out, with a `history-line-skipped` for
+/// each; `compact_history` drops them from the file, after they were backed up.
+#[tauri::command]
+pub fn get_history(app: AppHandle, limit: Option<usize>) -> Result<Vec<HistoryEntry>, String> {
+    let (lines, skipped) = read_lines_checked(&history_path()?)?;
+    for skipped in skipped {
+        let _ = app.emit_event("history-line-skipped", skipped);
+    }
+    Ok(newest_first(live_entries(lines), limit))
+}
+
+/// Remove one entry by its `seq`. A tombstone is appended rather than
+/// rewriting the file; `compact_history` clears them out.
+#[tauri::command]
+pub fn delete_history_entry(app: AppHandle, state: State<'_, HistoryState>, id: u64) -> Result<(), String> {
+    // Held so an append can't interleave with the check
+    let _cursor = state.cursor.lock().unwrap();
+    if !read_entries()?.iter().any(|entry| entry.seq == id) {
+        return Err(format!("History entry {id} does not exist"));
+    }
+    append_line(&Line::Deleted { deleted: id })?;
+    tray::rebuild_menu(&app);
+    Ok(())
+}
+
+/// Rewrite `history.jsonl` without deleted entries, keeping the newest
+/// `history_max_entries` (0 = all). The new file is written next to the old
+/// one and renamed over it, so a crash leaves one or the other intact.
+#[tauri::command]
+pub fn compact_history(app: AppHandle, state: State<'_, HistoryState>) -> Result<CompactSummary, String> {
+    let mut cursor = state.cursor.lock().unwrap();
+    let current = match *cursor {
+        Some(current) => current,
+        None => load_cursor()?,
+    };
+    let path = history_path()?;
+    let (lines, _) = read_lines_checked(&path)?;
+    let total = lines.iter().filter(|line| matches!(line, Line::Entry(_))).count();
+    let mut entries = live_entries(lines);
+    entries.sort_by_key(|entry| entry.seq);
+    let max = config::get(&app).history_max_entries;
+    if max > 0 && entries.len() > max {
+        entries.drain(..entries.len() - max);
+    }
+
+    let mut lines: Vec<Line> = entries.into_iter().map(Line::Entry).collect();
+    let kept = lines.len();
+    // A tombstone for the newest id ever handed out keeps it from being reused after a restart
+    let newest = current.next_seq.saturating_sub(1);
+    if !matches!(lines.last(), Some(Line::Entry(entry)) if entry.seq == newest) && newest > 0 {
+        lines.push(Line::Deleted { deleted: newest });
+    }
+    let mut content = String::new();
+    for line in &lines {
+        content.push_str(&serde_json::to_string(line).map_err(|e| e.to_string())?);
+        content.push('\n');
+    }
+    let temp = path.with_extension("jsonl.tmp");
+    let mut file = std::fs::File::create(&temp).map_err(|e| e.to_string())?;
+    file.write_all(content.as_bytes()).map_err(|e| e.to_string())?;
+    file.sync_all().map_err(|e| e.to_string())?;
+    drop(file);
+    std::fs::rename(&temp, &path).map_err(|e| format!("Could not replace {}: {e}", path.display()))?;
+
+    *cursor = Some(current);
+    drop(cursor);
+
+    tray::rebuild_menu(&app);
+    Ok(CompactSummary { kept, removed: total - kept })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{emitted, SCHEMA_VERSION};
+    use serde_json::json;
+
+    const CORRUPT: &str = include_str!("../tests/fixtures/history/corrupt.jsonl");
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ama-agent-test-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Contents of the backups in `dir`, sorted
+    fn backups(dir: &Path) -> Vec<String> {
+        let mut backups: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.file_name().unwrap().to_string_lossy().starts_with("history.jsonl.corrupt-"))
+            .map(|path| std::fs::read_to_string(path).unwrap())
+            .collect();
+        backups.sort();
+        backups
+    }
+
+    #[test]
+    fn good_entries_survive_corrupt_lines() {
+        let (lines, skipped) = parse_lines(CORRUPT);
+        let seqs: Vec<u64> = live_entries(lines).iter().map(|entry| entry.seq).collect();
+        assert_eq!(seqs, [1, 4]);
+        let skipped_lines: Vec<usize> = skipped.iter().map(|skipped| skipped.line).collect();
+        assert_eq!(skipped_lines, [2, 6]);
+    }
+
+    #[test]
+    fn corrupt_history_is_backed_up_aside() {
+        let dir = temp_dir("history-backup");
+        let path = dir.join("history.jsonl");
+        std::fs::write(&path, CORRUPT).unwrap();
+
+        read_lines_checked(&path).unwrap();
+        read_lines_checked(&path).unwrap();
+        assert_eq!(backups(&dir), [CORRUPT]);
+        // The original is read, not moved
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), CORRUPT);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn a_later_corruption_keeps_the_earlier_backup() {
+        let dir = temp_dir("history-second-backup");
+        let path = dir.join("history.jsonl");
+        std::fs::write(&path, CORRUPT).unwrap();
+        read_lines_checked(&path).unwrap();
+
+        let later = "{\"seq\":5,\"timestampMs\":5000,\"transcript\":\"fifth\",\"result\":\"five\"}\nnot json\n";
+        std::fs::write(&path, later).unwrap();
+        read_lines_checked(&path).unwrap();
+
+        let mut expected = [CORRUPT, later];
+        expected.sort();
+        assert_eq!(backups(&dir), expected);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn clean_history_is_not_backed_up() {
+        let dir = temp_dir("history-clean");
+        let path = dir.join("history.jsonl");
+        std::fs::write(&path, "{\"seq\":1,\"timestampMs\":1000,\"transcript\":\"a\",\"result\":\"b\"}\n").unwrap();
+
+        let (lines, skipped) = read_lines_checked(&path).unwrap();
+        assert_eq!(lines.len(), 1);
+        assert!(skipped.is_empty());
+        assert!(backups(&dir).is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn event_payloads_serialize_camel_case() {
+        assert_eq!(
+            emitted(SkippedLine { line: 2, error: "EOF".into() }),
+            json!({ "schemaVersion": SCHEMA_VERSION, "line": 2, "error": "EOF" })
+        );
+        assert_eq!(
+            emitted(ClockSkew { previous_timestamp_ms: 2000, timestamp_ms: 1000 }),
+            json!({ "schemaVersion": SCHEMA_VERSION, "previousTimestampMs": 2000, "timestampMs": 1000 })
+        );
+    }
+}
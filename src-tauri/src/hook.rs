@@ -0,0 +1,128 @@
+use futures_util::future;
+use std::io::ErrorKind;
+use std::process::Stdio;
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::activity;
+use crate::config::AppConfig;
+
+// Longest stderr excerpt copied into the activity log
+const MAX_STDERR_CHARS: usize = 500;
+
+#[cfg(target_os = "windows")]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+#[cfg(not(target_os = "windows"))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+async fn run(command: &str, text: &str, timeout: Duration) -> Result<(String, String), String> {
+    let mut child = shell(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to start: {e}"))?;
+    // Written alongside reading the output, so a hook that never reads its
+    // input (or floods stdout first) can't stall the write past the timeout.
+    // Dropping stdin once written tells the hook the input is complete.
+    let stdin = child.stdin.take();
+    let write = async move {
+        match stdin {
+            Some(mut stdin) => stdin.write_all(text.as_bytes()).await,
+            None => Ok(()),
+        }
+    };
+    let (written, output) = tokio::time::timeout(timeout, future::join(write, child.wait_with_output()))
+        .await
+        .map_err(|_| format!("Timed out after {}s", timeout.as_secs()))?;
+    let output = output.map_err(|e| e.to_string())?;
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().chars().take(MAX_STDERR_CHARS).collect();
+    if !output.status.success() {
+        return Err(format!("Exited with {}: {stderr}", output.status));
+    }
+    match written {
+        // The hook succeeded without reading all of its input, which is its call
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => {}
+        Err(e) => return Err(format!("Failed to write the result: {e}")),
+        Ok(()) => {}
+    }
+    Ok((stdout, stderr))
+}
+
+/// Pipe a finished result into `post_hook_command` through the system shell.
+/// This runs whatever command the user configured, with their permissions.
+/// Returns the hook's output when `post_hook_replaces_result` is on and the
+/// hook succeeded, otherwise `text` unchanged. Failures and anything the hook
+/// writes to stderr go to the activity log.
+pub async fn post_process(app: &AppHandle, config: &AppConfig, text: String) -> String {
+    let command = config.post_hook_command.trim();
+    if command.is_empty() {
+        return text;
+    }
+    match run(command, &text, Duration::from_secs(config.post_hook_timeout_secs)).await {
+        Ok((stdout, stderr)) => {
+            if !stderr.is_empty() {
+                activity::record(app, "hook", stderr, None);
+            }
+            if config.post_hook_replaces_result {
+                stdout.trim_end().to_string()
+            } else {
+                text
+            }
+        }
+        Err(e) => {
+            tracing::warn!("Post hook failed: {e}");
+            activity::record(app, "hook", format!("Post hook failed: {e}"), None);
+            text
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    // Well past any pipe buffer, so an unread write would block
+    fn large_input() -> String {
+        "x".repeat(4 * 1024 * 1024)
+    }
+
+    #[test]
+    fn passes_the_result_through_stdin() {
+        let output = tauri::async_runtime::block_on(run("tr a-z A-Z", "hello", Duration::from_secs(10)));
+        assert_eq!(output, Ok(("HELLO".to_string(), String::new())));
+    }
+
+    #[test]
+    fn times_out_on_a_hook_that_never_reads() {
+        let started = std::time::Instant::now();
+        let result = tauri::async_runtime::block_on(run("sleep 30", &large_input(), Duration::from_secs(1)));
+        assert_eq!(result, Err("Timed out after 1s".to_string()));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn ignores_unread_input_when_the_hook_succeeds() {
+        let result = tauri::async_runtime::block_on(run("echo done", &large_input(), Duration::from_secs(10)));
+        assert_eq!(result, Ok(("done\n".to_string(), String::new())));
+    }
+
+    #[test]
+    fn reports_a_failing_hook() {
+        let result = tauri::async_runtime::block_on(run("echo oops >&2; exit 3", "", Duration::from_secs(10)));
+        assert_eq!(result, Err("Exited with exit status: 3: oops".to_string()));
+    }
+}
@@ -0,0 +1,295 @@
+use regex::Regex;
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::{Certificate, Url};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::Semaphore;
+
+use crate::config::{self, AppConfig};
+use crate::error::AppError;
+use crate::events::{EmitEvent, Empty};
+
+// Headers providers use to identify a request for their support teams
+const REQUEST_ID_HEADERS: &[&str] = &["x-request-id", "request-id", "x-goog-request-id"];
+
+// Upper bound on simultaneous provider requests across all commands
+const MAX_CONCURRENT_REQUESTS: usize = 2;
+// Captured response bodies are cut off past this
+const MAX_CAPTURED_BODY_BYTES: usize = 64 * 1024;
+// Budget for the DNS lookup and TCP connect of the offline check
+const REACHABILITY_TIMEOUT: Duration = Duration::from_secs(2);
+// A host that answered is not checked again for this long
+const REACHABILITY_TTL: Duration = Duration::from_secs(30);
+
+// Things in a body that look like credentials: provider key formats, bearer
+// tokens and JSON fields named like secrets
+static SECRET_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)(sk-[a-z0-9_-]{8,}|AIza[0-9a-z_-]{20,}|pplx-[a-z0-9]{8,}|bearer\s+[a-z0-9._~+/=-]+|"[a-z_]*(key|token|secret)"\s*:\s*"[^"]*")"#)
+        .unwrap()
+});
+
+/// A provider response body kept for `get_last_raw_response`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawResponse {
+    pub provider: String,
+    pub status: u16,
+    pub body: String,
+    pub truncated: bool,
+}
+
+/// Blank out the configured API keys and anything else shaped like a secret
+fn scrub(config: &AppConfig, body: &str) -> String {
+    let mut config = config.clone();
+    let mut body = body.to_string();
+    for key in config::api_keys(&mut config).into_iter().filter(|key| !key.is_empty()) {
+        body = body.replace(key.as_str(), "********");
+    }
+    SECRET_PATTERN.replace_all(&body, "********").into_owned()
+}
+
+/// Overall cap on a transcription request
+pub fn whisper_timeout(config: &AppConfig) -> Duration {
+    Duration::from_secs(config.whisper_timeout_secs.unwrap_or(config.http_timeout_secs))
+}
+
+/// Overall cap on an LLM request. A streamed answer is also subject to
+/// `stream_idle_timeout_secs` between chunks; whichever runs out first wins.
+pub fn llm_timeout(config: &AppConfig) -> Duration {
+    Duration::from_secs(config.llm_timeout_secs.unwrap_or(config.http_timeout_secs))
+}
+
+/// Hosts on this machine or the local network, which don't need the internet
+fn is_local(host: &str) -> bool {
+    match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => ip.is_loopback() || ip.is_private() || ip.is_link_local(),
+        Ok(IpAddr::V6(ip)) => ip.is_loopback() || (ip.segments()[0] & 0xfe00) == 0xfc00,
+        Err(_) => host == "localhost" || host.ends_with(".local") || host.ends_with(".localhost"),
+    }
+}
+
+/// Whether requests to `url` go through a proxy from the environment, as
+/// reqwest picks them up. The host itself is then never connected to, so it
+/// can't be probed.
+fn uses_proxy(url: &Url, env: impl Fn(&str) -> Option<String>) -> bool {
+    let var = |name: &str| env(&name.to_uppercase()).or_else(|| env(name)).filter(|value| !value.trim().is_empty());
+    let proxy = match url.scheme() {
+        "https" => var("https_proxy"),
+        "http" => var("http_proxy"),
+        _ => None,
+    };
+    if proxy.or_else(|| var("all_proxy")).is_none() {
+        return false;
+    }
+    let host = url.host_str().unwrap_or_default().trim_matches(['[', ']']).to_ascii_lowercase();
+    let bypassed = var("no_proxy").is_some_and(|no_proxy| {
+        no_proxy.split(',').map(|entry| entry.trim().trim_start_matches("*.").trim_start_matches('.').to_ascii_lowercase()).any(|entry| {
+            entry == "*" || (!entry.is_empty() && (host == entry || host.ends_with(&format!(".{entry}"))))
+        })
+    });
+    !bypassed
+}
+
+/// Resolve `host` and open a TCP connection to it, within `REACHABILITY_TIMEOUT`
+async fn probe(host: &str, port: u16) -> bool {
+    let connect = async {
+        let address = tokio::net::lookup_host((host, port)).await.ok()?.next()?;
+        tokio::net::TcpStream::connect(address).await.ok()
+    };
+    matches!(tokio::time::timeout(REACHABILITY_TIMEOUT, connect).await, Ok(Some(_)))
+}
+
+/// With `fast_fail_offline`, check that `url`'s host resolves and accepts a
+/// connection before a request is made, failing at once with
+/// `Network("offline")` and an `offline` event when it doesn't. Local hosts and
+/// hosts reached through a proxy are never checked; hosts that answered
+/// recently aren't checked again.
+pub async fn ensure_online(app: &AppHandle, config: &AppConfig, url: &str) -> Result<(), AppError> {
+    if !config.fast_fail_offline {
+        return Ok(());
+    }
+    let Ok(url) = Url::parse(url) else {
+        return Ok(());
+    };
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return Ok(());
+    };
+    if is_local(host) || uses_proxy(&url, |name| std::env::var(name).ok()) {
+        return Ok(());
+    }
+    let key = format!("{host}:{port}");
+    let state = app.state::<HttpState>();
+    if state.reachable.lock().unwrap().get(&key).is_some_and(|checked| checked.elapsed() < REACHABILITY_TTL) {
+        return Ok(());
+    }
+    if probe(host, port).await {
+        state.reachable.lock().unwrap().insert(key, Instant::now());
+        return Ok(());
+    }
+    tracing::warn!("{key} is unreachable, failing fast");
+    let _ = app.emit_event("offline", Empty {});
+    Err(AppError::Network("offline".into()))
+}
+
+/// Shared HTTP client and the semaphore every provider request goes through
+pub struct HttpState {
+    // Rebuilt when the user agent or TLS settings change; clones share one pool
+    client: Mutex<reqwest::Client>,
+    pub semaphore: Semaphore,
+    // Request ID of the most recent provider response, for support tickets
+    pub last_request_id: Mutex<Option<String>>,
+    // Most recent response body, when `debug_capture_responses` is on
+    last_raw_response: Mutex<Option<RawResponse>>,
+    // host:port -> when it last passed the offline check
+    reachable: Mutex<HashMap<String, Instant>>,
+}
+
+impl Default for HttpState {
+    fn default() -> Self {
+        Self {
+            client: Mutex::new(reqwest::Client::new()),
+            semaphore: Semaphore::new(MAX_CONCURRENT_REQUESTS),
+            last_request_id: Mutex::new(None),
+            last_raw_response: Mutex::new(None),
+            reachable: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl HttpState {
+    pub fn client(&self) -> reqwest::Client {
+        self.client.lock().unwrap().clone()
+    }
+
+    /// Rebuild the client with the app's User-Agent (or `user_agent` when set),
+    /// an `X-Client-Version` header on every request, and the TLS settings
+    pub fn configure(&self, app: &AppHandle, config: &AppConfig) {
+        let version = app.package_info().version.to_string();
+        let user_agent = if config.user_agent.is_empty() {
+            format!("ama-agent/{version} ({})", std::env::consts::OS)
+        } else {
+            config.user_agent.clone()
+        };
+
+        let mut headers = HeaderMap::new();
+        if let Ok(value) = HeaderValue::from_str(&version) {
+            headers.insert("x-client-version", value);
+        }
+        let mut builder = reqwest::Client::builder().user_agent(user_agent).default_headers(headers);
+        if !config.ca_cert_path.is_empty() {
+            match load_certificates(&config.ca_cert_path) {
+                Ok(certificates) => {
+                    for certificate in certificates {
+                        builder = builder.add_root_certificate(certificate);
+                    }
+                }
+                Err(e) => tracing::error!("Ignoring custom CA certificate: {e}"),
+            }
+        }
+        if config.danger_accept_invalid_certs {
+            tracing::warn!("TLS certificate verification is DISABLED; provider traffic can be intercepted");
+            let _ = app.emit_event("tls-verification-disabled", Empty {});
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+        match builder.build() {
+            Ok(client) => *self.client.lock().unwrap() = client,
+            Err(e) => tracing::error!("Failed to configure HTTP client: {e}"),
+        }
+    }
+
+    /// Read the body of `response` as text. With `debug_capture_responses` on,
+    /// a scrubbed copy of at most `MAX_CAPTURED_BODY_BYTES` is kept for
+    /// `get_last_raw_response`.
+    pub async fn read_body(&self, config: &AppConfig, provider: &str, response: reqwest::Response) -> reqwest::Result<String> {
+        let status = response.status().as_u16();
+        let body = response.text().await?;
+        if config.debug_capture_responses {
+            let mut captured = scrub(config, &body);
+            let truncated = captured.len() > MAX_CAPTURED_BODY_BYTES;
+            if truncated {
+                let end = (0..=MAX_CAPTURED_BODY_BYTES).rev().find(|end| captured.is_char_boundary(*end)).unwrap_or(0);
+                captured.truncate(end);
+            }
+            let raw = RawResponse { provider: provider.to_string(), status, body: captured, truncated };
+            *self.last_raw_response.lock().unwrap() = Some(raw);
+        }
+        Ok(body)
+    }
+
+    /// Remember and log the provider's request ID for `response`, if it sent one
+    pub fn record_request_id(&self, provider: &str, response: &reqwest::Response) -> Option<String> {
+        let request_id = REQUEST_ID_HEADERS
+            .iter()
+            .find_map(|name| response.headers().get(*name)?.to_str().ok())
+            .map(str::to_string)?;
+        tracing::info!("{provider} responded {} (request id {request_id})", response.status());
+        *self.last_request_id.lock().unwrap() = Some(request_id.clone());
+        Some(request_id)
+    }
+}
+
+#[tauri::command]
+pub fn get_last_request_id(http: State<'_, HttpState>) -> Option<String> {
+    http.last_request_id.lock().unwrap().clone()
+}
+
+/// The last provider response body, scrubbed of secrets. Only recorded while
+/// `debug_capture_responses` is on.
+#[tauri::command]
+pub fn get_last_raw_response(http: State<'_, HttpState>) -> Option<RawResponse> {
+    http.last_raw_response.lock().unwrap().clone()
+}
+
+/// Every certificate in a PEM file, trusted in addition to the system roots
+pub fn load_certificates(path: &str) -> Result<Vec<Certificate>, String> {
+    let pem = std::fs::read(path).map_err(|e| format!("{path}: {e}"))?;
+    let certificates = Certificate::from_pem_bundle(&pem).map_err(|e| format!("{path}: {e}"))?;
+    if certificates.is_empty() {
+        return Err(format!("{path} has no PEM certificates"));
+    }
+    Ok(certificates)
+}
+
+/// Apply the current config to the shared client
+pub fn configure(app: &AppHandle, config: &AppConfig) {
+    app.state::<HttpState>().configure(app, config);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proxied(url: &str, vars: &[(&str, &str)]) -> bool {
+        let vars: HashMap<&str, &str> = vars.iter().copied().collect();
+        uses_proxy(&Url::parse(url).unwrap(), |name| vars.get(name).map(|value| value.to_string()))
+    }
+
+    #[test]
+    fn no_proxy_configured() {
+        assert!(!proxied("https://api.openai.com/v1/chat/completions", &[]));
+        assert!(!proxied("https://api.openai.com/", &[("HTTPS_PROXY", " ")]));
+    }
+
+    #[test]
+    fn proxy_matches_the_scheme() {
+        assert!(proxied("https://api.openai.com/", &[("HTTPS_PROXY", "http://proxy:3128")]));
+        assert!(proxied("https://api.openai.com/", &[("https_proxy", "http://proxy:3128")]));
+        assert!(!proxied("https://api.openai.com/", &[("HTTP_PROXY", "http://proxy:3128")]));
+        assert!(proxied("http://api.openai.com/", &[("http_proxy", "http://proxy:3128")]));
+        assert!(proxied("https://api.openai.com/", &[("ALL_PROXY", "socks5://proxy:1080")]));
+    }
+
+    #[test]
+    fn no_proxy_hosts_are_still_probed() {
+        let proxy = ("HTTPS_PROXY", "http://proxy:3128");
+        assert!(!proxied("https://api.openai.com/", &[proxy, ("NO_PROXY", "example.com, openai.com")]));
+        assert!(!proxied("https://api.openai.com/", &[proxy, ("no_proxy", ".openai.com")]));
+        assert!(!proxied("https://api.openai.com/", &[proxy, ("NO_PROXY", "*")]));
+        assert!(proxied("https://api.openai.com/", &[proxy, ("NO_PROXY", "notopenai.com")]));
+        assert!(proxied("https://api.openai.com/", &[proxy, ("NO_PROXY", "")]));
+    }
+}
@@ -0,0 +1,76 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::config;
+use crate::events::EmitEvent;
+use crate::notify;
+use crate::tray::{self, TrayState};
+
+// How often the idle timer is checked
+const IDLE_CHECK_SECS: u64 = 30;
+// Time between `idle-quitting` and the actual quit, for a "stay open" answer
+const QUIT_GRACE_SECS: u64 = 30;
+
+/// When the user last did anything, for `idle_quit_minutes`
+pub struct IdleState {
+    last_activity: Mutex<Instant>,
+}
+
+impl Default for IdleState {
+    fn default() -> Self {
+        Self { last_activity: Mutex::new(Instant::now()) }
+    }
+}
+
+/// Payload of `idle-quitting`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IdleQuitting {
+    grace_secs: u64,
+}
+
+/// Restart the idle timer
+pub fn touch(app: &AppHandle) {
+    *app.state::<IdleState>().last_activity.lock().unwrap() = Instant::now();
+}
+
+fn idle_for(app: &AppHandle) -> Duration {
+    app.state::<IdleState>().last_activity.lock().unwrap().elapsed()
+}
+
+/// Keep the app running after `idle-quitting`; counts as activity
+#[tauri::command]
+pub fn stay_open(app: AppHandle) {
+    touch(&app);
+}
+
+/// Quit once nothing has happened for `idle_quit_minutes` (0 = never). A
+/// recording or request in progress always counts as activity.
+pub fn watch(app: AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_secs(IDLE_CHECK_SECS));
+        let limit = Duration::from_secs(config::get(&app).idle_quit_minutes.saturating_mul(60));
+        if limit.is_zero() {
+            continue;
+        }
+        if tray::current_state(&app) != TrayState::Idle {
+            touch(&app);
+            continue;
+        }
+        if idle_for(&app) < limit {
+            continue;
+        }
+
+        tracing::info!("Idle for {} minutes, quitting in {QUIT_GRACE_SECS}s", limit.as_secs() / 60);
+        let _ = app.emit_event("idle-quitting", IdleQuitting { grace_secs: QUIT_GRACE_SECS });
+        notify::hint(&app, "Quitting soon because AMA Agent has been idle");
+        thread::sleep(Duration::from_secs(QUIT_GRACE_SECS));
+        if idle_for(&app) >= limit && tray::current_state(&app) == TrayState::Idle {
+            app.exit(0);
+            return;
+        }
+    });
+}
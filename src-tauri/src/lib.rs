@@ -1,157 +1,298 @@
-use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
-use tauri::{
-    menu::{Menu, MenuItem},
-    tray::TrayIconBuilder,
-    Emitter,
-    Manager,
-    RunEvent,
-    WindowEvent,
-};
-use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut};
+mod activity;
+mod attachment;
+mod audio;
+mod autohide;
+mod batch;
+mod benchmark;
+mod cache;
+mod cancel;
+mod capabilities;
+mod circuit;
+mod clipboard;
+mod config;
+mod diagnostics;
+mod error;
+mod events;
+mod foreground;
+mod fullscreen;
+mod handoff;
+mod headless;
+mod history;
+mod hook;
+mod http;
+mod idle;
+mod llm;
+mod logging;
+mod notify;
+mod onboarding;
+mod palette;
+mod permission;
+mod pipeline;
+mod preset;
+mod profile;
+mod progress;
+mod readiness;
+mod realtime;
+mod replace;
+mod repeat;
+mod reveal;
+mod shortcut;
+mod sink;
+mod snapshot;
+mod stats;
+mod stream;
+mod template;
+mod tokens;
+mod tray;
+mod typing;
+mod upload;
+mod whisper;
+mod window;
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod x11;
 
-// Debounce duration for global shortcut (prevents spam when key is held)
-const SHORTCUT_DEBOUNCE_MS: u64 = 300;
+use std::sync::Mutex;
+use tauri::{DragDropEvent, Manager, RunEvent, WindowEvent};
 
-#[tauri::command]
-fn get_config() -> Result<serde_json::Value, String> {
-    let config_path = dirs::config_dir()
-        .ok_or("Could not find config directory")?
-        .join("ama-agent")
-        .join("config.json");
-
-    if config_path.exists() {
-        let content = std::fs::read_to_string(&config_path).map_err(|e| e.to_string())?;
-        serde_json::from_str(&content).map_err(|e| e.to_string())
-    } else {
-        Ok(serde_json::json!({
-            "whisperUrl": "https://api.openai.com/v1/audio/transcriptions",
-            "whisperApiKey": "",
-            "llmProvider": "openai",
-            "llmApiKey": ""
-        }))
-    }
-}
+use audio::AudioState;
+use autohide::AutoHideState;
+use cancel::CancelState;
+use events::EmitEvent;
+use history::HistoryState;
+use http::HttpState;
+use onboarding::OnboardingState;
+use tray::TrayState;
 
+/// Hide the main window. By default a running recording keeps going and the
+/// tray stays in recording mode with its Stop Recording item; with
+/// `refuse_hide_while_recording` set it fails with `recording-active` instead.
 #[tauri::command]
-fn save_config(config: serde_json::Value) -> Result<(), String> {
-    let config_dir = dirs::config_dir()
-        .ok_or("Could not find config directory")?
-        .join("ama-agent");
-
-    std::fs::create_dir_all(&config_dir).map_err(|e| e.to_string())?;
-
-    let config_path = config_dir.join("config.json");
-    let content = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
-    std::fs::write(&config_path, content).map_err(|e| e.to_string())?;
-
-    Ok(())
-}
-
-#[tauri::command]
-fn hide_to_tray(window: tauri::Window) -> Result<(), String> {
-    window.emit("window-hidden", ()).map_err(|e| e.to_string())?;
-    window.hide().map_err(|e| e.to_string())?;
-    Ok(())
+fn hide_to_tray(app: tauri::AppHandle) -> Result<(), String> {
+    if pipeline::is_recording(app.clone()) && config::get(&app).refuse_hide_while_recording {
+        return Err("recording-active".into());
+    }
+    window::hide_main(&app)
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // Track last shortcut activation time for debounce
-    let last_shortcut_time: Arc<Mutex<Instant>> = Arc::new(Mutex::new(Instant::now() - Duration::from_secs(1)));
-
-    let shortcut_time_clone = last_shortcut_time.clone();
+    config::init_path_override();
+    logging::init();
+    if let Some(path) = config::path_override() {
+        tracing::info!("Using config file {}", path.display());
+    }
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
-        .setup(move |app| {
-            // Create tray menu
-            let show_item = MenuItem::with_id(app, "show", "Show", true, None::<&str>)?;
-            let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-            let menu = Menu::with_items(app, &[&show_item, &quit_item])?;
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_notification::init())
+        .manage(CancelState::default())
+        .manage(AudioState::default())
+        .manage(HttpState::default())
+        .manage(HistoryState::default())
+        .manage(OnboardingState::default())
+        .manage(readiness::ReadinessState::default())
+        .manage(AutoHideState::default())
+        .manage(shortcut::RegistrationState::default())
+        .manage(shortcut::DoNotDisturb::default())
+        .manage(realtime::RealtimeState::default())
+        .manage(activity::ActivityState::default())
+        .manage(stats::SessionStats::default())
+        .manage(progress::ProgressState::default())
+        .manage(profile::ProfileState::default())
+        .manage(pipeline::DuplicateState::default())
+        .manage(idle::IdleState::default())
+        .manage(circuit::CircuitState::default())
+        .manage(repeat::LastActionState::default())
+        .manage(config::RememberState::default())
+        .manage(error::LastErrorState::default())
+        .manage(Mutex::new(TrayState::Idle))
+        .setup(|app| {
+            if let Err(e) = config::ensure_dirs() {
+                tracing::error!("{e}");
+            }
+
+            // Guess the transcription language from the system locale on first run
+            match config::seed_first_run_defaults() {
+                Ok(Some(language)) => {
+                    let _ = app.emit_event("default-language-detected", events::LanguageDetected { language });
+                }
+                Ok(None) => {}
+                Err(e) => tracing::error!("Failed to write first-run config: {e}"),
+            }
 
-            // Build tray icon with app icon
-            let _tray = TrayIconBuilder::new()
-                .icon(app.default_window_icon().unwrap().clone())
-                .menu(&menu)
-                .on_menu_event(|app, event| match event.id.as_ref() {
-                    "show" => {
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                        }
-                    }
-                    "quit" => {
-                        app.exit(0);
-                    }
-                    _ => {}
-                })
-                .on_tray_icon_event(|tray, event| {
-                    if let tauri::tray::TrayIconEvent::DoubleClick { .. } = event {
-                        if let Some(window) = tray.app_handle().get_webview_window("main") {
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                            let _ = window.emit("window-shown", ());
-                        }
-                    }
-                })
-                .build(app)?;
+            let config = config::load().unwrap_or_else(|e| {
+                tracing::error!("Failed to load config, using defaults: {e}");
+                config::AppConfig::default()
+            });
+            app.manage(config::ConfigState::new(config.clone()));
+            headless::init(&config);
+            http::configure(app.handle(), &config);
+            llm::autoselect_provider(app.handle());
+            if config.show_tray && !headless::active() {
+                tray::create(app.handle())?;
+            }
 
-            // Register global shortcut: Ctrl+Shift+Space with debounce
-            let shortcut = Shortcut::new(Some(Modifiers::CONTROL | Modifiers::SHIFT), Code::Space);
-            let app_handle = app.handle().clone();
-            let shortcut_time = shortcut_time_clone.clone();
+            // Register global shortcuts from config (Ctrl+Shift+Space by default)
+            if let Err(e) = shortcut::register_all(app.handle(), &config) {
+                tracing::warn!("Falling back to default shortcuts: {e}");
+                shortcut::register_all(app.handle(), &config::AppConfig::default())?;
+            }
 
-            app.global_shortcut().on_shortcut(shortcut, move |_app, _shortcut, _event| {
-                // Debounce check
-                let now = Instant::now();
-                {
-                    let mut last_time = shortcut_time.lock().unwrap();
-                    if now.duration_since(*last_time) < Duration::from_millis(SHORTCUT_DEBOUNCE_MS) {
-                        return; // Ignore - too soon since last activation
-                    }
-                    *last_time = now;
-                }
+            shortcut::watch_resume(app.handle().clone());
+            idle::watch(app.handle().clone());
+            onboarding::refresh(app.handle());
+            readiness::refresh(app.handle());
+            readiness::watch(app.handle().clone());
 
-                if let Some(window) = app_handle.get_webview_window("main") {
-                    // Toggle window visibility
-                    if window.is_visible().unwrap_or(false) {
-                        // Window is visible - emit action event to let frontend handle based on state
-                        let _ = window.emit("shortcut-action", ());
-                    } else {
-                        // Show window and emit event to start recording
-                        let _ = window.show();
-                        let _ = window.set_focus();
-                        let _ = window.emit("window-shown", ());
-                    }
-                }
-            })?;
+            if config.prewarm_llm {
+                tauri::async_runtime::spawn(benchmark::warm_llm(app.handle().clone()));
+            }
+
+            if headless::active() {
+                headless::start(app.handle());
+                shortcut::mark_ready(app.handle());
+                return Ok(());
+            }
 
-            // Show window on startup in dev mode
-            #[cfg(debug_assertions)]
             if let Some(window) = app.get_webview_window("main") {
-                let _ = window.show();
+                let _ = window.set_always_on_top(config.always_on_top);
             }
 
+            // Show window on startup in dev mode, or always when there is no tray to open it from
+            if cfg!(debug_assertions) || !config.show_tray {
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.show();
+                }
+            }
+
+            shortcut::mark_ready(app.handle());
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![get_config, save_config, hide_to_tray])
+        .invoke_handler(tauri::generate_handler![
+            config::get_config,
+            config::save_config,
+            config::validate_config,
+            config::get_effective_config,
+            hide_to_tray,
+            window::recenter_window,
+            window::open_editor_window,
+            window::list_monitors,
+            window::navigate,
+            cancel::cancel_all,
+            http::get_last_request_id,
+            http::get_last_raw_response,
+            error::get_last_errors,
+            tokens::estimate_tokens,
+            tray::set_tray_state,
+            llm::get_active_model,
+            llm::set_active_model,
+            llm::preview_llm_request,
+            llm::rotate_secret,
+            tray::rebuild_tray_menu,
+            autohide::result_delivered,
+            autohide::user_activity,
+            idle::stay_open,
+            shortcut::parse_accelerator,
+            shortcut::capture_next_shortcut,
+            shortcut::list_registered_shortcuts,
+            shortcut::unregister_all_shortcuts,
+            shortcut::trigger_shortcut,
+            shortcut::set_do_not_disturb,
+            shortcut::get_do_not_disturb,
+            batch::transcribe_batch,
+            clipboard::query_from_clipboard,
+            sink::get_result_sinks,
+            sink::set_result_sinks,
+            sink::test_delivery,
+            repeat::repeat_last_action,
+            pipeline::get_pipeline_state,
+            pipeline::is_recording,
+            pipeline::transcribe,
+            pipeline::query_llm,
+            pipeline::query_llm_stream,
+            pipeline::query_llm_with_image,
+            pipeline::transcribe_and_process,
+            pipeline::reprocess_transcript,
+            pipeline::stop_recording_and_process,
+            history::get_history,
+            history::delete_history_entry,
+            history::compact_history,
+            cache::clear_response_cache,
+            activity::get_session_log,
+            stats::get_session_stats,
+            stats::reset_session_stats,
+            snapshot::export_state,
+            snapshot::import_state,
+            reveal::reveal_in_file_manager,
+            handoff::open_result_in,
+            audio::start_recording,
+            audio::stop_recording,
+            audio::start_mic_monitor,
+            audio::stop_mic_monitor,
+            audio::set_passive_buffer,
+            audio::get_passive_buffer,
+            audio::save_passive_buffer,
+            audio::calibrate_input,
+            audio::probe_input_device,
+            audio::force_release_audio,
+            audio::reset_audio,
+            realtime::start_realtime_transcription,
+            realtime::stop_realtime_transcription,
+            realtime::stop_realtime_and_process,
+            realtime::start_dictation,
+            realtime::stop_dictation,
+            benchmark::benchmark_models,
+            benchmark::test_llm,
+            benchmark::test_whisper,
+            benchmark::warm_llm,
+            onboarding::get_onboarding_status,
+            onboarding::import_from_env,
+            readiness::check_ready_to_record,
+            preset::list_presets,
+            preset::save_preset,
+            preset::apply_preset,
+            palette::list_commands,
+            palette::run_command,
+            capabilities::get_capabilities,
+            diagnostics::export_diagnostics,
+            diagnostics::run_diagnostics,
+            logging::get_logs_dir,
+            logging::get_log_level,
+            logging::set_log_level,
+            permission::check_mic_permission,
+            permission::request_mic_permission
+        ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
         .run(|app_handle, event| {
-            // Handle window close request - hide to tray instead of closing
-            if let RunEvent::WindowEvent { label, event: WindowEvent::CloseRequested { api, .. }, .. } = event {
-                if label == "main" {
+            match event {
+                // Handle window close request - hide to tray instead of closing. Without
+                // a tray the close goes through and quits the app like a normal window.
+                // Other windows such as the editor always close normally.
+                RunEvent::WindowEvent { label, event: WindowEvent::CloseRequested { api, .. }, .. }
+                    if label == "main" && app_handle.tray_by_id(tray::TRAY_ID).is_some() =>
+                {
                     // Prevent the window from being closed
                     api.prevent_close();
-                    // Hide window and emit event
-                    if let Some(window) = app_handle.get_webview_window("main") {
-                        let _ = window.emit("window-hidden", ());
-                        let _ = window.hide();
-                    }
+                    let _ = window::hide_main(app_handle);
+                }
+                // With no window left, a headless run keeps going until it is told to quit
+                RunEvent::ExitRequested { code: None, api, .. } if headless::active() => api.prevent_exit(),
+                RunEvent::WindowEvent { label, event: WindowEvent::DragDrop(DragDropEvent::Drop { paths, .. }), .. }
+                    if label == "main" =>
+                {
+                    batch::on_drop(app_handle, paths);
+                }
+                RunEvent::WindowEvent { label, event: WindowEvent::ThemeChanged(_), .. } if label == "main" => {
+                    tray::refresh_icon(app_handle);
                 }
+                RunEvent::WindowEvent { label, event: WindowEvent::Focused(focused), .. } if label == "main" => {
+                    autohide::on_focus_changed(app_handle, focused);
+                    audio::on_focus_changed(app_handle, focused);
+                }
+                _ => {}
             }
         });
 }
-
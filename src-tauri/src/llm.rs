@@ -0,0 +1,795 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio_util::sync::CancellationToken;
+
+use crate::attachment::ImageAttachment;
+use crate::cache;
+use crate::cancel;
+use crate::circuit;
+use crate::config::{self, AppConfig};
+use crate::error::AppError;
+use crate::events::{EmitEvent, ProviderAutoselected, ProviderChoiceNeeded, Text};
+use crate::http::{self, HttpState};
+use crate::readiness;
+use crate::stream::{self, Delta};
+use crate::template;
+use crate::tokens;
+use crate::tray;
+
+// Primary plus fallbacks; later entries in `llm_fallback` are ignored
+const MAX_ATTEMPTS: usize = 3;
+// OpenAI's limits on `metadata`, applied to every provider so tags stay portable
+const MAX_REQUEST_TAGS: usize = 16;
+const MAX_TAG_KEY_CHARS: usize = 64;
+const MAX_TAG_VALUE_CHARS: usize = 512;
+const MAX_MODEL_CHARS: usize = 128;
+
+/// Provider/model pair from `llm_fallback`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FallbackTarget {
+    pub provider: String,
+    // Provider default when unset
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    OpenAi,
+    Claude,
+    Gemini,
+    Perplexity,
+}
+
+impl Provider {
+    pub const ALL: [Provider; 4] = [Provider::OpenAi, Provider::Claude, Provider::Gemini, Provider::Perplexity];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Provider::OpenAi => "openai",
+            Provider::Claude => "claude",
+            Provider::Gemini => "gemini",
+            Provider::Perplexity => "perplexity",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self, AppError> {
+        Provider::ALL
+            .into_iter()
+            .find(|provider| provider.name() == name)
+            .ok_or_else(|| AppError::Config(format!("Unknown LLM provider \"{name}\"")))
+    }
+
+    pub fn default_model(self) -> &'static str {
+        match self {
+            Provider::OpenAi => "gpt-5-nano-2025-08-07",
+            Provider::Claude => "claude-opus-4-5-20251101",
+            Provider::Gemini => "gemini-3-flash-preview",
+            Provider::Perplexity => "llama-3.1-sonar-large-128k-online",
+        }
+    }
+
+    /// Offered in the tray's model menu; any other model the provider knows
+    /// can still be set
+    pub fn suggested_models(self) -> &'static [&'static str] {
+        match self {
+            Provider::OpenAi => &["gpt-5-nano-2025-08-07", "gpt-5-mini", "gpt-5", "gpt-4.1", "gpt-4o-mini"],
+            Provider::Claude => &["claude-opus-4-5-20251101", "claude-sonnet-4-5", "claude-haiku-4-5"],
+            Provider::Gemini => &["gemini-3-flash-preview", "gemini-2.5-pro", "gemini-2.5-flash"],
+            Provider::Perplexity => &["llama-3.1-sonar-large-128k-online", "sonar", "sonar-pro"],
+        }
+    }
+
+    pub fn api_key(self, config: &AppConfig) -> &str {
+        match self {
+            Provider::OpenAi => &config.openai_api_key,
+            Provider::Claude => &config.anthropic_api_key,
+            Provider::Gemini => &config.gemini_api_key,
+            Provider::Perplexity => &config.llm_api_key,
+        }
+    }
+
+    /// How the provider's keys start, for catching keys pasted into the wrong field
+    fn key_prefix(self) -> &'static str {
+        match self {
+            Provider::OpenAi => "sk-",
+            Provider::Claude => "sk-ant-",
+            Provider::Gemini => "AIza",
+            Provider::Perplexity => "pplx-",
+        }
+    }
+
+    /// Config field holding the provider's key, as the frontend names it
+    fn key_field(self) -> &'static str {
+        match self {
+            Provider::OpenAi => "openaiApiKey",
+            Provider::Claude => "anthropicApiKey",
+            Provider::Gemini => "geminiApiKey",
+            Provider::Perplexity => "llmApiKey",
+        }
+    }
+
+    /// Whether `model` accepts image input
+    pub fn supports_images(self, model: &str) -> bool {
+        match self {
+            Provider::OpenAi => !model.starts_with("gpt-3.5"),
+            Provider::Claude | Provider::Gemini => true,
+            Provider::Perplexity => false,
+        }
+    }
+
+    /// Scheme and host of the provider's API, for connection warm-up
+    pub fn origin(self) -> &'static str {
+        match self {
+            Provider::OpenAi => "https://api.openai.com",
+            Provider::Claude => "https://api.anthropic.com",
+            Provider::Gemini => "https://generativelanguage.googleapis.com",
+            Provider::Perplexity => "https://api.perplexity.ai",
+        }
+    }
+
+    fn url(self, model: &str, stream: bool) -> String {
+        match self {
+            Provider::OpenAi => "https://api.openai.com/v1/chat/completions".into(),
+            Provider::Claude => "https://api.anthropic.com/v1/messages".into(),
+            Provider::Gemini if stream => {
+                format!("https://generativelanguage.googleapis.com/v1beta/models/{model}:streamGenerateContent?alt=sse")
+            }
+            Provider::Gemini => format!("https://generativelanguage.googleapis.com/v1beta/models/{model}:generateContent"),
+            Provider::Perplexity => "https://api.perplexity.ai/chat/completions".into(),
+        }
+    }
+}
+
+/// The user turn in each provider's format, with the image as an extra part when given
+fn user_content(provider: Provider, prompt: &str, image: Option<&ImageAttachment>) -> Value {
+    let Some(image) = image else {
+        return match provider {
+            Provider::Gemini => json!([{ "text": prompt }]),
+            _ => json!(prompt),
+        };
+    };
+    match provider {
+        Provider::OpenAi | Provider::Perplexity => json!([
+            { "type": "text", "text": prompt },
+            { "type": "image_url", "image_url": { "url": format!("data:{};base64,{}", image.media_type, image.data) } },
+        ]),
+        Provider::Claude => json!([
+            { "type": "image", "source": { "type": "base64", "media_type": image.media_type, "data": image.data } },
+            { "type": "text", "text": prompt },
+        ]),
+        Provider::Gemini => json!([
+            { "text": prompt },
+            { "inline_data": { "mime_type": image.media_type, "data": image.data } },
+        ]),
+    }
+}
+
+fn request_body(provider: Provider, config: &AppConfig, model: &str, prompt: &str, image: Option<&ImageAttachment>) -> Value {
+    let system_prompt = Some(config.system_prompt.as_str()).filter(|p| !p.is_empty());
+    let content = user_content(provider, prompt, image);
+
+    let mut body = match provider {
+        Provider::OpenAi | Provider::Perplexity => {
+            let mut messages = Vec::new();
+            if let Some(system_prompt) = system_prompt {
+                messages.push(json!({ "role": "system", "content": system_prompt }));
+            }
+            messages.push(json!({ "role": "user", "content": content }));
+            json!({ "model": model, "messages": messages })
+        }
+        Provider::Claude => json!({
+            "model": model,
+            "max_tokens": 4096,
+            "messages": [{ "role": "user", "content": content }],
+        }),
+        Provider::Gemini => json!({
+            "contents": [{ "parts": content }],
+        }),
+    };
+
+    if provider == Provider::OpenAi {
+        body["reasoning_effort"] = json!("low");
+    }
+    if let Some(system_prompt) = system_prompt {
+        match provider {
+            Provider::Claude => body["system"] = json!(system_prompt),
+            Provider::Gemini => body["systemInstruction"] = json!({ "parts": [{ "text": system_prompt }] }),
+            Provider::OpenAi | Provider::Perplexity => {}
+        }
+    }
+    if let Some(temperature) = config.llm_temperature {
+        match provider {
+            Provider::Gemini => body["generationConfig"] = json!({ "temperature": temperature }),
+            _ => body["temperature"] = json!(temperature),
+        }
+    }
+
+    body
+}
+
+fn parse_response(provider: Provider, data: &Value) -> String {
+    match provider {
+        Provider::OpenAi | Provider::Perplexity => data["choices"][0]["message"]["content"].as_str().unwrap_or_default().to_string(),
+        Provider::Claude => data["content"]
+            .as_array()
+            .map(|blocks| {
+                blocks
+                    .iter()
+                    .filter(|block| block["type"] == "text")
+                    .filter_map(|block| block["text"].as_str())
+                    .collect::<String>()
+            })
+            .unwrap_or_default(),
+        Provider::Gemini => data["candidates"][0]["content"]["parts"][0]["text"].as_str().unwrap_or_default().to_string(),
+    }
+}
+
+/// When the selected provider has no key, switch to the only provider that has
+/// one (`provider-autoselected`), or list the candidates if there are several
+/// (`provider-choice-needed`) and leave the choice to the user
+pub fn autoselect_provider(app: &AppHandle) {
+    let config = config::get(app);
+    if Provider::from_name(&config.llm_provider).is_ok_and(|provider| !provider.api_key(&config).is_empty()) {
+        return;
+    }
+
+    let ready: Vec<&'static str> =
+        Provider::ALL.into_iter().filter(|provider| !provider.api_key(&config).is_empty()).map(Provider::name).collect();
+    match ready.as_slice() {
+        [] => {}
+        [provider] => {
+            if let Err(e) = config::modify(app, |config| config.llm_provider = provider.to_string()) {
+                tracing::error!("Failed to save auto-selected provider: {e}");
+                return;
+            }
+            let _ = app.emit_event("provider-autoselected", ProviderAutoselected { provider });
+        }
+        providers => {
+            let _ = app.emit_event("provider-choice-needed", ProviderChoiceNeeded { providers });
+        }
+    }
+}
+
+/// The configured provider and the model it answers with
+pub fn active_model(config: &AppConfig) -> Option<(Provider, String)> {
+    let provider = Provider::from_name(&config.llm_provider).ok()?;
+    let model = config.llm_model.clone().unwrap_or_else(|| provider.default_model().to_string());
+    Some((provider, model))
+}
+
+/// Returned by `get_active_model`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveModel {
+    provider: &'static str,
+    model: String,
+    // No `llm_model` is set, so the provider's default is used
+    is_default: bool,
+    suggestions: &'static [&'static str],
+}
+
+/// Payload of `model-changed`
+#[derive(Debug, Clone, Serialize)]
+struct ModelChanged<'a> {
+    provider: &'static str,
+    model: &'a str,
+}
+
+/// Model ids are short and URL-safe (Gemini puts them in the path), so
+/// anything else is a typo or a pasted sentence
+fn check_model_name(model: &str) -> Result<(), AppError> {
+    if model.is_empty() || model.len() > MAX_MODEL_CHARS {
+        return Err(AppError::Config(format!("A model name must be 1-{MAX_MODEL_CHARS} characters")));
+    }
+    if let Some(c) = model.chars().find(|c| !c.is_ascii_alphanumeric() && !"-._:/@".contains(*c)) {
+        return Err(AppError::Config(format!("\"{model}\" is not a model name: it contains {c:?}")));
+    }
+    Ok(())
+}
+
+/// Make `model` the default for the configured provider and save it
+pub fn set_model(app: &AppHandle, model: &str) -> Result<(), AppError> {
+    let model = model.trim();
+    check_model_name(model)?;
+    let provider = Provider::from_name(&config::get(app).llm_provider)?;
+    config::modify(app, |config| config.llm_model = Some(model.to_string())).map_err(AppError::Config)?;
+    let _ = app.emit_event("model-changed", ModelChanged { provider: provider.name(), model });
+    tray::refresh_tooltip(app);
+    tray::rebuild_menu(app);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_active_model(app: AppHandle) -> Result<ActiveModel, AppError> {
+    let config = config::get(&app);
+    let provider = Provider::from_name(&config.llm_provider)?;
+    Ok(ActiveModel {
+        provider: provider.name(),
+        model: config.llm_model.clone().unwrap_or_else(|| provider.default_model().to_string()),
+        is_default: config.llm_model.is_none(),
+        suggestions: provider.suggested_models(),
+    })
+}
+
+/// Switch the model of the configured provider without going through the settings
+#[tauri::command]
+pub fn set_active_model(app: AppHandle, model: String) -> Result<(), AppError> {
+    set_model(&app, &model)
+}
+
+/// `llm_extra_params` must be a JSON object to be merged into request bodies
+pub fn validate(config: &AppConfig) -> Result<(), String> {
+    if !config.llm_extra_params.is_object() {
+        return Err("Extra LLM parameters must be a JSON object".into());
+    }
+    if config.request_tags.len() > MAX_REQUEST_TAGS {
+        return Err(format!("At most {MAX_REQUEST_TAGS} request tags are allowed"));
+    }
+    for (key, value) in &config.request_tags {
+        if key.is_empty() || key.len() > MAX_TAG_KEY_CHARS || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            return Err(format!("Request tag \"{key}\" must be 1-{MAX_TAG_KEY_CHARS} letters, digits, '_' or '-'"));
+        }
+        // Values go into a header, so they have to be plain printable ASCII
+        if value.len() > MAX_TAG_VALUE_CHARS || !value.chars().all(|c| (' '..='~').contains(&c) && c != ';') {
+            return Err(format!("Request tag \"{key}\" must be at most {MAX_TAG_VALUE_CHARS} printable ASCII characters without ';'"));
+        }
+    }
+    Ok(())
+}
+
+/// Payload of `api-key-warning`
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiKeyWarning {
+    pub field: &'static str,
+    pub message: String,
+}
+
+/// Keys whose shape suggests they won't work: the wrong prefix for the
+/// provider (often another provider's key) or whitespace inside. Only the
+/// format is checked; nothing is sent anywhere.
+pub fn key_warnings(config: &AppConfig) -> Vec<ApiKeyWarning> {
+    let mut warnings = Vec::new();
+    for provider in Provider::ALL {
+        let key = provider.api_key(config);
+        if key.is_empty() {
+            continue;
+        }
+        let field = provider.key_field();
+        if key.chars().any(char::is_whitespace) {
+            warnings.push(ApiKeyWarning { field, message: "The key contains spaces or line breaks".into() });
+        }
+        // OpenAI's prefix is a prefix of Anthropic's, so look for the most specific match
+        let looks_like = Provider::ALL
+            .into_iter()
+            .filter(|other| key.starts_with(other.key_prefix()))
+            .max_by_key(|other| other.key_prefix().len());
+        match looks_like {
+            Some(other) if other == provider => {}
+            Some(other) => warnings.push(ApiKeyWarning {
+                field,
+                message: format!("This looks like a {} key, not a {} key", other.name(), provider.name()),
+            }),
+            None => warnings.push(ApiKeyWarning {
+                field,
+                message: format!("{} keys usually start with \"{}\"", provider.name(), provider.key_prefix()),
+            }),
+        }
+    }
+    if config.whisper_api_key.chars().any(char::is_whitespace) {
+        warnings.push(ApiKeyWarning { field: "whisperApiKey", message: "The key contains spaces or line breaks".into() });
+    }
+    warnings
+}
+
+// How long a new key's test request may take
+const KEY_CHECK_TIMEOUT_SECS: u64 = 15;
+
+/// A cheap request that only succeeds with a working `key`: the model list
+/// where the provider has one, otherwise a one-token completion
+fn key_check(http: &HttpState, provider: Provider, key: &str) -> reqwest::RequestBuilder {
+    let client = http.client();
+    let request = match provider {
+        Provider::OpenAi => client.get(format!("{}/v1/models", provider.origin())).bearer_auth(key),
+        Provider::Claude => client
+            .get(format!("{}/v1/models", provider.origin()))
+            .header("x-api-key", key)
+            .header("anthropic-version", "2023-06-01"),
+        Provider::Gemini => client.get(format!("{}/v1beta/models", provider.origin())).header("x-goog-api-key", key),
+        Provider::Perplexity => client
+            .post(provider.url(provider.default_model(), false))
+            .bearer_auth(key)
+            .json(&json!({ "model": provider.default_model(), "max_tokens": 1, "messages": [{ "role": "user", "content": "ping" }] })),
+    };
+    request.timeout(Duration::from_secs(KEY_CHECK_TIMEOUT_SECS))
+}
+
+/// Payload of `secret-rotated`, also returned by `rotate_secret`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretRotated {
+    which: String,
+    // False when the endpoint has no way to test a key (custom transcription
+    // servers); the key was saved untested
+    verified: bool,
+}
+
+/// Replace the API key in setting `which` (e.g. `openaiApiKey`,
+/// `whisperApiKey`) once a test request with `new_value` succeeds. A key the
+/// provider rejects, or that can't be tested for a network error, leaves the
+/// current one in place. On success the provider's circuit breaker is reset
+/// and `secret-rotated` emitted.
+#[tauri::command]
+pub async fn rotate_secret(app: AppHandle, which: String, new_value: String) -> Result<SecretRotated, AppError> {
+    let key = new_value.trim().to_string();
+    if key.is_empty() || key.chars().any(char::is_whitespace) {
+        return Err(AppError::Config("The new key is empty or contains spaces".into()));
+    }
+    let config = config::get(&app);
+    let http = app.state::<HttpState>();
+    let (name, request) = if which == "whisperApiKey" {
+        // OpenAI-compatible servers list models next to the transcription route
+        let models = config.whisper_url.strip_suffix("/audio/transcriptions").map(|base| format!("{base}/models"));
+        ("Whisper", models.map(|url| http.client().get(url).bearer_auth(&key).timeout(Duration::from_secs(KEY_CHECK_TIMEOUT_SECS))))
+    } else {
+        let provider = Provider::ALL
+            .into_iter()
+            .find(|provider| provider.key_field() == which)
+            .ok_or_else(|| AppError::Config(format!("\"{which}\" is not an API key setting")))?;
+        (provider.name(), Some(key_check(&http, provider, &key)))
+    };
+
+    let verified = request.is_some();
+    if let Some(request) = request {
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(match AppError::from_status(status, body) {
+                AppError::Auth(_) => AppError::Auth(format!("{name} rejected the new key; the current key is unchanged")),
+                e => e,
+            });
+        }
+    }
+
+    config::modify(&app, |config| match which.as_str() {
+        "whisperApiKey" => config.whisper_api_key = key,
+        "openaiApiKey" => config.openai_api_key = key,
+        "anthropicApiKey" => config.anthropic_api_key = key,
+        "geminiApiKey" => config.gemini_api_key = key,
+        _ => config.llm_api_key = key,
+    })
+    .map_err(AppError::Config)?;
+    if which != "whisperApiKey" {
+        circuit::record_success(&app, name);
+    }
+    readiness::refresh(&app);
+    tracing::info!("Rotated {which}{}", if verified { "" } else { " without testing it" });
+    let rotated = SecretRotated { which, verified };
+    let _ = app.emit_event("secret-rotated", rotated.clone());
+    Ok(rotated)
+}
+
+/// The body exactly as sent: `request_body` plus streaming, tags and `llm_extra_params`
+fn full_body(provider: Provider, config: &AppConfig, model: &str, prompt: &str, image: Option<&ImageAttachment>, stream: bool) -> Value {
+    let mut body = request_body(provider, config, model, prompt, image);
+    if stream && provider != Provider::Gemini {
+        body["stream"] = json!(true);
+    }
+    if provider == Provider::OpenAi && !config.request_tags.is_empty() {
+        body["metadata"] = json!(config.request_tags);
+    }
+    // Extras fill in what the app doesn't set; its own fields always win
+    if let (Some(body), Some(extra)) = (body.as_object_mut(), config.llm_extra_params.as_object()) {
+        for (key, value) in extra {
+            body.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+    }
+    body
+}
+
+/// One message of a `RequestPreview`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewMessage {
+    role: &'static str,
+    content: String,
+    tokens: usize,
+}
+
+/// Returned by `preview_llm_request`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestPreview {
+    provider: &'static str,
+    model: String,
+    url: String,
+    template: Option<String>,
+    messages: Vec<PreviewMessage>,
+    total_tokens: usize,
+    // Provider-specific JSON body; credentials travel in headers and aren't part of it
+    body: Value,
+}
+
+/// What the LLM would be sent for `transcript` under `template` (the active
+/// one when unset): the system prompt and templated user message with token
+/// estimates, and the request body. Nothing is sent and no key is included.
+#[tauri::command]
+pub fn preview_llm_request(app: AppHandle, transcript: String, template: Option<String>) -> Result<RequestPreview, AppError> {
+    let config = config::get(&app);
+    let (provider, model) = active_model(&config).ok_or_else(|| AppError::Config(format!("Unknown provider {}", config.llm_provider)))?;
+    let template = match template {
+        Some(name) => {
+            let found = config.templates.iter().find(|template| template.name == name);
+            Some(found.ok_or_else(|| AppError::Config(format!("No template named \"{name}\"")))?)
+        }
+        None => template::active(&config),
+    };
+    let prompt = template::apply_template(template, transcript.trim());
+
+    let mut messages = Vec::new();
+    if !config.system_prompt.is_empty() {
+        messages.push(PreviewMessage { role: "system", content: config.system_prompt.clone(), tokens: 0 });
+    }
+    messages.push(PreviewMessage { role: "user", content: prompt.clone(), tokens: 0 });
+    for message in &mut messages {
+        message.tokens = tokens::estimate(&message.content, &model);
+    }
+    Ok(RequestPreview {
+        provider: provider.name(),
+        url: provider.url(&model, false),
+        template: template.map(|template| template.name.clone()),
+        total_tokens: messages.iter().map(|message| message.tokens).sum(),
+        messages,
+        body: full_body(provider, &config, &model, &prompt, None, false),
+        model,
+    })
+}
+
+/// Authenticated request for `target`; `stream` asks for a server-sent event response
+fn build_request(
+    http: &HttpState,
+    config: &AppConfig,
+    target: &FallbackTarget,
+    prompt: &str,
+    image: Option<&ImageAttachment>,
+    stream: bool,
+) -> Result<(Provider, reqwest::RequestBuilder), AppError> {
+    let provider = Provider::from_name(&target.provider)?;
+    let api_key = provider.api_key(config);
+    if api_key.is_empty() {
+        return Err(AppError::Config(format!("{} API key is not set", target.provider)));
+    }
+    let model = target.model.as_deref().unwrap_or(provider.default_model());
+    if image.is_some() && !provider.supports_images(model) {
+        return Err(AppError::Config(format!("{model} ({}) does not accept images", target.provider)));
+    }
+
+    let body = full_body(provider, config, model, prompt, image, stream);
+    tracing::debug!("LLM request to {} ({model}), stream: {stream}", target.provider);
+    let mut request = http.client().post(provider.url(model, stream)).timeout(http::llm_timeout(config)).json(&body);
+    if !config.request_tags.is_empty() {
+        let tags: Vec<String> = config.request_tags.iter().map(|(key, value)| format!("{key}={value}")).collect();
+        request = request.header("X-Request-Tags", tags.join(";"));
+    }
+    let request = match provider {
+        Provider::OpenAi | Provider::Perplexity => request.bearer_auth(api_key),
+        Provider::Claude => request.header("x-api-key", api_key).header("anthropic-version", "2023-06-01"),
+        Provider::Gemini => request.header("x-goog-api-key", api_key),
+    };
+    Ok((provider, request))
+}
+
+/// Send a single prompt to one provider and return the full answer
+async fn query_provider(
+    http: &HttpState,
+    config: &AppConfig,
+    target: &FallbackTarget,
+    prompt: &str,
+    image: Option<&ImageAttachment>,
+    token: &CancellationToken,
+) -> Result<String, AppError> {
+    let (provider, request) = build_request(http, config, target, prompt, image, false)?;
+
+    cancel::run(token, async {
+        let _permit = http.semaphore.acquire().await.map_err(|e| AppError::Network(e.to_string()))?;
+
+        let response = request.send().await?;
+        http.record_request_id(&target.provider, &response);
+        let status = response.status();
+        let body = http.read_body(config, &target.provider, response).await;
+        if !status.is_success() {
+            return Err(AppError::from_status(status, body.unwrap_or_default()));
+        }
+
+        let data: Value = serde_json::from_str(&body?).map_err(|e| AppError::Provider {
+            status: status.as_u16(),
+            message: format!("The {} response is not JSON: {e}", target.provider),
+        })?;
+        Ok(parse_response(provider, &data))
+    })
+    .await
+}
+
+/// Outages are worth retrying elsewhere; auth and config errors are not
+pub fn should_fall_back(err: &AppError) -> bool {
+    match err {
+        AppError::Network(_) | AppError::Timeout => true,
+        AppError::Provider { status, .. } => *status >= 500,
+        _ => false,
+    }
+}
+
+/// An answer and the provider/model that produced it
+#[derive(Debug, Clone)]
+pub struct LlmAnswer {
+    pub text: String,
+    pub provider: String,
+    pub model: String,
+    pub fallback_used: bool,
+}
+
+/// Send a prompt to the configured provider, moving down `llm_fallback` when
+/// it is unreachable. Emits `llm-fallback-used` when a fallback answers.
+/// With `cache_responses` on, a cached answer is returned without a request.
+pub async fn query(app: &AppHandle, config: &AppConfig, prompt: &str, token: &CancellationToken) -> Result<String, AppError> {
+    query_detailed(app, config, prompt, token).await.map(|answer| answer.text)
+}
+
+/// The offline check against the primary provider; fallbacks are on the internet too
+async fn ensure_online(app: &AppHandle, config: &AppConfig) -> Result<(), AppError> {
+    match Provider::from_name(&config.llm_provider) {
+        Ok(provider) => http::ensure_online(app, config, provider.origin()).await,
+        Err(_) => Ok(()),
+    }
+}
+
+/// Like `query`, but also reports which provider and model answered
+pub async fn query_detailed(
+    app: &AppHandle,
+    config: &AppConfig,
+    prompt: &str,
+    token: &CancellationToken,
+) -> Result<LlmAnswer, AppError> {
+    if let Some(answer) = cache::lookup(app, config, prompt) {
+        return Ok(answer);
+    }
+    let answer = query_uncached(app, config, prompt, token).await?;
+    cache::store(config, prompt, &answer);
+    Ok(answer)
+}
+
+/// Like `query_detailed`, but always asks a provider and never fills the
+/// cache, for checks that have to reach the network
+pub async fn query_uncached(
+    app: &AppHandle,
+    config: &AppConfig,
+    prompt: &str,
+    token: &CancellationToken,
+) -> Result<LlmAnswer, AppError> {
+    ensure_online(app, config).await?;
+    let http = app.state::<HttpState>();
+    let primary = FallbackTarget { provider: config.llm_provider.clone(), model: config.llm_model.clone() };
+    let targets = std::iter::once(&primary).chain(&config.llm_fallback).take(MAX_ATTEMPTS);
+
+    let mut last_err = None;
+    for (attempt, target) in targets.enumerate() {
+        // An open circuit skips straight to the next fallback
+        if let Err(e) = circuit::check(app, &target.provider) {
+            tracing::warn!("Skipping LLM provider {}: {e}", target.provider);
+            last_err = Some(e);
+            continue;
+        }
+        let result = query_provider(&http, config, target, prompt, None, token).await;
+        match &result {
+            Ok(_) => circuit::record_success(app, &target.provider),
+            Err(e) if should_fall_back(e) => circuit::record_failure(app, config, &target.provider),
+            Err(_) => {}
+        }
+        match result {
+            Ok(text) => {
+                if attempt > 0 {
+                    let _ = app.emit_event("llm-fallback-used", target);
+                }
+                let model = match &target.model {
+                    Some(model) => model.clone(),
+                    None => Provider::from_name(&target.provider)?.default_model().to_string(),
+                };
+                return Ok(LlmAnswer { text, provider: target.provider.clone(), model, fallback_used: attempt > 0 });
+            }
+            Err(e) if should_fall_back(&e) => {
+                tracing::warn!("LLM provider {} failed, trying next: {e}", target.provider);
+                last_err = Some(e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Err(last_err.expect("at least the primary provider is tried"))
+}
+
+/// Ask the configured provider about an image. Fallbacks may not be
+/// multimodal, so only the primary provider is used.
+pub async fn query_with_image(
+    app: &AppHandle,
+    config: &AppConfig,
+    prompt: &str,
+    image: &ImageAttachment,
+    token: &CancellationToken,
+) -> Result<String, AppError> {
+    ensure_online(app, config).await?;
+    let target = FallbackTarget { provider: config.llm_provider.clone(), model: config.llm_model.clone() };
+    query_provider(&app.state::<HttpState>(), config, &target, prompt, Some(image), token).await
+}
+
+/// Stream an answer from the configured provider, calling `on_text` for each
+/// piece as it arrives. Fallbacks can't take over once output has started,
+/// so streaming only uses the primary provider. Returns the full answer.
+pub async fn query_stream(
+    app: &AppHandle,
+    config: &AppConfig,
+    prompt: &str,
+    token: &CancellationToken,
+    mut on_text: impl FnMut(&str),
+) -> Result<String, AppError> {
+    ensure_online(app, config).await?;
+    let http = app.state::<HttpState>();
+    let target = FallbackTarget { provider: config.llm_provider.clone(), model: config.llm_model.clone() };
+    let (provider, request) = build_request(&http, config, &target, prompt, None, true)?;
+    circuit::check(app, &target.provider)?;
+
+    let result = cancel::run(token, async {
+        let _permit = http.semaphore.acquire().await.map_err(|e| AppError::Network(e.to_string()))?;
+
+        let mut response = request.send().await?;
+        http.record_request_id(&target.provider, &response);
+        let status = response.status();
+        if !status.is_success() {
+            let body = http.read_body(config, &target.provider, response).await;
+            return Err(AppError::from_status(status, body.unwrap_or_default()));
+        }
+
+        let mut parser = stream::parser_for(provider);
+        let mut answer = String::new();
+        // Separate from the request timeout: only the gap between chunks counts
+        let idle = Duration::from_secs(config.stream_idle_timeout_secs);
+        loop {
+            let chunk = if idle.is_zero() {
+                response.chunk().await?
+            } else {
+                match tokio::time::timeout(idle, response.chunk()).await {
+                    Ok(chunk) => chunk?,
+                    Err(_) => {
+                        // Hand back what arrived so the user isn't left with nothing
+                        tracing::warn!("LLM stream stalled after {}s without data", idle.as_secs());
+                        let _ = app.emit_event("llm-stream-stalled", Text { text: &answer });
+                        return Ok(answer);
+                    }
+                }
+            };
+            let Some(chunk) = chunk else {
+                break;
+            };
+            for delta in parser.parse_chunk(&chunk) {
+                match delta {
+                    Delta::Text(text) => {
+                        on_text(&text);
+                        answer.push_str(&text);
+                    }
+                    Delta::Done => return Ok(answer),
+                }
+            }
+        }
+        Ok(answer)
+    })
+    .await;
+
+    match &result {
+        Ok(_) => circuit::record_success(app, &target.provider),
+        Err(e) if should_fall_back(e) => circuit::record_failure(app, config, &target.provider),
+        Err(_) => {}
+    }
+    result
+}
@@ -0,0 +1,65 @@
+use std::sync::OnceLock;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, Registry};
+
+use crate::config;
+
+const LEVELS: &[&str] = &["error", "warn", "info", "debug", "trace"];
+
+// Handle for changing the level live, and the guard that flushes the log file
+static RELOAD: OnceLock<reload::Handle<LevelFilter, Registry>> = OnceLock::new();
+static FILE_GUARD: OnceLock<WorkerGuard> = OnceLock::new();
+
+/// Log to stderr and to a daily file under `logs/`, starting at info. Must run
+/// after the config path override is resolved so the file lands next to it.
+pub fn init() {
+    let (filter, handle) = reload::Layer::new(LevelFilter::INFO);
+    let file_layer = config::config_dir().ok().map(|dir| {
+        let (writer, guard) = tracing_appender::non_blocking(tracing_appender::rolling::daily(dir.join("logs"), "ama-agent.log"));
+        let _ = FILE_GUARD.set(guard);
+        fmt::layer().with_ansi(false).with_writer(writer)
+    });
+
+    let result = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(std::io::stderr))
+        .with(file_layer)
+        .try_init();
+    match result {
+        Ok(()) => {
+            let _ = RELOAD.set(handle);
+        }
+        Err(e) => eprintln!("Failed to set up logging: {e}"),
+    }
+}
+
+/// Where the log files are written, for a "show logs" button
+#[tauri::command]
+pub fn get_logs_dir() -> Result<String, String> {
+    Ok(config::config_dir()?.join("logs").to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub fn get_log_level() -> String {
+    RELOAD
+        .get()
+        .and_then(|handle| handle.with_current(|level| level.to_string().to_lowercase()).ok())
+        .unwrap_or_else(|| "info".into())
+}
+
+/// Change verbosity until the app exits; one of error, warn, info, debug or trace
+#[tauri::command]
+pub fn set_log_level(level: String) -> Result<(), String> {
+    let level = level.to_lowercase();
+    if !LEVELS.contains(&level.as_str()) {
+        return Err(format!("Unknown log level \"{level}\", expected one of {}", LEVELS.join(", ")));
+    }
+    let filter: LevelFilter = level.parse().map_err(|e| format!("{e}"))?;
+    let handle = RELOAD.get().ok_or("Logging is not initialized")?;
+    handle.reload(filter).map_err(|e| e.to_string())?;
+    tracing::info!("Log level set to {level}");
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+use crate::activity;
+use crate::config;
+use crate::error::AppError;
+use crate::window;
+
+/// Show `body` as a notification when the window is hidden, for feedback on
+/// actions taken from a shortcut
+pub fn hint(app: &AppHandle, body: &str) {
+    if window::is_main_visible(app) {
+        return;
+    }
+    if let Err(e) = app.notification().builder().title("AMA Agent").body(body).show() {
+        tracing::error!("Failed to show notification: {e}");
+    }
+}
+
+/// Tell the user about a failure they can't see because the window is hidden.
+/// Only the error category is shown; messages may echo request data.
+pub fn error(app: &AppHandle, err: &AppError) {
+    match err {
+        AppError::Cancelled => activity::record(app, "cancelled", "Cancelled", None),
+        _ => activity::record(app, "error", err.to_string(), None),
+    }
+    if matches!(err, AppError::Cancelled) || window::is_main_visible(app) {
+        return;
+    }
+    if !config::get(app).notify_on_error {
+        return;
+    }
+
+    // Desktop notifications have no click callback; activating the app from the
+    // notification brings the window back, where the details can be retried
+    let result = app
+        .notification()
+        .builder()
+        .title("AMA Agent")
+        .body(err.summary())
+        .show();
+    if let Err(e) = result {
+        tracing::error!("Failed to show error notification: {e}");
+    }
+}
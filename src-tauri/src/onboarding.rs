@@ -0,0 +1,167 @@
+use cpal::traits::HostTrait;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_global_shortcut::GlobalShortcutExt;
+
+use crate::config::{self, AppConfig};
+use crate::error::AppError;
+use crate::events::{ConfigImported, EmitEvent, StepComplete};
+use crate::llm::Provider;
+use crate::permission::{self, PermissionState};
+use crate::shortcut;
+
+/// First-run prerequisites; recording is only expected to work once all are true
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct OnboardingStatus {
+    pub config_dir_ok: bool,
+    pub api_key_set: bool,
+    pub mic_permission_ok: bool,
+    pub shortcut_registered: bool,
+}
+
+impl OnboardingStatus {
+    fn steps(self) -> [(&'static str, bool); 4] {
+        [
+            ("config_dir_ok", self.config_dir_ok),
+            ("api_key_set", self.api_key_set),
+            ("mic_permission_ok", self.mic_permission_ok),
+            ("shortcut_registered", self.shortcut_registered),
+        ]
+    }
+}
+
+/// Last reported status, used to emit each step only when it turns complete
+#[derive(Default)]
+pub struct OnboardingState {
+    last: Mutex<OnboardingStatus>,
+}
+
+fn check(app: &AppHandle) -> OnboardingStatus {
+    let config_dir_ok = config::ensure_dirs().is_ok();
+    let config = config::get(app);
+
+    let whisper_key_set = !config.whisper_api_key.is_empty() || !config.openai_api_key.is_empty();
+    let llm_key_set = Provider::from_name(&config.llm_provider).is_ok_and(|provider| !provider.api_key(&config).is_empty());
+
+    let shortcut_registered = config
+        .shortcuts
+        .get(shortcut::ACTION_TOGGLE)
+        .and_then(|accelerator| shortcut::parse(accelerator).ok())
+        .is_some_and(|toggle| app.global_shortcut().is_registered(toggle));
+
+    OnboardingStatus {
+        config_dir_ok,
+        api_key_set: whisper_key_set && llm_key_set,
+        mic_permission_ok: permission::check() == PermissionState::Granted && cpal::default_host().default_input_device().is_some(),
+        shortcut_registered,
+    }
+}
+
+/// Re-check every step, emitting `onboarding-step-complete` for newly satisfied ones
+pub fn refresh(app: &AppHandle) -> OnboardingStatus {
+    let status = check(app);
+    let state = app.state::<OnboardingState>();
+    let mut last = state.last.lock().unwrap();
+
+    for ((step, done), (_, was_done)) in status.steps().into_iter().zip(last.steps()) {
+        if done && !was_done {
+            let _ = app.emit_event("onboarding-step-complete", StepComplete { step });
+        }
+    }
+
+    *last = status;
+    status
+}
+
+#[tauri::command]
+pub fn get_onboarding_status(app: AppHandle) -> OnboardingStatus {
+    refresh(&app)
+}
+
+// Anything bigger isn't a dotenv file
+const MAX_DOTENV_BYTES: u64 = 64 * 1024;
+
+type Field = fn(&mut AppConfig) -> &mut String;
+
+// Environment variable -> the settings it fills in, by camelCase name. The
+// OpenAI key serves both transcription and the OpenAI LLM provider.
+const ENV_FIELDS: &[(&str, &[(&str, Field)])] = &[
+    ("OPENAI_API_KEY", &[("whisperApiKey", |c| &mut c.whisper_api_key), ("openaiApiKey", |c| &mut c.openai_api_key)]),
+    ("ANTHROPIC_API_KEY", &[("anthropicApiKey", |c| &mut c.anthropic_api_key)]),
+    ("GEMINI_API_KEY", &[("geminiApiKey", |c| &mut c.gemini_api_key)]),
+    ("GOOGLE_API_KEY", &[("geminiApiKey", |c| &mut c.gemini_api_key)]),
+    ("PERPLEXITY_API_KEY", &[("llmApiKey", |c| &mut c.llm_api_key)]),
+    ("OPENAI_BASE_URL", &[("whisperUrl", |c| &mut c.whisper_url)]),
+];
+
+/// `KEY=value` lines of a dotenv file. Comments, blank lines and an `export`
+/// prefix are skipped; one layer of matching quotes is stripped.
+fn parse_dotenv(contents: &str) -> HashMap<String, String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (key, value) = line.strip_prefix("export ").unwrap_or(line).split_once('=')?;
+            let value = value.trim();
+            let unquoted = ['"', '\'']
+                .iter()
+                .find_map(|quote| value.strip_prefix(*quote)?.strip_suffix(*quote))
+                .unwrap_or_else(|| value.split(" #").next().unwrap_or_default().trim_end());
+            Some((key.trim().to_string(), unquoted.to_string()))
+        })
+        .collect()
+}
+
+fn read_dotenv(path: &str) -> Result<HashMap<String, String>, AppError> {
+    let size = std::fs::metadata(path).map_err(|e| AppError::Io(format!("{path}: {e}")))?.len();
+    if size > MAX_DOTENV_BYTES {
+        return Err(AppError::Config(format!("{path} is too large for a dotenv file")));
+    }
+    let contents = std::fs::read_to_string(path).map_err(|e| AppError::Io(format!("{path}: {e}")))?;
+    Ok(parse_dotenv(&contents))
+}
+
+/// Fill in API keys from `OPENAI_API_KEY` and friends, read from the dotenv
+/// file at `path` or, without one, the app's environment. `OPENAI_BASE_URL`
+/// points transcription at that server. Settings that already have a value are
+/// kept unless `force`. Saved through `save_config`; emits `config-imported`
+/// and returns the settings that changed.
+#[tauri::command]
+pub fn import_from_env(app: AppHandle, path: Option<String>, force: Option<bool>) -> Result<Vec<&'static str>, AppError> {
+    let vars = match path.as_deref().filter(|path| !path.is_empty()) {
+        Some(path) => read_dotenv(path)?,
+        None => ENV_FIELDS.iter().filter_map(|(var, _)| Some((var.to_string(), std::env::var(var).ok()?))).collect(),
+    };
+    let force = force.unwrap_or(false);
+    let mut defaults = AppConfig::default();
+    let mut config = config::get(&app);
+    let mut fields = Vec::new();
+    for (var, targets) in ENV_FIELDS {
+        let Some(value) = vars.get(*var).map(|value| value.trim()).filter(|value| !value.is_empty()) else {
+            continue;
+        };
+        let value = match *var {
+            "OPENAI_BASE_URL" => format!("{}/audio/transcriptions", value.trim_end_matches('/')),
+            _ => value.to_string(),
+        };
+        for (field, target) in *targets {
+            // A default URL counts as unset; a default key is empty anyway
+            let current = target(&mut config);
+            let unset = current.is_empty() || *current == *target(&mut defaults);
+            if (force || unset) && *current != value {
+                *current = value.clone();
+                if !fields.contains(field) {
+                    fields.push(*field);
+                }
+            }
+        }
+    }
+    if !fields.is_empty() {
+        config::save_config(app.clone(), config).map_err(AppError::Config)?;
+    }
+    let _ = app.emit_event("config-imported", ConfigImported { fields: fields.clone() });
+    Ok(fields)
+}
@@ -0,0 +1,135 @@
+use serde::Serialize;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+use crate::audio;
+use crate::batch;
+use crate::cancel;
+use crate::clipboard;
+use crate::config;
+use crate::error::AppError;
+use crate::history;
+use crate::llm;
+use crate::preset;
+use crate::realtime;
+use crate::repeat;
+use crate::shortcut;
+use crate::template;
+use crate::window;
+
+/// An entry of the command palette. Ids shared with shortcut actions run the
+/// same thing as the shortcut.
+struct Command {
+    id: &'static str,
+    label: &'static str,
+    category: &'static str,
+    // What the argument to `run_command` is, for commands that need one
+    arg: Option<&'static str>,
+}
+
+// Every palette command, in display order. New actions belong here and in `run`.
+const COMMANDS: &[Command] = &[
+    Command { id: shortcut::ACTION_TOGGLE, label: "Start or stop recording", category: "Recording", arg: None },
+    Command { id: shortcut::ACTION_CANCEL, label: "Cancel", category: "Recording", arg: None },
+    Command { id: shortcut::ACTION_DICTATE, label: "Start or stop dictation", category: "Recording", arg: None },
+    Command { id: "transcribe-file", label: "Transcribe a file", category: "Recording", arg: Some("path") },
+    Command { id: "apply-preset", label: "Apply preset", category: "Recording", arg: Some("preset") },
+    Command { id: "toggle-passive-buffer", label: "Keep listening in the background", category: "Recording", arg: None },
+    Command { id: shortcut::ACTION_PROCESS_CLIPBOARD, label: "Ask about the clipboard", category: "Query", arg: None },
+    Command { id: shortcut::ACTION_REPEAT_LAST, label: "Repeat the last action", category: "Query", arg: None },
+    Command { id: shortcut::ACTION_CYCLE_TEMPLATE, label: "Next template", category: "Query", arg: None },
+    Command { id: "switch-template", label: "Switch template", category: "Query", arg: Some("template") },
+    Command { id: "switch-model", label: "Switch model", category: "Query", arg: Some("model") },
+    Command { id: "compact-history", label: "Compact history", category: "History", arg: None },
+    Command { id: "toggle-do-not-disturb", label: "Do not disturb", category: "App", arg: None },
+    Command { id: shortcut::ACTION_OPEN_SETTINGS, label: "Open settings", category: "App", arg: None },
+    Command { id: "show-window", label: "Show window", category: "App", arg: None },
+    Command { id: "quit", label: "Quit", category: "App", arg: None },
+];
+
+/// Returned by `list_commands`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandDescriptor {
+    id: &'static str,
+    label: &'static str,
+    category: &'static str,
+    arg: Option<&'static str>,
+    // Known values for `arg`, e.g. template names; empty when it's free-form
+    options: Vec<String>,
+    // Accelerator bound to the same action, if any
+    shortcut: Option<String>,
+}
+
+/// Everything the command palette can run, with current bindings and choices
+#[tauri::command]
+pub fn list_commands(app: AppHandle) -> Vec<CommandDescriptor> {
+    let config = config::get(&app);
+    COMMANDS
+        .iter()
+        .map(|command| {
+            let options = match command.id {
+                "apply-preset" => config.presets.iter().map(|preset| preset.name.clone()).collect(),
+                "switch-template" => config.templates.iter().map(|template| template.name.clone()).collect(),
+                "switch-model" => llm::active_model(&config)
+                    .map(|(provider, _)| provider.suggested_models().iter().map(|model| model.to_string()).collect())
+                    .unwrap_or_default(),
+                _ => Vec::new(),
+            };
+            CommandDescriptor {
+                id: command.id,
+                label: command.label,
+                category: command.category,
+                arg: command.arg,
+                options,
+                shortcut: config.shortcuts.get(command.id).cloned(),
+            }
+        })
+        .collect()
+}
+
+fn run(app: &AppHandle, id: &str, arg: String) -> Result<(), AppError> {
+    match id {
+        shortcut::ACTION_TOGGLE => shortcut::on_toggle(app),
+        shortcut::ACTION_CANCEL => cancel::cancel_all(app.clone()),
+        shortcut::ACTION_DICTATE => {
+            tauri::async_runtime::spawn(realtime::toggle_dictation(app.clone()));
+        }
+        "transcribe-file" => batch::on_drop(app, vec![PathBuf::from(arg)]),
+        "apply-preset" => preset::apply_preset(app.clone(), arg).map_err(AppError::Config)?,
+        "toggle-passive-buffer" => audio::set_passive_buffer(app.clone(), !audio::passive_status(app).enabled, None)?,
+        shortcut::ACTION_PROCESS_CLIPBOARD => {
+            tauri::async_runtime::spawn(clipboard::process_clipboard(app.clone()));
+        }
+        shortcut::ACTION_REPEAT_LAST => {
+            tauri::async_runtime::spawn(repeat::repeat_from_shortcut(app.clone()));
+        }
+        shortcut::ACTION_CYCLE_TEMPLATE => template::cycle(app),
+        "switch-template" => template::set_active(app, &arg).map_err(AppError::Config)?,
+        "switch-model" => llm::set_model(app, &arg)?,
+        "compact-history" => {
+            history::compact_history(app.clone(), app.state()).map_err(AppError::Io)?;
+        }
+        "toggle-do-not-disturb" => shortcut::set_do_not_disturb(app.clone(), !shortcut::get_do_not_disturb(app.clone())),
+        shortcut::ACTION_OPEN_SETTINGS => window::navigate_to(app, "settings"),
+        "show-window" => window::show_main(app),
+        "quit" => app.exit(0),
+        _ => return Err(AppError::Config(format!("Unknown command \"{id}\""))),
+    }
+    Ok(())
+}
+
+/// Run a palette command by id. `arg` is required by commands that declare one.
+/// Long-running commands start in the background and report through their
+/// usual events.
+#[tauri::command]
+pub fn run_command(app: AppHandle, id: String, arg: Option<String>) -> Result<(), AppError> {
+    let Some(command) = COMMANDS.iter().find(|command| command.id == id) else {
+        return Err(AppError::Config(format!("Unknown command \"{id}\"")));
+    };
+    let arg = arg.filter(|arg| !arg.trim().is_empty());
+    if let (Some(name), None) = (command.arg, &arg) {
+        return Err(AppError::Config(format!("\"{id}\" needs a {name}")));
+    }
+    run(&app, command.id, arg.unwrap_or_default())
+}
@@ -0,0 +1,104 @@
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::events::EmitEvent;
+use crate::readiness;
+
+// Deep link to the microphone pane of macOS privacy settings
+const MIC_SETTINGS_URL: &str = "x-apple.systempreferences:com.apple.preference.security?Privacy_Microphone";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionState {
+    Granted,
+    Denied,
+    // Only reported on macOS before the first prompt
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    NotDetermined,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PermissionDenied {
+    settings_url: &'static str,
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use block2::RcBlock;
+    use objc2::runtime::Bool;
+    use objc2_av_foundation::{AVAuthorizationStatus, AVCaptureDevice, AVMediaTypeAudio};
+    use std::sync::mpsc;
+
+    use super::PermissionState;
+
+    pub fn check() -> PermissionState {
+        let Some(media_type) = (unsafe { AVMediaTypeAudio }) else {
+            return PermissionState::NotDetermined;
+        };
+        let status = unsafe { AVCaptureDevice::authorizationStatusForMediaType(media_type) };
+        if status == AVAuthorizationStatus::Authorized {
+            PermissionState::Granted
+        } else if status == AVAuthorizationStatus::Denied || status == AVAuthorizationStatus::Restricted {
+            PermissionState::Denied
+        } else {
+            PermissionState::NotDetermined
+        }
+    }
+
+    /// Show the TCC prompt (only the first time) and wait for the answer
+    pub fn request() -> PermissionState {
+        let Some(media_type) = (unsafe { AVMediaTypeAudio }) else {
+            return PermissionState::NotDetermined;
+        };
+        let (tx, rx) = mpsc::channel();
+        let handler = RcBlock::new(move |granted: Bool| {
+            let _ = tx.send(granted.as_bool());
+        });
+        unsafe { AVCaptureDevice::requestAccessForMediaType_completionHandler(media_type, &handler) };
+
+        match rx.recv() {
+            Ok(true) => PermissionState::Granted,
+            Ok(false) => PermissionState::Denied,
+            Err(_) => check(),
+        }
+    }
+}
+
+// Other platforms have no per-app prompt we can query; device errors surface when opening the stream
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    use super::PermissionState;
+
+    pub fn check() -> PermissionState {
+        PermissionState::Granted
+    }
+
+    pub fn request() -> PermissionState {
+        PermissionState::Granted
+    }
+}
+
+pub use platform::check;
+
+/// Emit `mic-permission-denied` with a link to the settings pane
+pub fn notify_denied(app: &AppHandle) {
+    let _ = app.emit_event("mic-permission-denied", PermissionDenied { settings_url: MIC_SETTINGS_URL });
+}
+
+#[tauri::command]
+pub fn check_mic_permission() -> PermissionState {
+    platform::check()
+}
+
+#[tauri::command]
+pub async fn request_mic_permission(app: AppHandle) -> PermissionState {
+    let state = tauri::async_runtime::spawn_blocking(platform::request)
+        .await
+        .unwrap_or_else(|_| platform::check());
+    if state == PermissionState::Denied {
+        notify_denied(&app);
+    }
+    readiness::refresh(&app);
+    state
+}
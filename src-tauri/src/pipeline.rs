@@ -0,0 +1,721 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::future::Future;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager};
+use tokio_util::sync::CancellationToken;
+
+use crate::activity;
+use crate::attachment;
+use crate::audio::{self, StreamedUpload};
+use crate::cancel::CancelState;
+use crate::config::{self, AppConfig, Overrides};
+use crate::error::{self, AppError};
+use crate::events::{EmitEvent, Empty, Text};
+use crate::history;
+use crate::hook;
+use crate::http::HttpState;
+use crate::llm::{self, LlmAnswer};
+use crate::notify;
+use crate::profile::{self, ProfileMode};
+use crate::progress;
+use crate::realtime;
+use crate::repeat::{self, LastAction};
+use crate::replace;
+use crate::sink;
+use crate::template::{self, PromptTemplate};
+use crate::tokens;
+use crate::tray::{self, TrayState};
+use crate::whisper::{self, Transcript};
+
+/// When the LLM starts on a recording's transcript
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PipelineMode {
+    // Once the transcript is complete. The LLM sees exactly what was said.
+    #[default]
+    Sequential,
+    // During realtime transcription, on the transcript so far each time a
+    // segment is final. When nothing follows, the answer is ready sooner;
+    // otherwise it is thrown away, so this costs extra requests.
+    Streaming,
+}
+
+/// An LLM answer started before the transcript was complete
+pub struct Speculation {
+    transcript: String,
+    token: CancellationToken,
+    answer: JoinHandle<Result<LlmAnswer, AppError>>,
+}
+
+impl Speculation {
+    /// Answer `raw_transcript` in the background. Tied to the shared token, so
+    /// `cancel_all` stops it along with everything else.
+    pub fn start(app: &AppHandle, raw_transcript: &str) -> Self {
+        let config = config::get(app);
+        let transcript = prepare_transcript(&config, raw_transcript);
+        let prompt = template::apply_template(template::active(&config), &transcript);
+        let token = app.state::<CancelState>().token().child_token();
+
+        let task_app = app.clone();
+        let task_token = token.clone();
+        let answer = tauri::async_runtime::spawn(async move {
+            let llm_config = Overrides::default().for_llm(&config);
+            llm::query_detailed(&task_app, &llm_config, &prompt, &task_token).await
+        });
+        Self { transcript, token, answer }
+    }
+
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+}
+
+/// Replacements and trimming, applied the same way to speculative and final transcripts
+fn prepare_transcript(config: &AppConfig, raw: &str) -> String {
+    replace::apply_replacements(&config.replacements, raw).trim().to_string()
+}
+
+/// Result of `transcribe_and_process` with whatever provenance is known.
+/// Fields that couldn't be determined are `None`.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResultEnvelope {
+    pub text: String,
+    // Transcript before the template was applied
+    pub raw_transcript: String,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    // Language requested from Whisper; None when it auto-detected
+    pub language: Option<String>,
+    pub latency_ms: u64,
+    // Estimated prompt + answer tokens
+    pub tokens: Option<usize>,
+    pub request_id: Option<String>,
+    pub warnings: Vec<String>,
+    // Stages that failed; whatever came before them is still filled in
+    pub errors: Vec<StageError>,
+}
+
+/// A pipeline stage that failed, serialized as `{ stage, kind, message }`
+#[derive(Debug, Clone, Serialize)]
+pub struct StageError {
+    pub stage: &'static str,
+    #[serde(flatten)]
+    pub error: AppError,
+}
+
+/// Turn a failed LLM step into a partial result that keeps the transcript.
+/// Cancellation is still an error: the user asked for nothing to come back.
+fn llm_failed(app: &AppHandle, transcript: &str, error: AppError, started: Instant) -> Result<ResultEnvelope, AppError> {
+    if matches!(error, AppError::Cancelled) {
+        return Err(error);
+    }
+    notify::error(app, &error);
+    Ok(ResultEnvelope {
+        raw_transcript: transcript.to_string(),
+        latency_ms: started.elapsed().as_millis() as u64,
+        errors: vec![StageError { stage: "llm", error }],
+        ..ResultEnvelope::default()
+    })
+}
+
+/// Emit `result-final` with everything that succeeded. A failure of the whole
+/// run still produces the event, with `stage` as its only error.
+fn emit_final(app: &AppHandle, result: &Result<ResultEnvelope, AppError>, stage: &'static str) {
+    let envelope = match result {
+        Ok(envelope) => envelope.clone(),
+        Err(AppError::Cancelled) => return,
+        Err(error) => ResultEnvelope {
+            errors: vec![StageError { stage, error: error.clone() }],
+            ..ResultEnvelope::default()
+        },
+    };
+    let _ = app.emit_event("result-final", envelope);
+}
+
+/// Returned by `get_pipeline_state`: what the app is doing right now
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PipelineState {
+    // Idle, recording or processing, as shown in the tray
+    pub stage: TrayState,
+    // A recording captured by the backend rather than the webview
+    pub native_recording: bool,
+    pub realtime: bool,
+}
+
+/// Where the pipeline is, for the UI to resync after missing events. Each
+/// field is a short lock, so this is cheap enough to poll.
+#[tauri::command]
+pub fn get_pipeline_state(app: AppHandle) -> PipelineState {
+    PipelineState {
+        stage: tray::current_state(&app),
+        native_recording: audio::is_recording(&app),
+        realtime: realtime::is_active(&app),
+    }
+}
+
+/// True while audio is being captured, natively or by the webview
+#[tauri::command]
+pub fn is_recording(app: AppHandle) -> bool {
+    tray::current_state(&app) == TrayState::Recording || audio::is_recording(&app)
+}
+
+/// Await `step`, logging how long it took to the session activity log on
+/// success. `kind` doubles as the stage for `get_last_errors`.
+async fn timed<T>(
+    app: &AppHandle,
+    kind: &'static str,
+    label: &str,
+    step: impl Future<Output = Result<T, AppError>>,
+) -> Result<T, AppError> {
+    let started = progress::start(app, kind);
+    let result = step.await;
+    progress::end(app, kind, started, result.is_ok());
+    error::record_outcome(app, kind, &result);
+    if result.is_ok() {
+        let elapsed = started.elapsed();
+        activity::record(app, kind, format!("{label} in {:.1}s", elapsed.as_secs_f32()), Some(elapsed));
+    }
+    result
+}
+
+async fn run_pipeline(app: &AppHandle, audio: Vec<u8>, file_name: &str, overrides: &Overrides) -> Result<ResultEnvelope, AppError> {
+    let started = Instant::now();
+    let config = config::get(app);
+    let token = app.state::<CancelState>().token();
+
+    let transcription_config = overrides.for_transcription(&config);
+    let retry_audio = (!transcription_config.empty_retry_model.is_empty()).then(|| audio.clone());
+    let transcribe = whisper::transcribe_with_fallback(app, &transcription_config, audio, file_name, &token);
+    let mut transcript = timed(app, "transcription", "Transcribed", transcribe).await?;
+    if let Some(audio) = retry_audio {
+        transcript = retry_empty(app, &transcription_config, transcript, audio, file_name).await?;
+    }
+    finish_pipeline(app, &config, &transcription_config, transcript, overrides, started).await
+}
+
+/// Payload of `empty-retry-used`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EmptyRetryUsed<'a> {
+    model: &'a str,
+    recovered: bool,
+}
+
+/// Transcribe `audio` once more with `empty_retry_model` when `transcript` came
+/// back empty, for models that now and then return nothing on clear speech.
+/// Emits `empty-retry-used`; the first transcript stands if the retry fails.
+async fn retry_empty(
+    app: &AppHandle,
+    config: &AppConfig,
+    transcript: Transcript,
+    audio: Vec<u8>,
+    file_name: &str,
+) -> Result<Transcript, AppError> {
+    let model = &config.empty_retry_model;
+    if model.is_empty() || *model == config.whisper_model || !transcript.text.trim().is_empty() {
+        return Ok(transcript);
+    }
+    tracing::warn!("Transcript is empty, retrying with {model}");
+    let retry_config = AppConfig { whisper_model: model.clone(), ..config.clone() };
+    let token = app.state::<CancelState>().token();
+    let transcribe = whisper::transcribe_with_fallback(app, &retry_config, audio, file_name, &token);
+    let retried = match timed(app, "transcription", "Transcribed again", transcribe).await {
+        Ok(retried) => retried,
+        Err(AppError::Cancelled) => return Err(AppError::Cancelled),
+        Err(e) => {
+            tracing::warn!("Retry with {model} failed: {e}");
+            transcript
+        }
+    };
+    let recovered = !retried.text.trim().is_empty();
+    let _ = app.emit_event("empty-retry-used", EmptyRetryUsed { model, recovered });
+    Ok(retried)
+}
+
+/// Template, LLM and delivery for a transcript that has just come back
+async fn finish_pipeline(
+    app: &AppHandle,
+    config: &AppConfig,
+    transcription_config: &AppConfig,
+    transcript: Transcript,
+    overrides: &Overrides,
+    started: Instant,
+) -> Result<ResultEnvelope, AppError> {
+    let Transcript { text: transcript, confidence, language } = transcript;
+    let language = language.or_else(|| transcription_config.transcription_language.clone().filter(|l| !l.is_empty() && l != "auto"));
+    if transcript.trim().is_empty() {
+        let _ = app.emit_event("recording-empty", Empty {});
+        return Ok(ResultEnvelope {
+            language,
+            latency_ms: started.elapsed().as_millis() as u64,
+            warnings: vec!["No speech was detected".into()],
+            ..ResultEnvelope::default()
+        });
+    }
+    if check_confidence(app, config, confidence) && config.skip_llm_on_low_confidence {
+        return Ok(ResultEnvelope {
+            raw_transcript: transcript.trim().to_string(),
+            language,
+            latency_ms: started.elapsed().as_millis() as u64,
+            warnings: vec!["The transcript looks unreliable, so it was not sent to the LLM".into()],
+            ..ResultEnvelope::default()
+        });
+    }
+
+    if check_duplicate(app, config, transcript.trim()) {
+        return Ok(ResultEnvelope {
+            raw_transcript: transcript.trim().to_string(),
+            language,
+            latency_ms: started.elapsed().as_millis() as u64,
+            warnings: vec!["Same as the previous transcript, so it was not processed again".into()],
+            ..ResultEnvelope::default()
+        });
+    }
+
+    let profile = overrides.profile.as_ref();
+    if profile.map_or(config.recording_mode, |profile| profile.mode) == ProfileMode::Transcribe {
+        let transcript = transcript.trim();
+        sink::deliver(app, config, transcript);
+        return Ok(ResultEnvelope {
+            raw_transcript: transcript.to_string(),
+            text: transcript.to_string(),
+            language,
+            latency_ms: started.elapsed().as_millis() as u64,
+            ..ResultEnvelope::default()
+        });
+    }
+
+    let template = match profile.and_then(|profile| profile.template.as_deref()) {
+        Some(name) => config.templates.iter().find(|template| template.name == name),
+        None => template::for_language(config, language.as_deref()),
+    };
+    repeat::record(
+        app,
+        LastAction::Process {
+            transcript: transcript.trim().to_string(),
+            template: template.map(|template| template.name.clone()),
+            overrides: overrides.clone(),
+        },
+    );
+    let mut envelope = process_transcript(app, config, transcript.trim(), template, overrides, started).await?;
+    envelope.language = language;
+    Ok(envelope)
+}
+
+/// Payload of `low-confidence-transcript`
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LowConfidence {
+    confidence: f32,
+    threshold: f32,
+    llm_skipped: bool,
+}
+
+/// Whether the transcript falls below `min_transcript_confidence`, emitting
+/// `low-confidence-transcript` so the UI can offer to record again. Endpoints
+/// that report no confidence always pass.
+fn check_confidence(app: &AppHandle, config: &AppConfig, confidence: Option<f32>) -> bool {
+    let threshold = config.min_transcript_confidence;
+    let Some(confidence) = confidence.filter(|confidence| *confidence < threshold) else {
+        return false;
+    };
+    tracing::warn!("Transcript confidence {confidence:.2} is below {threshold:.2}");
+    let low = LowConfidence { confidence, threshold, llm_skipped: config.skip_llm_on_low_confidence };
+    let _ = app.emit_event("low-confidence-transcript", low);
+    true
+}
+
+/// The last transcript a recording produced, for `duplicate_window_secs`
+#[derive(Default)]
+pub struct DuplicateState {
+    last: Mutex<Option<(Instant, String)>>,
+}
+
+/// Payload of `duplicate-transcript`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DuplicateTranscript<'a> {
+    transcript: &'a str,
+    similarity: f32,
+    ms_since_previous: u64,
+}
+
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()).map(str::to_lowercase).collect()
+}
+
+/// Share of distinct words the two texts have in common, 0.0-1.0; case and
+/// punctuation don't count
+fn similarity(a: &str, b: &str) -> f32 {
+    let (a, b) = (words(a), words(b));
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(&b).count() as f32 / union as f32
+}
+
+/// Whether `transcript` repeats the previous recording's within
+/// `duplicate_window_secs`, which usually means the shortcut fired twice.
+/// Emits `duplicate-transcript` so the UI can offer to process it anyway.
+fn check_duplicate(app: &AppHandle, config: &AppConfig, transcript: &str) -> bool {
+    let state = app.state::<DuplicateState>();
+    let previous = state.last.lock().unwrap().replace((Instant::now(), transcript.to_string()));
+    if config.duplicate_window_secs == 0 {
+        return false;
+    }
+    let Some((at, previous)) = previous.filter(|(at, _)| at.elapsed() < Duration::from_secs(config.duplicate_window_secs)) else {
+        return false;
+    };
+    let similarity = similarity(transcript, &previous);
+    if similarity < config.duplicate_similarity {
+        return false;
+    }
+    tracing::info!("Transcript matches the previous one ({similarity:.2}), not processing it");
+    let ms_since_previous = at.elapsed().as_millis() as u64;
+    let _ = app.emit_event("duplicate-transcript", DuplicateTranscript { transcript, similarity, ms_since_previous });
+    true
+}
+
+/// Wait for a transcript that was uploaded while recording. When the endpoint
+/// couldn't take the stream, the buffered `wav` is uploaded the usual way.
+async fn run_streamed_pipeline(
+    app: &AppHandle,
+    upload: StreamedUpload,
+    wav: Vec<u8>,
+    overrides: &Overrides,
+) -> Result<ResultEnvelope, AppError> {
+    let started = Instant::now();
+    let config = config::get(app);
+    let streamed = async { upload.await.map_err(|e| AppError::Network(e.to_string()))? };
+    match timed(app, "transcription", "Transcribed while recording", streamed).await {
+        Ok(transcript) => {
+            let transcript = retry_empty(app, &config, transcript, wav, "recording.wav").await?;
+            finish_pipeline(app, &config, &config, transcript, overrides, started).await
+        }
+        Err(e @ (AppError::Cancelled | AppError::Auth(_) | AppError::Config(_))) => Err(e),
+        Err(e) => {
+            tracing::warn!("Streamed upload failed, uploading the buffered recording: {e}");
+            run_pipeline(app, wav, "recording.wav", overrides).await
+        }
+    }
+}
+
+/// Template, LLM, sinks and history for a finished transcript
+async fn process_transcript(
+    app: &AppHandle,
+    config: &AppConfig,
+    transcript: &str,
+    template: Option<&PromptTemplate>,
+    overrides: &Overrides,
+    started: Instant,
+) -> Result<ResultEnvelope, AppError> {
+    let token = app.state::<CancelState>().token();
+    let prompt = template::apply_template(template, transcript);
+    let llm_config = overrides.for_llm(config);
+    let query = llm::query_detailed(app, &llm_config, &prompt, &token);
+    match timed(app, "llm", "LLM answered", query).await {
+        Ok(answer) => Ok(deliver_answer(app, config, transcript, &prompt, answer, started).await),
+        Err(e) => llm_failed(app, transcript, e, started),
+    }
+}
+
+/// Post hook, sinks, history and the result envelope for an answered transcript
+async fn deliver_answer(
+    app: &AppHandle,
+    config: &AppConfig,
+    transcript: &str,
+    prompt: &str,
+    mut answer: LlmAnswer,
+    started: Instant,
+) -> ResultEnvelope {
+    let tokens = tokens::estimate(prompt, &answer.model) + tokens::estimate(&answer.text, &answer.model);
+    answer.text = hook::post_process(app, config, answer.text).await;
+    sink::deliver(app, config, &answer.text);
+    if let Err(e) = history::append(app, transcript, &answer.text) {
+        tracing::error!("Failed to record history: {e}");
+    }
+
+    let mut warnings = Vec::new();
+    if answer.fallback_used {
+        warnings.push(format!("Answered by fallback provider {}", answer.provider));
+    }
+    if answer.text.trim().is_empty() {
+        warnings.push("The provider returned an empty answer".into());
+    }
+    ResultEnvelope {
+        tokens: Some(tokens),
+        request_id: app.state::<HttpState>().last_request_id.lock().unwrap().clone(),
+        latency_ms: started.elapsed().as_millis() as u64,
+        raw_transcript: transcript.to_string(),
+        text: answer.text,
+        provider: Some(answer.provider),
+        model: Some(answer.model),
+        language: None,
+        warnings,
+        errors: Vec::new(),
+    }
+}
+
+/// Run a stored transcript through the named template again
+pub async fn reprocess(
+    app: AppHandle,
+    transcript: String,
+    template: Option<String>,
+    overrides: Overrides,
+) -> Result<ResultEnvelope, AppError> {
+    let config = config::get(&app);
+    let template = template.and_then(|name| config.templates.iter().find(|template| template.name == name));
+
+    tray::set_state(&app, TrayState::Processing);
+    let result = process_transcript(&app, &config, &transcript, template, &overrides, Instant::now()).await;
+    tray::set_state(&app, TrayState::Idle);
+
+    emit_final(&app, &result, "llm");
+    if let Err(e) = &result {
+        notify::error(&app, e);
+    }
+    result
+}
+
+/// Ask something new of an existing transcript, e.g. one picked from history:
+/// only the LLM stage runs, with `template` (the active one when unset) and
+/// `overrides`. The answer is delivered and recorded like any other, and
+/// `repeat_last_action` runs it again.
+#[tauri::command]
+pub async fn reprocess_transcript(
+    app: AppHandle,
+    transcript: String,
+    template: Option<String>,
+    overrides: Option<Overrides>,
+) -> Result<ResultEnvelope, AppError> {
+    let transcript = transcript.trim().to_string();
+    if transcript.is_empty() {
+        return Err(AppError::Config("The transcript is empty".into()));
+    }
+    let config = config::get(&app);
+    let template = match template {
+        Some(name) if !config.templates.iter().any(|template| template.name == name) => {
+            return Err(AppError::Config(format!("No template named \"{name}\"")));
+        }
+        Some(name) => Some(name),
+        None => template::active(&config).map(|template| template.name.clone()),
+    };
+    let overrides = overrides.unwrap_or_default();
+    repeat::record(
+        &app,
+        LastAction::Process { transcript: transcript.clone(), template: template.clone(), overrides: overrides.clone() },
+    );
+    reprocess(app, transcript, template, overrides).await
+}
+
+/// Process a finished realtime transcript, reusing `speculation` when it was
+/// started on exactly this transcript
+pub async fn process_realtime(
+    app: AppHandle,
+    raw_transcript: String,
+    speculation: Option<Speculation>,
+) -> Result<ResultEnvelope, AppError> {
+    let started = Instant::now();
+    let config = config::get(&app);
+    let transcript = prepare_transcript(&config, &raw_transcript);
+    if transcript.is_empty() {
+        if let Some(speculation) = &speculation {
+            speculation.cancel();
+        }
+        return Ok(ResultEnvelope { warnings: vec!["No speech was detected".into()], ..ResultEnvelope::default() });
+    }
+
+    let template = template::active(&config);
+    let overrides = Overrides::default();
+    repeat::record(
+        &app,
+        LastAction::Process {
+            transcript: transcript.clone(),
+            template: template.map(|template| template.name.clone()),
+            overrides: overrides.clone(),
+        },
+    );
+
+    tray::set_state(&app, TrayState::Processing);
+    let result = match speculation {
+        Some(speculation) if speculation.transcript == transcript => {
+            let prompt = template::apply_template(template, &transcript);
+            let early = async { speculation.answer.await.map_err(|e| AppError::Network(e.to_string()))? };
+            match timed(&app, "llm", "Early LLM answer ready", early).await {
+                Ok(answer) => Ok(deliver_answer(&app, &config, &transcript, &prompt, answer, started).await),
+                Err(e) => llm_failed(&app, &transcript, e, started),
+            }
+        }
+        stale => {
+            if let Some(speculation) = stale {
+                speculation.cancel();
+            }
+            process_transcript(&app, &config, &transcript, template, &overrides, started).await
+        }
+    };
+    tray::set_state(&app, TrayState::Idle);
+
+    emit_final(&app, &result, "llm");
+    if let Err(e) = &result {
+        notify::error(&app, e);
+    }
+    result
+}
+
+/// Transcribe a recording without post-processing
+#[tauri::command]
+pub async fn transcribe(
+    app: AppHandle,
+    audio: Vec<u8>,
+    file_name: Option<String>,
+    overrides: Option<Overrides>,
+) -> Result<String, AppError> {
+    let overrides = overrides.unwrap_or_default();
+    repeat::record(&app, LastAction::Transcribe { overrides: overrides.clone() });
+    let config = overrides.for_transcription(&config::get(&app));
+    let file_name = file_name.unwrap_or_else(|| "audio.webm".into());
+    let token = app.state::<CancelState>().token();
+
+    tray::set_state(&app, TrayState::Processing);
+    let transcribe = whisper::transcribe_with_fallback(&app, &config, audio, &file_name, &token);
+    let result = timed(&app, "transcription", "Transcribed", transcribe).await;
+    tray::set_state(&app, TrayState::Idle);
+    let result = result.map(|transcript| {
+        check_confidence(&app, &config, transcript.confidence);
+        transcript.text
+    });
+
+    if let Err(e) = &result {
+        notify::error(&app, e);
+    }
+    result
+}
+
+/// Send a prompt straight to the LLM
+#[tauri::command]
+pub async fn query_llm(app: AppHandle, prompt: String, overrides: Option<Overrides>) -> Result<String, AppError> {
+    let saved = config::get(&app);
+    let overrides = overrides.unwrap_or_default();
+    repeat::record(&app, LastAction::Query { prompt: prompt.clone(), overrides: overrides.clone() });
+    let config = overrides.for_llm(&saved);
+    let token = app.state::<CancelState>().token();
+
+    tray::set_state(&app, TrayState::Processing);
+    let result = timed(&app, "llm", "LLM answered", llm::query(&app, &config, &prompt, &token)).await;
+    tray::set_state(&app, TrayState::Idle);
+
+    if let (Ok(_), Some(model)) = (&result, overrides.model) {
+        if saved.remember_last_model && saved.llm_model.as_ref() != Some(&model) {
+            config::remember_model(&app, model);
+        }
+    }
+
+    if let Err(e) = &result {
+        notify::error(&app, e);
+    }
+    result
+}
+
+/// Send a prompt with an image attached to a vision-capable model
+#[tauri::command]
+pub async fn query_llm_with_image(
+    app: AppHandle,
+    prompt: String,
+    image_path: String,
+    overrides: Option<Overrides>,
+) -> Result<String, AppError> {
+    let config = overrides.unwrap_or_default().for_llm(&config::get(&app));
+    let token = app.state::<CancelState>().token();
+
+    tray::set_state(&app, TrayState::Processing);
+    let result = match attachment::load_image(Path::new(&image_path)).await {
+        Ok(image) => timed(&app, "llm", "LLM answered", llm::query_with_image(&app, &config, &prompt, &image, &token)).await,
+        Err(e) => Err(e),
+    };
+    tray::set_state(&app, TrayState::Idle);
+
+    if let Err(e) = &result {
+        notify::error(&app, e);
+    }
+    result
+}
+
+/// Send a prompt straight to the LLM, emitting `llm-delta` with each piece of
+/// the answer as it streams in. The full answer is returned at the end.
+#[tauri::command]
+pub async fn query_llm_stream(app: AppHandle, prompt: String, overrides: Option<Overrides>) -> Result<String, AppError> {
+    let config = overrides.unwrap_or_default().for_llm(&config::get(&app));
+    let token = app.state::<CancelState>().token();
+
+    tray::set_state(&app, TrayState::Processing);
+    let stream = llm::query_stream(&app, &config, &prompt, &token, |text| {
+        let _ = app.emit_event("llm-delta", Text { text });
+    });
+    let result = timed(&app, "llm", "LLM streamed", stream).await;
+    tray::set_state(&app, TrayState::Idle);
+
+    if let Err(e) = &result {
+        notify::error(&app, e);
+    }
+    result
+}
+
+/// Transcribe a recording, run it through the active template and LLM, and
+/// deliver the answer to the configured sinks. The answer is also returned,
+/// along with metadata about how it was produced. If the LLM fails the
+/// transcript is still returned, with the failure listed in `errors`.
+#[tauri::command]
+pub async fn transcribe_and_process(
+    app: AppHandle,
+    audio: Vec<u8>,
+    file_name: Option<String>,
+    overrides: Option<Overrides>,
+) -> Result<ResultEnvelope, AppError> {
+    let file_name = file_name.unwrap_or_else(|| "audio.webm".into());
+    let mut overrides = overrides.unwrap_or_default();
+    // The webview's half of a shortcut-started recording
+    overrides.profile = profile::take(&app);
+
+    tray::set_state(&app, TrayState::Processing);
+    let result = run_pipeline(&app, audio, &file_name, &overrides).await;
+    tray::set_state(&app, TrayState::Idle);
+
+    emit_final(&app, &result, "transcription");
+    if let Err(e) = &result {
+        notify::error(&app, e);
+    }
+    result
+}
+
+/// Stop the native recording and process it like `transcribe_and_process`.
+/// With `stream_upload` the transcript is usually ready as soon as the
+/// endpoint has the last of the audio.
+#[tauri::command]
+pub async fn stop_recording_and_process(app: AppHandle) -> Result<ResultEnvelope, AppError> {
+    let (recording, upload) = audio::stop_with_upload(&app)?;
+    process_recording(app, recording.wav, upload).await
+}
+
+/// Process a stopped native recording, through its streamed upload when there is one
+pub async fn process_recording(app: AppHandle, wav: Vec<u8>, upload: Option<StreamedUpload>) -> Result<ResultEnvelope, AppError> {
+    let overrides = Overrides { profile: profile::take(&app), ..Overrides::default() };
+    tray::set_state(&app, TrayState::Processing);
+    let result = match upload {
+        Some(upload) => run_streamed_pipeline(&app, upload, wav, &overrides).await,
+        None => run_pipeline(&app, wav, "recording.wav", &overrides).await,
+    };
+    tray::set_state(&app, TrayState::Idle);
+
+    emit_final(&app, &result, "transcription");
+    if let Err(e) = &result {
+        notify::error(&app, e);
+    }
+    result
+}
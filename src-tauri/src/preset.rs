@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::config::{self, AppConfig};
+use crate::events::EmitEvent;
+use crate::profile::ProfileMode;
+use crate::shortcut;
+use crate::tray;
+
+/// Capture and processing settings saved under a name, e.g. "meeting notes"
+/// or "quick command", to switch between in one go
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingPreset {
+    pub name: String,
+    // Accelerator that applies the preset; unbound when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shortcut: Option<String>,
+    pub input_device: Option<String>,
+    pub input_gain_db: f32,
+    pub normalize_audio: bool,
+    pub trim_silence: bool,
+    pub min_record_ms: u64,
+    pub stream_upload: bool,
+    pub mode: ProfileMode,
+    pub template: Option<String>,
+    pub model: Option<String>,
+}
+
+impl RecordingPreset {
+    fn from_config(name: String, shortcut: Option<String>, config: &AppConfig) -> Self {
+        Self {
+            name,
+            shortcut,
+            input_device: config.input_device.clone(),
+            input_gain_db: config.input_gain_db,
+            normalize_audio: config.normalize_audio,
+            trim_silence: config.trim_silence,
+            min_record_ms: config.min_record_ms,
+            stream_upload: config.stream_upload,
+            mode: config.recording_mode,
+            template: config.active_template.clone(),
+            model: config.llm_model.clone(),
+        }
+    }
+
+    fn apply_to(&self, config: &mut AppConfig) {
+        config.input_device = self.input_device.clone();
+        config.input_gain_db = self.input_gain_db;
+        config.normalize_audio = self.normalize_audio;
+        config.trim_silence = self.trim_silence;
+        config.min_record_ms = self.min_record_ms;
+        config.stream_upload = self.stream_upload;
+        config.recording_mode = self.mode;
+        config.active_template = self.template.clone();
+        config.llm_model = self.model.clone();
+    }
+}
+
+/// Payload of `preset-applied`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PresetApplied<'a> {
+    name: &'a str,
+}
+
+#[tauri::command]
+pub fn list_presets(app: AppHandle) -> Vec<RecordingPreset> {
+    config::get(&app).presets
+}
+
+/// Save the current capture settings, mode, template and model as preset
+/// `name`, replacing a preset of that name but keeping its shortcut
+#[tauri::command]
+pub fn save_preset(app: AppHandle, name: String) -> Result<RecordingPreset, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Presets need a name".into());
+    }
+    let current = config::get(&app);
+    let shortcut = current.presets.iter().find(|preset| preset.name == name).and_then(|preset| preset.shortcut.clone());
+    let preset = RecordingPreset::from_config(name, shortcut, &current);
+    config::modify(&app, |config| match config.presets.iter_mut().find(|existing| existing.name == preset.name) {
+        Some(existing) => *existing = preset.clone(),
+        None => config.presets.push(preset.clone()),
+    })?;
+    Ok(preset)
+}
+
+/// Switch to preset `name`, all of its settings in one save, emitting `preset-applied`
+#[tauri::command]
+pub fn apply_preset(app: AppHandle, name: String) -> Result<(), String> {
+    let config = config::get(&app);
+    let preset = config.presets.iter().find(|preset| preset.name == name).ok_or_else(|| format!("No preset named \"{name}\""))?;
+    if let Some(template) = preset.template.as_deref().filter(|template| !config.templates.iter().any(|t| t.name == *template)) {
+        return Err(format!("Preset \"{name}\" uses unknown template \"{template}\""));
+    }
+    config::modify(&app, |config| preset.apply_to(config))?;
+    tracing::info!("Applied preset \"{name}\"");
+    let _ = app.emit_event("preset-applied", PresetApplied { name: &name });
+    tray::refresh_tooltip(&app);
+    tray::rebuild_menu(&app);
+    Ok(())
+}
+
+/// What a preset's shortcut runs
+pub fn on_shortcut(app: &AppHandle, name: &str) {
+    if let Err(e) = apply_preset(app.clone(), name.to_string()) {
+        tracing::error!("Failed to apply preset from its shortcut: {e}");
+    }
+}
+
+pub fn validate(config: &AppConfig) -> Result<(), String> {
+    for (index, preset) in config.presets.iter().enumerate() {
+        if preset.name.trim().is_empty() {
+            return Err("Presets need a name".into());
+        }
+        if config.presets[..index].iter().any(|other| other.name == preset.name) {
+            return Err(format!("There is more than one preset named \"{}\"", preset.name));
+        }
+        if let Some(accelerator) = &preset.shortcut {
+            shortcut::parse(accelerator).map_err(|e| format!("Shortcut for preset \"{}\": {e}", preset.name))?;
+        }
+        if let Some(name) = preset.template.as_deref().filter(|name| !config.templates.iter().any(|template| template.name == *name)) {
+            return Err(format!("Preset \"{}\" uses unknown template \"{name}\"", preset.name));
+        }
+    }
+    Ok(())
+}
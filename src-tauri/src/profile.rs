@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::config::{self, AppConfig};
+use crate::events::EmitEvent;
+use crate::foreground;
+
+/// What a recording started over a profile's app turns into
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProfileMode {
+    // Transcribe, then answer through the LLM as usual
+    #[default]
+    Agent,
+    // Deliver the transcript itself; the LLM isn't asked
+    Transcribe,
+}
+
+/// Settings for recordings started while a particular app has focus
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppProfile {
+    // As `foreground::foreground_app` reports it, case-insensitive; ".exe"
+    // may be left off on Windows
+    pub app: String,
+    #[serde(default)]
+    pub mode: ProfileMode,
+    // Template name; the usual choice when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    // LLM model; the configured one when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+impl AppProfile {
+    fn matches(&self, app: &str) -> bool {
+        let wanted = self.app.trim();
+        app.eq_ignore_ascii_case(wanted) || app.strip_suffix(".exe").is_some_and(|stem| stem.eq_ignore_ascii_case(wanted))
+    }
+}
+
+/// Profile picked when the last recording started, until its pipeline takes it
+#[derive(Default)]
+pub struct ProfileState {
+    pending: Mutex<Option<AppProfile>>,
+}
+
+/// Note which profile, if any, applies to the recording being started, from
+/// the app in front right now. Call before the window takes focus. Emits
+/// `app-profile-applied` on a match.
+pub fn capture(app: &AppHandle) {
+    let config = config::get(app);
+    let profile = if config.app_profiles.is_empty() {
+        None
+    } else {
+        foreground::foreground_app().and_then(|id| config.app_profiles.iter().find(|profile| profile.matches(&id)).cloned())
+    };
+    if let Some(profile) = &profile {
+        tracing::info!("Using the profile for {}", profile.app);
+        let _ = app.emit_event("app-profile-applied", profile);
+    }
+    *app.state::<ProfileState>().pending.lock().unwrap() = profile;
+}
+
+/// The profile noted by `capture`, once
+pub fn take(app: &AppHandle) -> Option<AppProfile> {
+    app.state::<ProfileState>().pending.lock().unwrap().take()
+}
+
+pub fn validate(config: &AppConfig) -> Result<(), String> {
+    for profile in &config.app_profiles {
+        if profile.app.trim().is_empty() {
+            return Err("App profiles need an app".into());
+        }
+        if let Some(name) = profile.template.as_deref().filter(|name| !config.templates.iter().any(|template| template.name == *name)) {
+            return Err(format!("The profile for \"{}\" uses unknown template \"{name}\"", profile.app));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,144 @@
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::image::Image;
+use tauri::{AppHandle, Manager};
+
+use crate::config;
+use crate::events::EmitEvent;
+use crate::tray::{self, TRAY_ID};
+use crate::window;
+
+// Latencies kept per stage for the estimate
+const RECENT_LATENCIES: usize = 20;
+const FRAME_INTERVAL: Duration = Duration::from_millis(250);
+// Icon opacity for each animation frame
+const FRAMES: [f32; 4] = [1.0, 0.7, 0.4, 0.7];
+
+/// Recent stage latencies, and the animation that is running. Bumping
+/// `generation` stops the animation thread at its next frame.
+#[derive(Default)]
+pub struct ProgressState {
+    latencies: Mutex<HashMap<&'static str, VecDeque<u64>>>,
+    generation: AtomicU64,
+}
+
+/// Payload of `processing-started`
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Started {
+    stage: &'static str,
+    // Median of recent runs of this stage; none until one has finished
+    estimate_ms: Option<u64>,
+}
+
+/// Payload of `processing-ended`
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Ended {
+    stage: &'static str,
+    elapsed_ms: u64,
+    ok: bool,
+}
+
+fn estimate(state: &ProgressState, stage: &'static str) -> Option<u64> {
+    let latencies = state.latencies.lock().unwrap();
+    let mut recent: Vec<u64> = latencies.get(stage)?.iter().copied().collect();
+    recent.sort_unstable();
+    recent.get(recent.len() / 2).copied()
+}
+
+/// The tray icon at each opacity in `FRAMES`
+fn frames(icon: &Image<'_>) -> Vec<Image<'static>> {
+    FRAMES
+        .iter()
+        .map(|opacity| {
+            let mut rgba = icon.rgba().to_vec();
+            for pixel in rgba.chunks_exact_mut(4) {
+                pixel[3] = (pixel[3] as f32 * opacity) as u8;
+            }
+            Image::new_owned(rgba, icon.width(), icon.height())
+        })
+        .collect()
+}
+
+/// Pulse the tray icon and count up in its tooltip until the stage ends
+fn animate(app: &AppHandle, stage: &'static str, generation: u64, started: Instant) {
+    let Some(icon) = tray::themed_icon(app) else {
+        return;
+    };
+    let label = match stage {
+        "transcription" => "Transcribing",
+        "llm" => "Waiting for the LLM",
+        _ => "Processing",
+    };
+    let app = app.clone();
+    thread::spawn(move || {
+        let frames = frames(&icon);
+        for frame in frames.iter().cycle() {
+            if app.state::<ProgressState>().generation.load(Ordering::SeqCst) != generation {
+                break;
+            }
+            if let Some(tray) = app.tray_by_id(TRAY_ID) {
+                let _ = tray.set_icon(Some(frame.clone()));
+                let tooltip = format!("AMA Agent - {label} ({}s)", started.elapsed().as_secs());
+                let _ = tray.set_tooltip(Some(tooltip));
+            }
+            thread::sleep(FRAME_INTERVAL);
+        }
+        tray::refresh_icon(&app);
+        tray::refresh_tooltip(&app);
+    });
+}
+
+/// Announce that a transcription or LLM stage is running. With the window
+/// hidden, the tray icon animates instead (`animate_tray_while_processing`).
+pub fn start(app: &AppHandle, stage: &'static str) -> Instant {
+    let started = Instant::now();
+    let state = app.state::<ProgressState>();
+    let generation = state.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    let _ = app.emit_event("processing-started", Started { stage, estimate_ms: estimate(&state, stage) });
+    if config::get(app).animate_tray_while_processing && !window::is_main_visible(app) {
+        animate(app, stage, generation, started);
+    }
+    started
+}
+
+/// Announce the end of a stage and stop the tray animation; successful runs
+/// feed the next estimate
+pub fn end(app: &AppHandle, stage: &'static str, started: Instant, ok: bool) {
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    let state = app.state::<ProgressState>();
+    state.generation.fetch_add(1, Ordering::SeqCst);
+    if ok {
+        let mut latencies = state.latencies.lock().unwrap();
+        let recent = latencies.entry(stage).or_default();
+        if recent.len() == RECENT_LATENCIES {
+            recent.pop_front();
+        }
+        recent.push_back(elapsed_ms);
+    }
+    let _ = app.emit_event("processing-ended", Ended { stage, elapsed_ms, ok });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{emitted, SCHEMA_VERSION};
+    use serde_json::json;
+
+    #[test]
+    fn event_payloads_serialize_camel_case() {
+        assert_eq!(
+            emitted(Started { stage: "transcription", estimate_ms: Some(1200) }),
+            json!({ "schemaVersion": SCHEMA_VERSION, "stage": "transcription", "estimateMs": 1200 })
+        );
+        assert_eq!(
+            emitted(Ended { stage: "transcription", elapsed_ms: 900, ok: true }),
+            json!({ "schemaVersion": SCHEMA_VERSION, "stage": "transcription", "elapsedMs": 900, "ok": true })
+        );
+    }
+}
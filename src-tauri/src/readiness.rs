@@ -0,0 +1,99 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::audio;
+use crate::config;
+use crate::events::EmitEvent;
+use crate::llm::Provider;
+use crate::permission::{self, PermissionState};
+use crate::whisper;
+
+// How often preconditions nothing reports on (a device plugged in) are re-checked
+const READINESS_CHECK_SECS: u64 = 10;
+
+/// One thing recording depends on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Precondition {
+    id: &'static str,
+    ok: bool,
+    // What to do about it, when not ok
+    missing: Option<String>,
+}
+
+impl Precondition {
+    fn new(id: &'static str, missing: Option<String>) -> Self {
+        Self { id, ok: missing.is_none(), missing }
+    }
+}
+
+/// Returned by `check_ready_to_record` and emitted as `record-readiness-changed`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadinessReport {
+    ready: bool,
+    preconditions: Vec<Precondition>,
+}
+
+/// Last emitted report, so `record-readiness-changed` only fires on a change
+#[derive(Default)]
+pub struct ReadinessState {
+    last: Mutex<Option<ReadinessReport>>,
+}
+
+fn check(app: &AppHandle) -> ReadinessReport {
+    let config = config::get(app);
+    let mic_permission = match permission::check() {
+        PermissionState::Granted => None,
+        PermissionState::Denied => Some("Allow microphone access in the system privacy settings".into()),
+        PermissionState::NotDetermined => Some("Grant microphone access".into()),
+    };
+    let llm_key = match Provider::from_name(&config.llm_provider) {
+        Ok(provider) if provider.api_key(&config).is_empty() => Some(format!("Set an API key for {}", provider.name())),
+        Ok(_) => None,
+        Err(e) => Some(e.to_string()),
+    };
+
+    let preconditions = vec![
+        Precondition::new("config_dir", config::ensure_dirs().err()),
+        Precondition::new("mic_permission", mic_permission),
+        Precondition::new("input_device", audio::find_input_device(config.input_device.as_deref()).err().map(|e| e.to_string())),
+        Precondition::new("transcription_key", whisper::api_key(&config).err().map(|e| e.to_string())),
+        Precondition::new("llm_key", llm_key),
+    ];
+    ReadinessReport { ready: preconditions.iter().all(|precondition| precondition.ok), preconditions }
+}
+
+/// Re-check every precondition, emitting `record-readiness-changed` when the
+/// report differs from the last one
+pub fn refresh(app: &AppHandle) -> ReadinessReport {
+    let report = check(app);
+    let state = app.state::<ReadinessState>();
+    let mut last = state.last.lock().unwrap();
+    if last.as_ref() != Some(&report) {
+        if !report.ready {
+            let missing: Vec<&str> = report.preconditions.iter().filter(|p| !p.ok).map(|p| p.id).collect();
+            tracing::info!("Not ready to record: {}", missing.join(", "));
+        }
+        let _ = app.emit_event("record-readiness-changed", report.clone());
+        *last = Some(report.clone());
+    }
+    report
+}
+
+/// Whether a recording can start right now, with what's missing if not
+#[tauri::command]
+pub fn check_ready_to_record(app: AppHandle) -> ReadinessReport {
+    refresh(&app)
+}
+
+/// Keep re-checking in the background, for changes made outside the app
+pub fn watch(app: AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_secs(READINESS_CHECK_SECS));
+        refresh(&app);
+    });
+}
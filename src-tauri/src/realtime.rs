@@ -0,0 +1,366 @@
+use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::audio::{self, StreamedUpload};
+use crate::cancel::CancelState;
+use crate::config::{self, AppConfig};
+use crate::error::AppError;
+use crate::events::{EmitEvent, Empty};
+use crate::notify;
+use crate::pipeline::{self, PipelineMode, ResultEnvelope, Speculation};
+use crate::replace;
+use crate::typing::{self, Typist};
+use crate::upload;
+
+const CONNECT_TIMEOUT_SECS: u64 = 10;
+// How long to wait for the server's last words after the mic stops
+const FINISH_TIMEOUT_SECS: u64 = 10;
+
+/// How a realtime transcription ended up running
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RealtimeMode {
+    Realtime,
+    // The socket couldn't be opened, so this is a plain native recording
+    Batch,
+}
+
+/// Payload of `realtime-transcript`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TranscriptEvent {
+    text: String,
+    is_final: bool,
+}
+
+enum Session {
+    Realtime {
+        reader: JoinHandle<String>,
+        // Latest early LLM run in streaming pipeline mode
+        speculation: Arc<Mutex<Option<Speculation>>>,
+    },
+    Batch,
+}
+
+/// What a stopped session left behind
+enum Finished {
+    Realtime { transcript: String, speculation: Option<Speculation> },
+    Batch(Vec<u8>, Option<StreamedUpload>),
+}
+
+#[derive(Default)]
+pub struct RealtimeState {
+    session: Mutex<Option<Session>>,
+    // The session types into the focused app instead of returning a transcript
+    dictating: AtomicBool,
+}
+
+/// True while a realtime (or fallback batch) session is capturing
+pub fn is_active(app: &AppHandle) -> bool {
+    app.state::<RealtimeState>().session.lock().unwrap().is_some()
+}
+
+/// True while continuous dictation is running
+pub fn is_dictating(app: &AppHandle) -> bool {
+    is_active(app) && app.state::<RealtimeState>().dictating.load(Ordering::SeqCst)
+}
+
+/// Forget a session whose capture was torn down elsewhere. The socket closes
+/// by itself once the capture's tap is gone.
+pub fn abandon(app: &AppHandle) {
+    if let Some(Session::Realtime { speculation, .. }) = app.state::<RealtimeState>().session.lock().unwrap().take() {
+        if let Some(speculation) = speculation.lock().unwrap().take() {
+            speculation.cancel();
+        }
+    }
+}
+
+/// Read a server message as `(text, is_final)`. JSON messages carry `text` or
+/// `transcript`; anything else is taken as plain text.
+fn parse_message(raw: &str) -> Option<(String, bool)> {
+    let Ok(event) = serde_json::from_str::<serde_json::Value>(raw) else {
+        return Some((raw.to_string(), false));
+    };
+    let text = event["text"].as_str().or(event["transcript"].as_str())?;
+    let is_final = event["is_final"].as_bool().or(event["isFinal"].as_bool()).unwrap_or(false);
+    Some((text.to_string(), is_final))
+}
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+async fn connect(config: &AppConfig) -> Result<Socket, AppError> {
+    let mut request = config
+        .realtime_url
+        .as_str()
+        .into_client_request()
+        .map_err(|e| AppError::Config(format!("Invalid realtime URL: {e}")))?;
+    let api_key = if config.whisper_api_key.is_empty() { &config.openai_api_key } else { &config.whisper_api_key };
+    if !api_key.is_empty() {
+        let header = HeaderValue::from_str(&format!("Bearer {api_key}")).map_err(|e| AppError::Config(e.to_string()))?;
+        request.headers_mut().insert("Authorization", header);
+    }
+
+    let (socket, _) = tokio::time::timeout(Duration::from_secs(CONNECT_TIMEOUT_SECS), tokio_tungstenite::connect_async(request))
+        .await
+        .map_err(|_| AppError::Timeout)?
+        .map_err(|e| AppError::Network(e.to_string()))?;
+    Ok(socket)
+}
+
+/// Keystrokes that keep the focused field in step with a dictation session
+struct Dictation {
+    typist: Typist,
+    // Also type words before they are final, correcting them with backspace
+    type_partials: bool,
+    // Whether anything final has been typed, so the next segment needs a space
+    started: bool,
+    // The partial as typed so far
+    typed_partial: String,
+}
+
+impl Dictation {
+    fn separator(&self) -> &'static str {
+        if self.started { " " } else { "" }
+    }
+
+    fn partial(&mut self, text: &str) {
+        if !self.type_partials {
+            return;
+        }
+        let target = format!("{}{text}", self.separator());
+        self.typist.revise(&self.typed_partial, &target);
+        self.typed_partial = target;
+    }
+
+    fn finished(&mut self, text: &str) {
+        if text.is_empty() {
+            self.typist.revise(&self.typed_partial, "");
+            self.typed_partial.clear();
+            return;
+        }
+        let target = format!("{}{text}", self.separator());
+        self.typist.revise(&self.typed_partial, &target);
+        self.typed_partial.clear();
+        self.started = true;
+    }
+}
+
+/// Stream the microphone to `realtime_url`, emitting `realtime-transcript` as
+/// text arrives. Falls back to a normal recording when the socket can't be opened.
+#[tauri::command]
+pub async fn start_realtime_transcription(app: AppHandle) -> Result<RealtimeMode, AppError> {
+    start(app, None).await
+}
+
+async fn start(app: AppHandle, mut dictation: Option<Dictation>) -> Result<RealtimeMode, AppError> {
+    if app.state::<RealtimeState>().session.lock().unwrap().is_some() {
+        return Err(AppError::Audio("Realtime transcription already running".into()));
+    }
+    let config = config::get(&app);
+    if config.realtime_url.is_empty() {
+        return Err(AppError::Config("Realtime URL is not set".into()));
+    }
+    app.state::<RealtimeState>().dictating.store(dictation.is_some(), Ordering::SeqCst);
+
+    let socket = match connect(&config).await {
+        Ok(socket) => socket,
+        Err(e) => {
+            tracing::warn!("Realtime transcription unavailable, recording for batch instead: {e}");
+            audio::start_recording(app.clone())?;
+            *app.state::<RealtimeState>().session.lock().unwrap() = Some(Session::Batch);
+            return Ok(RealtimeMode::Batch);
+        }
+    };
+
+    let (tap, mut chunks) = mpsc::unbounded_channel();
+    audio::start_tapped_recording(&app, tap)?;
+    let (mut write, mut read) = socket.split();
+
+    // `cancel_all` tears the session down: the upload stops, which closes the socket
+    let token = app.state::<CancelState>().token();
+    // A dictation session never goes to the LLM
+    let streaming = config.pipeline_mode == PipelineMode::Streaming && dictation.is_none();
+    let speculation: Arc<Mutex<Option<Speculation>>> = Arc::default();
+
+    // The tap closes when the capture stream is dropped, which ends the upload
+    let writer_token = token.clone();
+    tauri::async_runtime::spawn(async move {
+        while let Some(Some(chunk)) = writer_token.run_until_cancelled(chunks.recv()).await {
+            if write.send(Message::Binary(upload::encode_pcm16(&chunk).into())).await.is_err() {
+                return;
+            }
+        }
+        let _ = write.send(Message::Close(None)).await;
+    });
+
+    let reader_app = app.clone();
+    let reader_speculation = speculation.clone();
+    let reader = tauri::async_runtime::spawn(async move {
+        let mut finished = Vec::new();
+        let mut partial = String::new();
+        while let Some(Ok(message)) = read.next().await {
+            let Message::Text(raw) = message else {
+                continue;
+            };
+            let Some((text, is_final)) = parse_message(&raw) else {
+                continue;
+            };
+            let _ = reader_app.emit_event("realtime-transcript", TranscriptEvent { text: text.clone(), is_final });
+            if let Some(dictation) = &mut dictation {
+                let text = replace::apply_replacements(&config.replacements, &text);
+                if is_final {
+                    dictation.finished(text.trim());
+                } else {
+                    dictation.partial(text.trim());
+                }
+            }
+            if is_final {
+                finished.push(text);
+                partial.clear();
+                if streaming && !token.is_cancelled() {
+                    let next = Speculation::start(&reader_app, &finished.join(" "));
+                    if let Some(previous) = reader_speculation.lock().unwrap().replace(next) {
+                        previous.cancel();
+                    }
+                }
+            } else {
+                partial = text;
+            }
+        }
+        if token.is_cancelled() {
+            audio::discard(&reader_app);
+        } else if let Some(dictation) = &mut dictation {
+            // The server stopped before finalizing the last words
+            dictation.finished(replace::apply_replacements(&config.replacements, &partial).trim());
+        }
+        if !partial.is_empty() {
+            finished.push(partial);
+        }
+        finished.join(" ")
+    });
+
+    *app.state::<RealtimeState>().session.lock().unwrap() = Some(Session::Realtime { reader, speculation });
+    Ok(RealtimeMode::Realtime)
+}
+
+/// Stop the microphone and wait for the server's last words
+async fn finish(app: &AppHandle) -> Result<Finished, AppError> {
+    let session = app
+        .state::<RealtimeState>()
+        .session
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or_else(|| AppError::Audio("Realtime transcription is not running".into()))?;
+
+    match session {
+        Session::Batch => {
+            let (recording, upload) = audio::stop_with_upload(app)?;
+            Ok(Finished::Batch(recording.wav, upload))
+        }
+        Session::Realtime { reader, speculation } => {
+            audio::discard(app);
+            let transcript = tokio::time::timeout(Duration::from_secs(FINISH_TIMEOUT_SECS), reader)
+                .await
+                .map_err(|_| AppError::Timeout)?
+                .map_err(|e| AppError::Network(e.to_string()))?;
+            let speculation = speculation.lock().unwrap().take();
+            Ok(Finished::Realtime { transcript, speculation })
+        }
+    }
+}
+
+/// Stop the microphone and return the full transcript. A batch fallback is
+/// transcribed now through the regular Whisper endpoint.
+#[tauri::command]
+pub async fn stop_realtime_transcription(app: AppHandle) -> Result<String, AppError> {
+    match finish(&app).await? {
+        Finished::Batch(wav, upload) => {
+            if let Some(upload) = upload {
+                upload.abort();
+            }
+            pipeline::transcribe(app, wav, Some("recording.wav".into()), None).await
+        }
+        Finished::Realtime { transcript, speculation } => {
+            if let Some(speculation) = speculation {
+                speculation.cancel();
+            }
+            Ok(replace::apply_replacements(&config::get(&app).replacements, &transcript))
+        }
+    }
+}
+
+/// Stop the microphone and run the transcript through the template and LLM,
+/// like `transcribe_and_process`. In streaming pipeline mode the answer may
+/// already be underway.
+#[tauri::command]
+pub async fn stop_realtime_and_process(app: AppHandle) -> Result<ResultEnvelope, AppError> {
+    match finish(&app).await? {
+        Finished::Batch(wav, upload) => pipeline::process_recording(app, wav, upload).await,
+        Finished::Realtime { transcript, speculation } => pipeline::process_realtime(app, transcript, speculation).await,
+    }
+}
+
+/// Start continuous dictation: words are typed into the focused app as the
+/// realtime endpoint finalizes them, and never go to the LLM. Without a
+/// realtime connection this records normally and types everything on stop.
+#[tauri::command]
+pub async fn start_dictation(app: AppHandle) -> Result<RealtimeMode, AppError> {
+    let config = config::get(&app);
+    let dictation = Dictation {
+        typist: Typist::spawn(config.typing_delay_ms),
+        type_partials: config.dictation_type_partials,
+        started: false,
+        typed_partial: String::new(),
+    };
+    let mode = start(app.clone(), Some(dictation)).await?;
+    let _ = app.emit_event("dictation-started", Empty {});
+    Ok(mode)
+}
+
+/// Stop continuous dictation. A batch fallback is transcribed and typed now.
+#[tauri::command]
+pub async fn stop_dictation(app: AppHandle) -> Result<(), AppError> {
+    app.state::<RealtimeState>().dictating.store(false, Ordering::SeqCst);
+    let finished = finish(&app).await;
+    let _ = app.emit_event("dictation-stopped", Empty {});
+    match finished? {
+        Finished::Batch(wav, upload) => {
+            if let Some(upload) = upload {
+                upload.abort();
+            }
+            let text = pipeline::transcribe(app.clone(), wav, Some("recording.wav".into()), None).await?;
+            let delay_ms = config::get(&app).typing_delay_ms;
+            tauri::async_runtime::spawn_blocking(move || typing::type_text(text.trim(), delay_ms))
+                .await
+                .map_err(|e| AppError::Audio(e.to_string()))?
+                .map_err(AppError::Audio)
+        }
+        Finished::Realtime { speculation, .. } => {
+            if let Some(speculation) = speculation {
+                speculation.cancel();
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Shortcut entry point: start dictation, or stop the running session
+pub async fn toggle_dictation(app: AppHandle) {
+    let result = if is_dictating(&app) { stop_dictation(app.clone()).await } else { start_dictation(app.clone()).await.map(drop) };
+    if let Err(e) = result {
+        tracing::error!("Dictation failed: {e}");
+        notify::error(&app, &e);
+    }
+}
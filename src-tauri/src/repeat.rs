@@ -0,0 +1,62 @@
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::clipboard;
+use crate::config::Overrides;
+use crate::error::AppError;
+use crate::events::{EmitEvent, Empty, Text};
+use crate::pipeline;
+
+/// Parameters of the last operation, enough to run it again
+#[derive(Debug, Clone)]
+pub enum LastAction {
+    // Plain transcription; repeating starts a fresh recording
+    Transcribe { overrides: Overrides },
+    Query { prompt: String, overrides: Overrides },
+    // Transcript that went through a template and the LLM; repeated from the stored transcript
+    Process { transcript: String, template: Option<String>, overrides: Overrides },
+    Clipboard,
+}
+
+#[derive(Default)]
+pub struct LastActionState {
+    last: Mutex<Option<LastAction>>,
+}
+
+pub fn record(app: &AppHandle, action: LastAction) {
+    *app.state::<LastActionState>().last.lock().unwrap() = Some(action);
+}
+
+/// Run the last action again. Returns its answer, or `None` when it needs a new
+/// recording first (`start-recording` is emitted) or nothing has run yet
+/// (`no-last-action` is emitted).
+#[tauri::command]
+pub async fn repeat_last_action(app: AppHandle) -> Result<Option<String>, AppError> {
+    let last = app.state::<LastActionState>().last.lock().unwrap().clone();
+    match last {
+        None => {
+            let _ = app.emit_event("no-last-action", Empty {});
+            Ok(None)
+        }
+        Some(LastAction::Transcribe { overrides }) => {
+            let _ = app.emit_event("start-recording", overrides);
+            Ok(None)
+        }
+        Some(LastAction::Query { prompt, overrides }) => pipeline::query_llm(app, prompt, Some(overrides)).await.map(Some),
+        Some(LastAction::Process { transcript, template, overrides }) => {
+            pipeline::reprocess(app, transcript, template, overrides).await.map(|envelope| Some(envelope.text))
+        }
+        Some(LastAction::Clipboard) => clipboard::query_from_clipboard(app).await,
+    }
+}
+
+/// Shortcut entry point: the answer is emitted as `repeat-result`
+pub async fn repeat_from_shortcut(app: AppHandle) {
+    match repeat_last_action(app.clone()).await {
+        Ok(Some(answer)) => {
+            let _ = app.emit_event("repeat-result", Text { text: &answer });
+        }
+        Ok(None) => {}
+        Err(e) => tracing::error!("Failed to repeat last action: {e}"),
+    }
+}
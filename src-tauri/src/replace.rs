@@ -0,0 +1,77 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// A correction applied to every transcript, e.g. a name Whisper keeps mis-hearing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replacement {
+    pub from: String,
+    pub to: String,
+    // Treat `from` as a regex; `to` may then use `$1`-style captures
+    #[serde(default)]
+    pub regex: bool,
+}
+
+/// Check every regex rule compiles, so bad patterns are caught on save
+pub fn validate(rules: &[Replacement]) -> Result<(), String> {
+    for rule in rules.iter().filter(|rule| rule.regex) {
+        Regex::new(&rule.from).map_err(|e| format!("Replacement \"{}\": {e}", rule.from))?;
+    }
+    Ok(())
+}
+
+/// Apply the rules in order. Empty patterns and (unvalidated) bad regexes are skipped.
+pub fn apply_replacements(rules: &[Replacement], text: &str) -> String {
+    rules
+        .iter()
+        .filter(|rule| !rule.from.is_empty())
+        .fold(text.to_string(), |text, rule| {
+            if !rule.regex {
+                return text.replace(&rule.from, &rule.to);
+            }
+            match Regex::new(&rule.from) {
+                Ok(pattern) => pattern.replace_all(&text, rule.to.as_str()).into_owned(),
+                Err(_) => text,
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(from: &str, to: &str, regex: bool) -> Replacement {
+        Replacement { from: from.into(), to: to.into(), regex }
+    }
+
+    #[test]
+    fn literal_rules_replace_every_occurrence() {
+        let rules = [rule("git hub", "GitHub", false)];
+        assert_eq!(apply_replacements(&rules, "git hub and git hub"), "GitHub and GitHub");
+    }
+
+    #[test]
+    fn literal_rules_are_case_sensitive() {
+        let rules = [rule("jira", "Jira", false)];
+        assert_eq!(apply_replacements(&rules, "JIRA jira"), "JIRA Jira");
+    }
+
+    #[test]
+    fn regex_rules_use_captures_and_flags() {
+        let rules = [rule(r"(?i)\bjira-(\d+)", "JIRA-$1", true)];
+        assert_eq!(apply_replacements(&rules, "see Jira-12 and jira-7"), "see JIRA-12 and JIRA-7");
+    }
+
+    #[test]
+    fn rules_apply_in_order() {
+        let rules = [rule("a", "b", false), rule("b", "c", false)];
+        assert_eq!(apply_replacements(&rules, "a"), "c");
+    }
+
+    #[test]
+    fn invalid_and_empty_patterns_are_skipped() {
+        let rules = [rule("(unclosed", "x", true), rule("", "x", false), rule("ok", "OK", false)];
+        assert_eq!(apply_replacements(&rules, "ok (unclosed"), "OK (unclosed");
+        assert!(validate(&rules).is_err());
+        assert!(validate(&rules[1..]).is_ok());
+    }
+}
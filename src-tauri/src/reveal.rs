@@ -0,0 +1,67 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tauri::AppHandle;
+
+use crate::config;
+use crate::error::AppError;
+
+/// Directories the app writes into; only files under these can be revealed
+fn managed_dirs(app: &AppHandle) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = config::config_dir().into_iter().collect();
+    let config = config::get(app);
+    if let Some(parent) = Path::new(&config.result_file_path).parent() {
+        // A templated directory can't be checked against a fixed root
+        if !parent.as_os_str().is_empty() && !parent.to_string_lossy().contains("{timestamp}") {
+            dirs.push(parent.to_path_buf());
+        }
+    }
+    dirs.into_iter().filter_map(|dir| dir.canonicalize().ok()).collect()
+}
+
+#[cfg(target_os = "macos")]
+fn reveal(path: &Path) -> std::io::Result<()> {
+    Command::new("open").arg("-R").arg(path).spawn().map(drop)
+}
+
+#[cfg(target_os = "windows")]
+fn reveal(path: &Path) -> std::io::Result<()> {
+    let mut select = std::ffi::OsString::from("/select,");
+    select.push(path);
+    Command::new("explorer").arg(select).spawn().map(drop)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn reveal(path: &Path) -> std::io::Result<()> {
+    // Most file managers implement the FileManager1 interface, which can select
+    // the file; otherwise fall back to opening the containing folder
+    let uri = format!("file://{}", path.display());
+    let selected = Command::new("dbus-send")
+        .args([
+            "--session",
+            "--print-reply",
+            "--dest=org.freedesktop.FileManager1",
+            "/org/freedesktop/FileManager1",
+            "org.freedesktop.FileManager1.ShowItems",
+        ])
+        .arg(format!("array:string:{uri}"))
+        .arg("string:")
+        .output()
+        .is_ok_and(|output| output.status.success());
+    if selected {
+        return Ok(());
+    }
+    Command::new("xdg-open").arg(path.parent().unwrap_or(path)).spawn().map(drop)
+}
+
+/// Select a saved recording or result in the system file manager
+#[tauri::command]
+pub fn reveal_in_file_manager(app: AppHandle, path: String) -> Result<(), AppError> {
+    let path = Path::new(&path)
+        .canonicalize()
+        .map_err(|e| AppError::Io(format!("{path}: {e}")))?;
+    if !managed_dirs(&app).iter().any(|dir| path.starts_with(dir)) {
+        return Err(AppError::Io(format!("{} is not in an app directory", path.display())));
+    }
+    reveal(&path)?;
+    Ok(())
+}
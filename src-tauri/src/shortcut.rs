@@ -0,0 +1,664 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Listener, Manager};
+use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
+
+use crate::activity;
+use crate::audio;
+use crate::cancel;
+use crate::clipboard;
+use crate::config::{self, AppConfig};
+use crate::events::{DoNotDisturbChanged, EmitEvent, Empty, Reregistered};
+use crate::fullscreen;
+use crate::headless;
+use crate::pipeline;
+use crate::preset;
+use crate::profile;
+use crate::realtime;
+use crate::repeat;
+use crate::template;
+use crate::tray::{self, TrayState};
+use crate::window;
+
+// How long capture_next_shortcut waits for a key press
+const CAPTURE_TIMEOUT_SECS: u64 = 10;
+// How often registrations are checked, and how far the wall clock must jump
+// past that to count as a sleep/wake
+const RESUME_CHECK_SECS: u64 = 30;
+const RESUME_GAP_SECS: u64 = 60;
+
+pub const ACTION_TOGGLE: &str = "toggle";
+pub const ACTION_CANCEL: &str = "cancel";
+pub const ACTION_PROCESS_CLIPBOARD: &str = "process-clipboard";
+pub const ACTION_REPEAT_LAST: &str = "repeat-last";
+pub const ACTION_CYCLE_TEMPLATE: &str = "cycle-template";
+pub const ACTION_OPEN_SETTINGS: &str = "open-settings";
+// Hard reset for privacy; unbound unless the user picks a key
+pub const ACTION_PANIC: &str = "panic";
+// Starts and stops continuous dictation; unbound by default
+pub const ACTION_DICTATE: &str = "dictate";
+const ACTIONS: &[&str] = &[
+    ACTION_TOGGLE,
+    ACTION_CANCEL,
+    ACTION_PROCESS_CLIPBOARD,
+    ACTION_REPEAT_LAST,
+    ACTION_CYCLE_TEMPLATE,
+    ACTION_OPEN_SETTINGS,
+    ACTION_PANIC,
+    ACTION_DICTATE,
+];
+
+/// Set while shortcuts are deliberately unregistered, so the resume watcher
+/// doesn't put them back
+#[derive(Default)]
+pub struct RegistrationState {
+    suspended: AtomicBool,
+    // Last accepted press of each action, for debounce
+    last_fired: Mutex<HashMap<&'static str, Instant>>,
+    startup: Mutex<Startup>,
+}
+
+/// Holds presses back until setup has finished; the latest one waits in
+/// `pending` and is replayed once
+#[derive(Debug, Default)]
+struct Startup {
+    ready: bool,
+    pending: Option<&'static str>,
+}
+
+impl Startup {
+    /// Whether `action` runs now. Before ready a press is held back instead,
+    /// replacing any earlier one, and a release is dropped.
+    fn admit(&mut self, action: &'static str, state: ShortcutState) -> bool {
+        if self.ready {
+            return true;
+        }
+        if state == ShortcutState::Pressed {
+            tracing::info!("Shortcut {action} pressed during startup, running it once setup finishes");
+            self.pending = Some(action);
+        }
+        false
+    }
+
+    /// Become ready, handing back the held press to replay, if any
+    fn mark_ready(&mut self) -> Option<&'static str> {
+        self.ready = true;
+        self.pending.take()
+    }
+}
+
+fn set_suspended(app: &AppHandle, suspended: bool) {
+    app.state::<RegistrationState>().suspended.store(suspended, Ordering::SeqCst);
+}
+
+/// Manual switch that silences shortcuts, for when full-screen detection misses
+#[derive(Default)]
+pub struct DoNotDisturb(AtomicBool);
+
+#[tauri::command]
+pub fn set_do_not_disturb(app: AppHandle, enabled: bool) {
+    app.state::<DoNotDisturb>().0.store(enabled, Ordering::SeqCst);
+    let _ = app.emit_event("do-not-disturb-changed", DoNotDisturbChanged { enabled });
+}
+
+#[tauri::command]
+pub fn get_do_not_disturb(app: AppHandle) -> bool {
+    app.state::<DoNotDisturb>().0.load(Ordering::SeqCst)
+}
+
+/// Shortcuts are ignored in do-not-disturb, or over a full-screen app when
+/// `suppress_in_fullscreen` is on. Cancel still works either way.
+fn is_suppressed(app: &AppHandle) -> bool {
+    get_do_not_disturb(app.clone()) || (config::get(app).suppress_in_fullscreen && fullscreen::foreground_is_fullscreen())
+}
+
+/// What the toggle shortcut does while a recording is already running
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordingReentry {
+    // Stop and transcribe what was recorded
+    #[default]
+    Stop,
+    // Discard the recording
+    Cancel,
+    Ignore,
+}
+
+/// Payload of `shortcut-fired`: which action ran and what the backend did about it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ShortcutFired {
+    action: &'static str,
+    was_visible: bool,
+    // record, record-hidden, summon, stop, cancel, ignore, process, repeat,
+    // template, settings, panic, dictate, stop-dictation or suppressed
+    mode: &'static str,
+}
+
+fn emit_fired(app: &AppHandle, action: &'static str, mode: &'static str) {
+    let was_visible = window::is_main_visible(app);
+    let _ = app.emit_event("shortcut-fired", ShortcutFired { action, was_visible, mode });
+    activity::record(app, "shortcut", format!("{action}: {mode}"), None);
+}
+
+/// Like `is_suppressed`, reporting the ignored press as `shortcut-fired`
+fn suppress(app: &AppHandle, action: &'static str, state: ShortcutState) -> bool {
+    let suppressed = is_suppressed(app);
+    if suppressed && state == ShortcutState::Pressed {
+        emit_fired(app, action, "suppressed");
+    }
+    suppressed
+}
+
+/// An accelerator as the backend resolved it
+#[derive(Debug, Clone, Serialize)]
+pub struct ParsedShortcut {
+    /// Canonical form, e.g. `Ctrl+Shift+Space`
+    pub accelerator: String,
+    pub modifiers: Vec<String>,
+    pub key: String,
+}
+
+fn modifier_names(mods: Modifiers) -> Vec<String> {
+    [
+        (Modifiers::CONTROL, "Ctrl"),
+        (Modifiers::ALT, "Alt"),
+        (Modifiers::SHIFT, "Shift"),
+        (Modifiers::SUPER, "Super"),
+    ]
+    .into_iter()
+    .filter(|(flag, _)| mods.contains(*flag))
+    .map(|(_, name)| name.to_string())
+    .collect()
+}
+
+fn is_typing_key(key: Code) -> bool {
+    let name = key.to_string();
+    name.starts_with("Key") || name.starts_with("Digit") || matches!(key, Code::Space | Code::Enter | Code::Tab | Code::Backspace)
+}
+
+// Friendlier names for media keys, as printed on keyboards and headsets
+const KEY_ALIASES: &[(&str, &str)] = &[
+    ("playpause", "MediaPlayPause"),
+    ("play/pause", "MediaPlayPause"),
+    ("nexttrack", "MediaTrackNext"),
+    ("prevtrack", "MediaTrackPrevious"),
+    ("previoustrack", "MediaTrackPrevious"),
+    ("mute", "AudioVolumeMute"),
+];
+
+// Names people try for mouse buttons; no global shortcut backend can grab those
+const MOUSE_BUTTONS: &[&str] = &["mouse", "button", "xbutton", "lmb", "rmb", "mmb", "wheel"];
+
+/// Human name of the OS the shortcut backend runs on, for error messages
+fn platform() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "macOS"
+    } else if cfg!(target_os = "windows") {
+        "Windows"
+    } else {
+        "Linux (X11)"
+    }
+}
+
+/// Replace a media key alias in the last part of `accelerator` with its `Code` name
+fn resolve_alias(accelerator: &str) -> String {
+    let (mods, key) = accelerator.rsplit_once('+').unwrap_or(("", accelerator));
+    match KEY_ALIASES.iter().find(|(alias, _)| alias.eq_ignore_ascii_case(key.trim())) {
+        Some((_, code)) if mods.is_empty() => code.to_string(),
+        Some((_, code)) => format!("{mods}+{code}"),
+        None => accelerator.to_string(),
+    }
+}
+
+/// Why the key in an accelerator the parser rejected can never be bound,
+/// when it's a mouse button or a key the OS knows but the backend can't grab
+fn unsupported_trigger(accelerator: &str) -> Option<String> {
+    let key = accelerator.rsplit('+').next()?.trim();
+    let lower = key.to_ascii_lowercase();
+    if MOUSE_BUTTONS.iter().any(|prefix| lower.starts_with(prefix)) {
+        return Some(format!(
+            "Mouse buttons such as \"{key}\" can't be global shortcuts; have the mouse software send a spare key like F13 instead"
+        ));
+    }
+    Code::from_str(key)
+        .ok()
+        .map(|code| format!("\"{code}\" is a real key, but global shortcuts can't bind it on {}", platform()))
+}
+
+/// Keys the global hotkey backend has no mapping for on this OS
+fn is_unregistrable(key: Code) -> bool {
+    if cfg!(target_os = "macos") {
+        matches!(key, Code::F21 | Code::F22 | Code::F23 | Code::F24 | Code::MediaPlay | Code::MediaPause | Code::MediaStop)
+    } else if cfg!(target_os = "windows") {
+        false
+    } else {
+        // X11 grabs stop at F12 and have no combined play/pause key
+        matches!(
+            key,
+            Code::F13
+                | Code::F14
+                | Code::F15
+                | Code::F16
+                | Code::F17
+                | Code::F18
+                | Code::F19
+                | Code::F20
+                | Code::F21
+                | Code::F22
+                | Code::F23
+                | Code::F24
+                | Code::MediaPlayPause
+        )
+    }
+}
+
+/// Parse an accelerator string, the same way it will be registered
+pub fn parse(accelerator: &str) -> Result<Shortcut, String> {
+    let accelerator = accelerator.trim();
+    if accelerator.is_empty() {
+        return Err("Shortcut is empty".into());
+    }
+
+    let resolved = resolve_alias(accelerator);
+    let shortcut = Shortcut::from_str(&resolved).map_err(|e| unsupported_trigger(&resolved).unwrap_or_else(|| e.to_string()))?;
+
+    // A bare letter or space would swallow normal typing system-wide
+    let base_mods = Modifiers::CONTROL | Modifiers::ALT | Modifiers::SHIFT | Modifiers::SUPER;
+    if (shortcut.mods & base_mods).is_empty() && is_typing_key(shortcut.key) {
+        return Err(format!("\"{}\" needs at least one modifier (Ctrl, Alt, Shift or Super)", shortcut.key));
+    }
+    if is_unregistrable(shortcut.key) {
+        return Err(format!("\"{}\" can't be registered as a global shortcut on {}", shortcut.key, platform()));
+    }
+
+    Ok(shortcut)
+}
+
+pub fn describe(shortcut: &Shortcut) -> ParsedShortcut {
+    let modifiers = modifier_names(shortcut.mods);
+    let key = shortcut.key.to_string();
+    let accelerator = modifiers.iter().cloned().chain([key.clone()]).collect::<Vec<_>>().join("+");
+    ParsedShortcut { accelerator, modifiers, key }
+}
+
+/// Validate and normalize an accelerator for the settings UI
+#[tauri::command]
+pub fn parse_accelerator(s: String) -> Result<ParsedShortcut, String> {
+    parse(&s).map(|shortcut| describe(&shortcut))
+}
+
+/// Key chord reported by the webview, using DOM `KeyboardEvent` fields
+#[derive(Debug, Deserialize)]
+struct CapturedKey {
+    code: String,
+    #[serde(default)]
+    ctrl: bool,
+    #[serde(default)]
+    alt: bool,
+    #[serde(default)]
+    shift: bool,
+    #[serde(default)]
+    meta: bool,
+}
+
+/// Wait for the next key chord and return it as an accelerator. Global
+/// shortcuts are suspended meanwhile so the current binding can be captured too.
+/// The webview does the listening: it gets `shortcut-capture-started` and
+/// answers with `shortcut-captured`.
+#[tauri::command]
+pub async fn capture_next_shortcut(app: AppHandle) -> Result<ParsedShortcut, String> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let listener = app.once("shortcut-captured", move |event| {
+        let _ = tx.send(event.payload().to_string());
+    });
+
+    set_suspended(&app, true);
+    app.global_shortcut().unregister_all().map_err(|e| e.to_string())?;
+    let _ = app.emit_event("shortcut-capture-started", Empty {});
+    let payload = tokio::time::timeout(Duration::from_secs(CAPTURE_TIMEOUT_SECS), rx).await;
+    app.unlisten(listener);
+    register_all(&app, &config::get(&app))?;
+
+    let payload = match payload {
+        Ok(Ok(payload)) => payload,
+        Ok(Err(_)) => return Err("Shortcut capture was interrupted".into()),
+        Err(_) => return Err("No key was pressed".into()),
+    };
+    let key: CapturedKey = serde_json::from_str(&payload).map_err(|e| e.to_string())?;
+    let accelerator = [(key.ctrl, "Ctrl"), (key.alt, "Alt"), (key.shift, "Shift"), (key.meta, "Super")]
+        .into_iter()
+        .filter(|(pressed, _)| *pressed)
+        .map(|(_, name)| name)
+        .chain([key.code.as_str()])
+        .collect::<Vec<_>>()
+        .join("+");
+
+    parse(&accelerator).map(|shortcut| describe(&shortcut))
+}
+
+/// Configured accelerators the app currently holds with the OS
+#[tauri::command]
+pub fn list_registered_shortcuts(app: AppHandle) -> Vec<String> {
+    let global_shortcut = app.global_shortcut();
+    config::get(&app)
+        .shortcuts
+        .values()
+        .filter_map(|accelerator| parse(accelerator).ok())
+        .filter(|shortcut| global_shortcut.is_registered(*shortcut))
+        .map(|shortcut| describe(&shortcut).accelerator)
+        .collect()
+}
+
+/// Release every global shortcut; saving the config registers them again
+#[tauri::command]
+pub fn unregister_all_shortcuts(app: AppHandle) -> Result<(), String> {
+    set_suspended(&app, true);
+    app.global_shortcut().unregister_all().map_err(|e| e.to_string())
+}
+
+/// Check every configured binding parses and every debounce names a known
+/// action before they are saved
+pub fn validate(config: &AppConfig) -> Result<(), String> {
+    for (action, accelerator) in &config.shortcuts {
+        parse(accelerator).map_err(|e| format!("Shortcut for \"{action}\": {e}"))?;
+    }
+    if let Some(action) = config.shortcut_debounce.keys().find(|action| !ACTIONS.contains(&action.as_str())) {
+        return Err(format!("Debounce set for unknown shortcut action \"{action}\""));
+    }
+    Ok(())
+}
+
+pub fn on_toggle(app: &AppHandle) {
+    // A native recording counts even if something reset the tray
+    let recording = pipeline::is_recording(app.clone());
+    let config = config::get(app);
+
+    if recording {
+        emit_fired(app, ACTION_TOGGLE, match config.recording_reentry {
+            RecordingReentry::Stop => "stop",
+            RecordingReentry::Cancel => "cancel",
+            RecordingReentry::Ignore => "ignore",
+        });
+        on_reentry(app, config.recording_reentry);
+        return;
+    }
+
+    // Before the window comes up and takes focus
+    profile::capture(app);
+    if headless::active() {
+        // Nothing to show and no webview to record with, so capture natively
+        emit_fired(app, ACTION_TOGGLE, "record-hidden");
+        if let Err(e) = audio::start_recording(app.clone()) {
+            tracing::error!("Failed to start recording: {e}");
+        }
+    } else if window::is_main_visible(app) {
+        // Window is visible - emit action event to let frontend start recording
+        emit_fired(app, ACTION_TOGGLE, "record");
+        if config.legacy_shortcut_events {
+            let _ = app.emit_event("shortcut-action", Empty {});
+        }
+    } else if config.record_without_showing {
+        // Start capture only; the window appears once a result is delivered
+        emit_fired(app, ACTION_TOGGLE, "record-hidden");
+        let _ = app.emit_event("start-recording", Empty {});
+    } else {
+        // Show window and emit event to start recording
+        emit_fired(app, ACTION_TOGGLE, "summon");
+        window::summon_main(app);
+        if config.legacy_shortcut_events {
+            let _ = app.emit_event("window-shown", Empty {});
+        }
+    }
+}
+
+/// Toggle pressed mid-recording. Native captures are handled here; a recording
+/// made by the webview is told what to do through an event.
+fn on_reentry(app: &AppHandle, reentry: RecordingReentry) {
+    let native = audio::is_recording(app);
+    match reentry {
+        RecordingReentry::Stop if native => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = pipeline::stop_recording_and_process(app).await {
+                    tracing::error!("Failed to process recording: {e}");
+                }
+            });
+        }
+        RecordingReentry::Stop => {
+            let _ = app.emit_event("stop-recording", Empty {});
+        }
+        RecordingReentry::Cancel if native => {
+            audio::discard(app);
+            let _ = app.emit_event("recording-cancelled", Empty {});
+        }
+        RecordingReentry::Cancel => {
+            let _ = app.emit_event("cancel-recording", Empty {});
+        }
+        RecordingReentry::Ignore => {}
+    }
+}
+
+/// Stop everything at once: release the microphone, cancel every request,
+/// tell a webview recorder to drop its audio and hide the window. Runs
+/// entirely here so it works even when the UI is stuck.
+fn panic(app: &AppHandle) {
+    tracing::warn!("Panic shortcut pressed, stopping everything");
+    emit_fired(app, ACTION_PANIC, "panic");
+    audio::force_release_audio(app.clone());
+    cancel::cancel_all(app.clone());
+    let _ = app.emit_event("cancel-recording", Empty {});
+    if let Err(e) = window::hide_main(app) {
+        tracing::error!("Failed to hide window on panic: {e}");
+    }
+    tray::set_state(app, TrayState::Idle);
+    let _ = app.emit_event("panic-triggered", Empty {});
+}
+
+/// True when `action` fired too recently to count again. Each action has its
+/// own window (`shortcut_debounce`, else `shortcut_debounce_ms`), so a quick
+/// cancel right after a toggle still goes through.
+fn debounced(app: &AppHandle, action: &'static str) -> bool {
+    let config = config::get(app);
+    let window = Duration::from_millis(config.shortcut_debounce.get(action).copied().unwrap_or(config.shortcut_debounce_ms));
+    let state = app.state::<RegistrationState>();
+    let mut last_fired = state.last_fired.lock().unwrap();
+    let now = Instant::now();
+    if last_fired.get(action).is_some_and(|last| now.duration_since(*last) < window) {
+        return true;
+    }
+    last_fired.insert(action, now);
+    false
+}
+
+/// Run `action` as its shortcut would. Global shortcuts and `trigger_shortcut`
+/// both land here, so debounce, suppression and the recording-reentry rules
+/// apply the same way to either.
+fn dispatch(app: &AppHandle, action: &'static str, state: ShortcutState) {
+    tracing::debug!("Shortcut {action} {state:?}");
+    if !app.state::<RegistrationState>().startup.lock().unwrap().admit(action, state) {
+        return;
+    }
+    match action {
+        // Never debounced or suppressed: a second press must not be swallowed
+        ACTION_PANIC if state == ShortcutState::Pressed => panic(app),
+        _ if state != ShortcutState::Pressed || debounced(app, action) => {}
+        ACTION_TOGGLE if !suppress(app, action, state) => on_toggle(app),
+        ACTION_CANCEL => {
+            emit_fired(app, ACTION_CANCEL, "cancel");
+            cancel::cancel_all(app.clone());
+        }
+        ACTION_PROCESS_CLIPBOARD if !suppress(app, action, state) => {
+            emit_fired(app, ACTION_PROCESS_CLIPBOARD, "process");
+            tauri::async_runtime::spawn(clipboard::process_clipboard(app.clone()));
+        }
+        ACTION_REPEAT_LAST if !suppress(app, action, state) => {
+            emit_fired(app, ACTION_REPEAT_LAST, "repeat");
+            tauri::async_runtime::spawn(repeat::repeat_from_shortcut(app.clone()));
+        }
+        ACTION_CYCLE_TEMPLATE if !suppress(app, action, state) => {
+            emit_fired(app, ACTION_CYCLE_TEMPLATE, "template");
+            template::cycle(app);
+        }
+        ACTION_OPEN_SETTINGS if !suppress(app, action, state) => {
+            emit_fired(app, ACTION_OPEN_SETTINGS, "settings");
+            window::navigate_to(app, "settings");
+        }
+        ACTION_DICTATE if !suppress(app, action, state) => {
+            emit_fired(app, ACTION_DICTATE, if realtime::is_dictating(app) { "stop-dictation" } else { "dictate" });
+            tauri::async_runtime::spawn(realtime::toggle_dictation(app.clone()));
+        }
+        _ => {}
+    }
+}
+
+/// Called at the end of setup: shortcuts run from now on, starting with the
+/// last one pressed while the app was still starting
+pub fn mark_ready(app: &AppHandle) {
+    let pending = app.state::<RegistrationState>().startup.lock().unwrap().mark_ready();
+    if let Some(action) = pending {
+        dispatch(app, action, ShortcutState::Pressed);
+    }
+}
+
+/// Run an action exactly as if its global shortcut had been pressed, for
+/// automation and accessibility tools
+#[tauri::command]
+pub fn trigger_shortcut(app: AppHandle, action: String) -> Result<(), String> {
+    let action = ACTIONS
+        .iter()
+        .copied()
+        .find(|known| *known == action)
+        .ok_or_else(|| format!("Unknown shortcut action \"{action}\""))?;
+    dispatch(&app, action, ShortcutState::Pressed);
+    Ok(())
+}
+
+/// (Re)register all configured shortcuts, replacing any previous bindings
+pub fn register_all(app: &AppHandle, config: &AppConfig) -> Result<(), String> {
+    let global_shortcut = app.global_shortcut();
+    global_shortcut.unregister_all().map_err(|e| e.to_string())?;
+    set_suspended(app, false);
+
+    // A fresh debounce on every registration keeps a sleep gap from skewing it after resume
+    app.state::<RegistrationState>().last_fired.lock().unwrap().clear();
+
+    for (action, accelerator) in &config.shortcuts {
+        let shortcut = parse(accelerator).map_err(|e| format!("Shortcut for \"{action}\": {e}"))?;
+        let Some(action) = ACTIONS.iter().copied().find(|known| *known == action) else {
+            tracing::warn!("Ignoring shortcut for unknown action \"{action}\"");
+            continue;
+        };
+        let result = global_shortcut.on_shortcut(shortcut, move |app, _shortcut, event| dispatch(app, action, event.state()));
+        result.map_err(|e| format!("Could not register \"{accelerator}\" for \"{action}\": {e}"))?;
+    }
+    for preset in &config.presets {
+        let Some(accelerator) = &preset.shortcut else {
+            continue;
+        };
+        let shortcut = parse(accelerator).map_err(|e| format!("Shortcut for preset \"{}\": {e}", preset.name))?;
+        let name = preset.name.clone();
+        let result = global_shortcut.on_shortcut(shortcut, move |app, _shortcut, event| {
+            if event.state() == ShortcutState::Pressed {
+                preset::on_shortcut(app, &name);
+            }
+        });
+        result.map_err(|e| format!("Could not register \"{accelerator}\" for preset \"{}\": {e}", preset.name))?;
+    }
+
+    Ok(())
+}
+
+/// Whether a binding the app should hold is no longer registered
+fn registration_lost(app: &AppHandle, config: &AppConfig) -> bool {
+    let global_shortcut = app.global_shortcut();
+    config
+        .shortcuts
+        .iter()
+        .filter(|(action, _)| ACTIONS.contains(&action.as_str()))
+        .map(|(_, accelerator)| accelerator)
+        .chain(config.presets.iter().filter_map(|preset| preset.shortcut.as_ref()))
+        .filter_map(|accelerator| parse(accelerator).ok())
+        .any(|shortcut| !global_shortcut.is_registered(shortcut))
+}
+
+/// Some platforms drop global shortcuts across sleep. Poll for a wall-clock
+/// jump or a missing registration and re-register everything when either shows up.
+pub fn watch_resume(app: AppHandle) {
+    thread::spawn(move || {
+        let interval = Duration::from_secs(RESUME_CHECK_SECS);
+        let mut last_check = SystemTime::now();
+        // After a failed attempt (e.g. another app holds the key) only a resume retries
+        let mut failed = false;
+        loop {
+            thread::sleep(interval);
+            let now = SystemTime::now();
+            let gap = now.duration_since(last_check).unwrap_or_default();
+            last_check = now;
+
+            if app.state::<RegistrationState>().suspended.load(Ordering::SeqCst) {
+                continue;
+            }
+            let config = config::get(&app);
+            let resumed = gap > interval + Duration::from_secs(RESUME_GAP_SECS);
+            if !resumed && (failed || !registration_lost(&app, &config)) {
+                continue;
+            }
+
+            match register_all(&app, &config) {
+                Ok(()) => {
+                    failed = false;
+                    let _ = app.emit_event("shortcuts-reregistered", Reregistered { resumed });
+                }
+                Err(e) => {
+                    failed = true;
+                    tracing::error!("Failed to re-register shortcuts, falling back to defaults: {e}");
+                    let _ = register_all(&app, &AppConfig::default());
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_press_before_ready_is_replayed_once() {
+        let mut startup = Startup::default();
+        assert!(!startup.admit(ACTION_TOGGLE, ShortcutState::Pressed));
+        assert_eq!(startup.mark_ready(), Some(ACTION_TOGGLE));
+        assert_eq!(startup.mark_ready(), None);
+        assert!(startup.admit(ACTION_TOGGLE, ShortcutState::Pressed));
+    }
+
+    #[test]
+    fn only_the_latest_press_before_ready_is_kept() {
+        let mut startup = Startup::default();
+        assert!(!startup.admit(ACTION_TOGGLE, ShortcutState::Pressed));
+        assert!(!startup.admit(ACTION_CANCEL, ShortcutState::Pressed));
+        assert_eq!(startup.mark_ready(), Some(ACTION_CANCEL));
+    }
+
+    #[test]
+    fn releases_before_ready_are_dropped() {
+        let mut startup = Startup::default();
+        assert!(!startup.admit(ACTION_TOGGLE, ShortcutState::Released));
+        assert_eq!(startup.mark_ready(), None);
+    }
+
+    #[test]
+    fn presses_after_ready_run_straight_away() {
+        let mut startup = Startup::default();
+        assert_eq!(startup.mark_ready(), None);
+        assert!(startup.admit(ACTION_TOGGLE, ShortcutState::Pressed));
+        assert!(startup.admit(ACTION_TOGGLE, ShortcutState::Released));
+        assert_eq!(startup.mark_ready(), None);
+    }
+}
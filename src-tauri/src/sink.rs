@@ -0,0 +1,235 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::config::{self, AppConfig};
+use crate::error::AppError;
+use crate::events::{EmitEvent, Text};
+use crate::headless;
+use crate::tray;
+use crate::typing;
+
+/// Where a finished result is delivered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultSink {
+    Ui,
+    Clipboard,
+    File,
+    Stdout,
+    // Typed into the focused app, for fields that block or mangle paste
+    Type,
+}
+
+impl ResultSink {
+    pub const ALL: [ResultSink; 5] =
+        [ResultSink::Ui, ResultSink::Clipboard, ResultSink::File, ResultSink::Stdout, ResultSink::Type];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ResultSink::Ui => "ui",
+            ResultSink::Clipboard => "clipboard",
+            ResultSink::File => "file",
+            ResultSink::Stdout => "stdout",
+            ResultSink::Type => "type",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ResultSink::Ui => "Window",
+            ResultSink::Clipboard => "Clipboard",
+            ResultSink::File => "File",
+            ResultSink::Stdout => "Standard Output",
+            ResultSink::Type => "Type into Focused App",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        ResultSink::ALL
+            .into_iter()
+            .find(|sink| sink.name() == name)
+            .ok_or_else(|| format!("Unknown result sink \"{name}\""))
+    }
+}
+
+/// Switch result delivery to `sinks` for this session, or for good with
+/// `persist`, and update the tray's checkmarks
+pub fn set(app: &AppHandle, sinks: BTreeSet<ResultSink>, persist: bool) -> Result<(), String> {
+    if sinks.is_empty() {
+        return Err("At least one result sink is needed".into());
+    }
+    if persist {
+        config::modify(app, |config| config.result_sink = sinks)?;
+    } else {
+        config::modify_in_memory(app, |config| config.result_sink = sinks);
+    }
+    tray::rebuild_menu(app);
+    Ok(())
+}
+
+/// Where results are currently delivered
+#[tauri::command]
+pub fn get_result_sinks(app: AppHandle) -> Vec<ResultSink> {
+    config::get(&app).result_sink.into_iter().collect()
+}
+
+/// Change where results are delivered without going through the settings.
+/// Only this session is affected unless `persist` is set.
+#[tauri::command]
+pub fn set_result_sinks(app: AppHandle, sinks: Vec<String>, persist: Option<bool>) -> Result<(), String> {
+    let sinks = sinks.iter().map(|name| ResultSink::from_name(name)).collect::<Result<BTreeSet<_>, _>>()?;
+    set(&app, sinks, persist.unwrap_or(false))
+}
+
+/// Expand `{timestamp}` in the file sink template (default: `<config>/results/{timestamp}.txt`)
+fn result_file_path(config: &AppConfig) -> Result<PathBuf, String> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default()
+        .to_string();
+
+    if config.result_file_path.is_empty() {
+        Ok(config::config_dir()?.join("results").join(format!("{timestamp}.txt")))
+    } else {
+        Ok(PathBuf::from(config.result_file_path.replace("{timestamp}", &timestamp)))
+    }
+}
+
+fn write_result_file(config: &AppConfig, text: &str) -> Result<(), String> {
+    let path = result_file_path(config)?;
+    // The default results dir exists from startup; a custom path may not
+    if !config.result_file_path.is_empty() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+    }
+    std::fs::write(&path, text).map_err(|e| e.to_string())
+}
+
+/// Payload of `typing-too-long`
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TypingTooLong {
+    chars: usize,
+    max_chars: usize,
+}
+
+/// Type `text` on a background thread so a slow keystroke delay doesn't hold
+/// up the other sinks. Anything past `typing_max_chars` would take too long,
+/// so it goes to the clipboard instead with `typing-too-long`.
+fn type_result(app: &AppHandle, config: &AppConfig, text: &str) -> Result<(), String> {
+    let chars = text.chars().count();
+    if chars > config.typing_max_chars {
+        tracing::warn!("Result is {chars} characters, copying it instead of typing");
+        let _ = app.emit_event("typing-too-long", TypingTooLong { chars, max_chars: config.typing_max_chars });
+        return app.clipboard().write_text(text).map_err(|e| e.to_string());
+    }
+    let text = text.to_string();
+    let delay_ms = config.typing_delay_ms;
+    thread::spawn(move || {
+        if let Err(e) = typing::type_text(&text, delay_ms) {
+            tracing::error!("Failed to type result: {e}");
+        }
+    });
+    Ok(())
+}
+
+/// Sinks a result actually goes to. Headless runs have no UI to show it, so
+/// they fall back to the clipboard when nothing else is configured.
+fn active_sinks(config: &AppConfig) -> Vec<ResultSink> {
+    let headless = headless::active();
+    let sinks: Vec<ResultSink> = config.result_sink.iter().copied().filter(|sink| !headless || *sink != ResultSink::Ui).collect();
+    if headless && sinks.is_empty() {
+        vec![ResultSink::Clipboard]
+    } else {
+        sinks
+    }
+}
+
+/// Hand `text` to every configured sink. Failures are logged, not returned,
+/// so one broken sink doesn't swallow the result for the others.
+pub fn deliver(app: &AppHandle, config: &AppConfig, text: &str) {
+    for sink in &active_sinks(config) {
+        let result = match sink {
+            ResultSink::Ui => app.emit_event("result", Text { text }).map_err(|e| e.to_string()),
+            ResultSink::Clipboard => app.clipboard().write_text(text).map_err(|e| e.to_string()),
+            ResultSink::File => write_result_file(config, text),
+            // Not println!, which panics once the reader of a pipe has gone away
+            ResultSink::Stdout => writeln!(std::io::stdout().lock(), "{text}").map_err(|e| e.to_string()),
+            ResultSink::Type => type_result(app, config, text),
+        };
+        if let Err(e) = result {
+            tracing::error!("Failed to deliver result to {sink:?}: {e}");
+        }
+    }
+}
+
+// Delivered by `test_delivery` unless the caller picks its own sample
+const TEST_SAMPLE: &str = "ama-agent test 123";
+// Time to click into the target field before a test types or pastes
+const TEST_DELAY_MS: u64 = 3000;
+
+/// Payload of `delivery-tested`, also returned by `test_delivery`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeliveryTest {
+    method: String,
+    ok: bool,
+    error: Option<String>,
+}
+
+fn run_test(app: &AppHandle, method: &str, sample: &str) -> Result<(), String> {
+    match method {
+        "clipboard" | "paste" => {
+            app.clipboard().write_text(sample).map_err(|e| e.to_string())?;
+            // Read back: clipboard managers and sandboxing can eat the write
+            if app.clipboard().read_text().map_err(|e| e.to_string())? != sample {
+                return Err("The clipboard didn't keep the text".into());
+            }
+            if method == "paste" {
+                typing::paste()?;
+            }
+            Ok(())
+        }
+        _ => typing::type_text(sample, config::get(app).typing_delay_ms),
+    }
+}
+
+/// Check that results can reach the app the user cares about: put `sample`
+/// (a fixed test string by default) on the clipboard, paste it, or type it
+/// into whatever has focus after `delay_ms` (3s, time to click into the
+/// target field). Keystroke failures usually mean a missing accessibility
+/// permission. Emits `delivery-tested`.
+#[tauri::command]
+pub async fn test_delivery(
+    app: AppHandle,
+    method: String,
+    sample: Option<String>,
+    delay_ms: Option<u64>,
+) -> Result<DeliveryTest, AppError> {
+    if !["clipboard", "paste", "type"].contains(&method.as_str()) {
+        return Err(AppError::Config(format!("Unknown delivery method \"{method}\"; use clipboard, paste or type")));
+    }
+    let sample = sample.filter(|sample| !sample.is_empty()).unwrap_or_else(|| TEST_SAMPLE.to_string());
+    if method != "clipboard" {
+        tokio::time::sleep(Duration::from_millis(delay_ms.unwrap_or(TEST_DELAY_MS))).await;
+    }
+    let test_app = app.clone();
+    let test_method = method.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || run_test(&test_app, &test_method, &sample))
+        .await
+        .map_err(|e| AppError::Io(e.to_string()))?;
+    if let Err(e) = &result {
+        tracing::warn!("Delivery test with {method} failed: {e}");
+    }
+    let report = DeliveryTest { method, ok: result.is_ok(), error: result.err() };
+    let _ = app.emit_event("delivery-tested", report.clone());
+    Ok(report)
+}
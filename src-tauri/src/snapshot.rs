@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::config::{self, AppConfig};
+use crate::error::AppError;
+use crate::shortcut;
+use crate::stats::{self, SessionStatsSnapshot};
+
+// Bumped when the snapshot layout changes incompatibly
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// Everything that makes up the running app's setup, minus secrets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AppSnapshot {
+    version: u32,
+    // Settings as they are in effect, session-only changes (tray sink
+    // switches and the like) included; API keys are blank
+    config: AppConfig,
+    do_not_disturb: bool,
+    // For bug reports only; importing leaves the counters alone
+    #[serde(default)]
+    stats: Option<SessionStatsSnapshot>,
+}
+
+/// The current setup as one JSON document, for bug reports and test fixtures.
+/// API keys are left out.
+#[tauri::command]
+pub fn export_state(app: AppHandle) -> Result<String, AppError> {
+    let mut config = config::get(&app);
+    for key in config::api_keys(&mut config) {
+        key.clear();
+    }
+    let snapshot = AppSnapshot {
+        version: SNAPSHOT_VERSION,
+        config,
+        do_not_disturb: shortcut::get_do_not_disturb(app.clone()),
+        stats: Some(stats::get_session_stats(app)),
+    };
+    serde_json::to_string_pretty(&snapshot).map_err(|e| AppError::Io(e.to_string()))
+}
+
+/// Restore a setup from `export_state`. The settings go through the same
+/// validation as `save_config` and are saved; this install's API keys are kept.
+#[tauri::command]
+pub fn import_state(app: AppHandle, state: String) -> Result<(), AppError> {
+    let snapshot: AppSnapshot = serde_json::from_str(&state).map_err(|e| AppError::Config(format!("Not an app state export: {e}")))?;
+    if snapshot.version != SNAPSHOT_VERSION {
+        return Err(AppError::Config(format!("Unsupported state export version {}", snapshot.version)));
+    }
+
+    let mut current = config::get(&app);
+    let mut config = snapshot.config;
+    // Exported keys are blank; fill each one from the same field here
+    for (key, existing) in config::api_keys(&mut config).into_iter().zip(config::api_keys(&mut current)) {
+        if key.is_empty() {
+            *key = std::mem::take(existing);
+        }
+    }
+    config::save_config(app.clone(), config).map_err(AppError::Config)?;
+    shortcut::set_do_not_disturb(app, snapshot.do_not_disturb);
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::error::AppError;
+
+/// Counters for this session, never written to disk. Atomics rather than a
+/// lock since every stage bumps them.
+pub struct SessionStats {
+    launched: Instant,
+    // Milliseconds after launch of the last reset_session_stats
+    reset_at_ms: AtomicU64,
+    recordings: AtomicU64,
+    audio_ms: AtomicU64,
+    transcriptions: AtomicU64,
+    llm_queries: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl Default for SessionStats {
+    fn default() -> Self {
+        Self {
+            launched: Instant::now(),
+            reset_at_ms: AtomicU64::new(0),
+            recordings: AtomicU64::new(0),
+            audio_ms: AtomicU64::new(0),
+            transcriptions: AtomicU64::new(0),
+            llm_queries: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+        }
+    }
+}
+
+/// Returned by `get_session_stats`; counts are since launch or the last reset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionStatsSnapshot {
+    pub uptime_ms: u64,
+    pub counting_for_ms: u64,
+    pub recordings: u64,
+    pub audio_seconds: f64,
+    pub transcriptions: u64,
+    pub llm_queries: u64,
+    pub errors: u64,
+}
+
+/// Count a finished recording of `duration`
+pub fn recorded(app: &AppHandle, duration: Duration) {
+    let stats = app.state::<SessionStats>();
+    stats.recordings.fetch_add(1, Ordering::Relaxed);
+    stats.audio_ms.fetch_add(duration.as_millis() as u64, Ordering::Relaxed);
+}
+
+/// Count a transcription or LLM request, and the error if it failed
+pub fn stage_finished<T>(app: &AppHandle, stage: &str, result: &Result<T, AppError>) {
+    let stats = app.state::<SessionStats>();
+    match stage {
+        "transcription" => stats.transcriptions.fetch_add(1, Ordering::Relaxed),
+        "llm" => stats.llm_queries.fetch_add(1, Ordering::Relaxed),
+        _ => 0,
+    };
+    if matches!(result, Err(e) if !matches!(e, AppError::Cancelled)) {
+        stats.errors.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Uptime and what the agent has done this session
+#[tauri::command]
+pub fn get_session_stats(app: AppHandle) -> SessionStatsSnapshot {
+    let stats = app.state::<SessionStats>();
+    let uptime_ms = stats.launched.elapsed().as_millis() as u64;
+    SessionStatsSnapshot {
+        uptime_ms,
+        counting_for_ms: uptime_ms.saturating_sub(stats.reset_at_ms.load(Ordering::Relaxed)),
+        recordings: stats.recordings.load(Ordering::Relaxed),
+        audio_seconds: stats.audio_ms.load(Ordering::Relaxed) as f64 / 1000.0,
+        transcriptions: stats.transcriptions.load(Ordering::Relaxed),
+        llm_queries: stats.llm_queries.load(Ordering::Relaxed),
+        errors: stats.errors.load(Ordering::Relaxed),
+    }
+}
+
+/// Zero the counters; uptime keeps counting from launch
+#[tauri::command]
+pub fn reset_session_stats(app: AppHandle) {
+    let stats = app.state::<SessionStats>();
+    stats.reset_at_ms.store(stats.launched.elapsed().as_millis() as u64, Ordering::Relaxed);
+    for counter in [&stats.recordings, &stats.audio_ms, &stats.transcriptions, &stats.llm_queries, &stats.errors] {
+        counter.store(0, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,182 @@
+use serde_json::Value;
+
+use crate::llm::Provider;
+
+/// One piece of a streamed answer
+#[derive(Debug, Clone, PartialEq)]
+pub enum Delta {
+    Text(String),
+    Done,
+}
+
+/// Turns raw response bytes into deltas. Chunks may split events anywhere, so
+/// implementations buffer incomplete input until the next call.
+pub trait StreamParser {
+    fn parse_chunk(&mut self, bytes: &[u8]) -> Vec<Delta>;
+}
+
+/// Server-sent events framing shared by every provider: collects bytes and
+/// yields the `data:` payload of each complete line
+#[derive(Default)]
+struct SseBuffer {
+    pending: Vec<u8>,
+}
+
+impl SseBuffer {
+    fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(bytes);
+        let mut payloads = Vec::new();
+        while let Some(end) = self.pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(data) = line.trim_end().strip_prefix("data:") {
+                payloads.push(data.trim_start().to_string());
+            }
+        }
+        payloads
+    }
+}
+
+/// OpenAI-compatible chat completions (also used by Perplexity)
+#[derive(Default)]
+pub struct OpenAiParser {
+    buffer: SseBuffer,
+}
+
+impl StreamParser for OpenAiParser {
+    fn parse_chunk(&mut self, bytes: &[u8]) -> Vec<Delta> {
+        self.buffer
+            .push(bytes)
+            .into_iter()
+            .filter_map(|data| {
+                if data == "[DONE]" {
+                    return Some(Delta::Done);
+                }
+                let event: Value = serde_json::from_str(&data).ok()?;
+                let text = event["choices"][0]["delta"]["content"].as_str()?;
+                Some(Delta::Text(text.to_string()))
+            })
+            .collect()
+    }
+}
+
+/// Anthropic messages: text arrives in `content_block_delta` events
+#[derive(Default)]
+pub struct ClaudeParser {
+    buffer: SseBuffer,
+}
+
+impl StreamParser for ClaudeParser {
+    fn parse_chunk(&mut self, bytes: &[u8]) -> Vec<Delta> {
+        self.buffer
+            .push(bytes)
+            .into_iter()
+            .filter_map(|data| {
+                let event: Value = serde_json::from_str(&data).ok()?;
+                match event["type"].as_str()? {
+                    "content_block_delta" => Some(Delta::Text(event["delta"]["text"].as_str()?.to_string())),
+                    "message_stop" => Some(Delta::Done),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Gemini `streamGenerateContent?alt=sse`: each event is a partial response
+#[derive(Default)]
+pub struct GeminiParser {
+    buffer: SseBuffer,
+}
+
+impl StreamParser for GeminiParser {
+    fn parse_chunk(&mut self, bytes: &[u8]) -> Vec<Delta> {
+        let mut deltas = Vec::new();
+        for data in self.buffer.push(bytes) {
+            let Ok(event) = serde_json::from_str::<Value>(&data) else {
+                continue;
+            };
+            let candidate = &event["candidates"][0];
+            if let Some(text) = candidate["content"]["parts"][0]["text"].as_str() {
+                deltas.push(Delta::Text(text.to_string()));
+            }
+            if candidate["finishReason"].is_string() {
+                deltas.push(Delta::Done);
+            }
+        }
+        deltas
+    }
+}
+
+pub fn parser_for(provider: Provider) -> Box<dyn StreamParser + Send> {
+    match provider {
+        Provider::OpenAi | Provider::Perplexity => Box::<OpenAiParser>::default(),
+        Provider::Claude => Box::<ClaudeParser>::default(),
+        Provider::Gemini => Box::<GeminiParser>::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPENAI: &[u8] = include_bytes!("../tests/fixtures/stream/openai.sse");
+    const CLAUDE: &[u8] = include_bytes!("../tests/fixtures/stream/claude.sse");
+    const GEMINI: &[u8] = include_bytes!("../tests/fixtures/stream/gemini.sse");
+
+    fn fixtures() -> [(Provider, &'static [u8]); 4] {
+        [(Provider::OpenAi, OPENAI), (Provider::Perplexity, OPENAI), (Provider::Claude, CLAUDE), (Provider::Gemini, GEMINI)]
+    }
+
+    fn parse<'a>(provider: Provider, chunks: impl IntoIterator<Item = &'a [u8]>) -> Vec<Delta> {
+        let mut parser = parser_for(provider);
+        chunks.into_iter().flat_map(|chunk| parser.parse_chunk(chunk)).collect()
+    }
+
+    /// The text of `deltas`, checking the stream ends with exactly one `Done`
+    fn answer(deltas: &[Delta]) -> String {
+        let (last, text) = deltas.split_last().expect("no deltas");
+        assert_eq!(*last, Delta::Done);
+        text.iter()
+            .map(|delta| match delta {
+                Delta::Text(text) => text.as_str(),
+                Delta::Done => panic!("Done before the end of the stream"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_whole_streams() {
+        for (provider, stream) in fixtures() {
+            assert_eq!(answer(&parse(provider, [stream])), "Café 👋 ready", "{}", provider.name());
+        }
+    }
+
+    #[test]
+    fn parses_streams_split_at_any_byte() {
+        for (provider, stream) in fixtures() {
+            let whole = parse(provider, [stream]);
+            // Every split point, mid-line and mid-character included
+            for at in 1..stream.len() {
+                let (head, tail) = stream.split_at(at);
+                assert_eq!(parse(provider, [head, tail]), whole, "{} split at {at}", provider.name());
+            }
+            assert_eq!(parse(provider, stream.chunks(1)), whole, "{} one byte at a time", provider.name());
+        }
+    }
+
+    #[test]
+    fn splits_inside_a_multibyte_character() {
+        let wave = OPENAI.windows(4).position(|bytes| bytes == "👋".as_bytes()).unwrap();
+        let (head, tail) = OPENAI.split_at(wave + 2);
+        assert!(std::str::from_utf8(head).is_err());
+        assert_eq!(answer(&parse(Provider::OpenAi, [head, tail])), "Café 👋 ready");
+    }
+
+    #[test]
+    fn holds_back_an_unfinished_line() {
+        let mut parser = OpenAiParser::default();
+        assert!(parser.parse_chunk(b"data: [DO").is_empty());
+        assert_eq!(parser.parse_chunk(b"NE]\n"), vec![Delta::Done]);
+    }
+}
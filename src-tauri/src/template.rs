@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::config::{self, AppConfig};
+use crate::events::EmitEvent;
+use crate::notify;
+
+// Placeholder replaced with the transcript or clipboard text
+const TEXT_PLACEHOLDER: &str = "{text}";
+
+/// A named prompt wrapped around the user's text before it goes to the LLM
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptTemplate {
+    pub name: String,
+    pub prompt: String,
+    // Used instead of the active template for transcripts in this language
+    // (ISO-639-1 code such as "es", or the name Whisper reports)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when_language: Option<String>,
+}
+
+/// The template selected by `active_template`, if it still exists
+pub fn active(config: &AppConfig) -> Option<&PromptTemplate> {
+    let name = config.active_template.as_deref()?;
+    config.templates.iter().find(|template| template.name == name)
+}
+
+/// The first template whose `when_language` matches the transcript's
+/// language, otherwise the active one. The language is only known when it is
+/// set in `transcription_language` or detected through `whisper_verbose_json`;
+/// without it this is the same as `active`.
+pub fn for_language<'a>(config: &'a AppConfig, language: Option<&str>) -> Option<&'a PromptTemplate> {
+    language
+        .and_then(|language| {
+            config.templates.iter().find(|template| {
+                template.when_language.as_deref().is_some_and(|when| when.trim().eq_ignore_ascii_case(language))
+            })
+        })
+        .or_else(|| active(config))
+}
+
+/// Payload of `template-changed`
+#[derive(Debug, Clone, Serialize)]
+struct TemplateChanged {
+    name: String,
+}
+
+/// Template after the active one, wrapping around; the first when none is active
+fn next_name(config: &AppConfig) -> Option<String> {
+    let current = config
+        .active_template
+        .as_deref()
+        .and_then(|name| config.templates.iter().position(|template| template.name == name));
+    let next = current.map_or(0, |index| (index + 1) % config.templates.len());
+    config.templates.get(next).map(|template| template.name.clone())
+}
+
+/// Make the template called `name` active and save it, emitting `template-changed`
+pub fn set_active(app: &AppHandle, name: &str) -> Result<(), String> {
+    if !config::get(app).templates.iter().any(|template| template.name == name) {
+        return Err(format!("No template named \"{name}\""));
+    }
+    config::modify(app, |config| config.active_template = Some(name.to_string()))?;
+    if config::get(app).notify_on_template_change {
+        notify::hint(app, &format!("Template: {name}"));
+    }
+    let _ = app.emit_event("template-changed", TemplateChanged { name: name.to_string() });
+    Ok(())
+}
+
+/// Make the next template active and save it, so the next query uses it.
+/// Does nothing when no templates are defined.
+pub fn cycle(app: &AppHandle) {
+    let Some(name) = next_name(&config::get(app)) else {
+        return;
+    };
+    if let Err(e) = set_active(app, &name) {
+        tracing::error!("Failed to switch template: {e}");
+    }
+}
+
+/// Substitute `text` into the template, appending it when there's no placeholder
+pub fn apply_template(template: Option<&PromptTemplate>, text: &str) -> String {
+    match template {
+        Some(template) if template.prompt.contains(TEXT_PLACEHOLDER) => template.prompt.replace(TEXT_PLACEHOLDER, text),
+        Some(template) => format!("{}\n\n{}", template.prompt, text),
+        None => text.to_string(),
+    }
+}
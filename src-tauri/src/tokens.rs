@@ -0,0 +1,18 @@
+use tiktoken_rs::tokenizer::get_tokenizer;
+
+// Rough characters-per-token ratio for tokenizers we don't ship
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Count tokens for `model`. OpenAI-family models use the real BPE (loaded once
+/// and cached by tiktoken-rs); anything else falls back to a length heuristic.
+pub fn estimate(text: &str, model: &str) -> usize {
+    match get_tokenizer(model).and_then(|tokenizer| tiktoken_rs::bpe_for_tokenizer(tokenizer).ok()) {
+        Some(bpe) => bpe.encode_ordinary(text).len(),
+        None => text.chars().count().div_ceil(CHARS_PER_TOKEN),
+    }
+}
+
+#[tauri::command]
+pub fn estimate_tokens(text: String, model: String) -> usize {
+    estimate(&text, &model)
+}
@@ -0,0 +1,327 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{
+    image::Image,
+    menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu},
+    tray::{TrayIconBuilder, TrayIconEvent},
+    AppHandle, Manager, Theme, Wry,
+};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::audio;
+use crate::config;
+use crate::events::{EmitEvent, Empty};
+use crate::history;
+use crate::llm;
+use crate::sink::{self, ResultSink};
+use crate::{autohide, shortcut, window};
+
+pub const TRAY_ID: &str = "main";
+
+// Number of history entries listed under "Recent"
+const RECENT_ITEMS: usize = 5;
+// Longest transcript preview shown in a menu label
+const RECENT_LABEL_CHARS: usize = 40;
+
+/// The taskbar the tray icon has to stand out against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrayIconTheme {
+    // Follow the OS theme, switching when it changes
+    #[default]
+    Auto,
+    // Light taskbar: dark icon
+    Light,
+    // Dark taskbar: light icon
+    Dark,
+}
+
+fn icon_theme(app: &AppHandle) -> Theme {
+    match config::get(app).tray_icon_theme {
+        TrayIconTheme::Light => Theme::Light,
+        TrayIconTheme::Dark => Theme::Dark,
+        TrayIconTheme::Auto => app.get_webview_window("main").and_then(|window| window.theme().ok()).unwrap_or(Theme::Light),
+    }
+}
+
+/// The app icon as a single-colour glyph for the taskbar's theme: bright
+/// parts of the artwork become the glyph, the dark backdrop turns transparent
+pub fn themed_icon(app: &AppHandle) -> Option<Image<'static>> {
+    let icon = app.default_window_icon()?;
+    let shade = if matches!(icon_theme(app), Theme::Dark) { 255 } else { 0 };
+    let mut rgba = icon.rgba().to_vec();
+    for pixel in rgba.chunks_exact_mut(4) {
+        let luma = (pixel[0] as u32 * 299 + pixel[1] as u32 * 587 + pixel[2] as u32 * 114) / 1000;
+        pixel[3] = (pixel[3] as u32 * luma / 255) as u8;
+        pixel[..3].fill(shade);
+    }
+    Some(Image::new_owned(rgba, icon.width(), icon.height()))
+}
+
+/// Redraw the tray icon for the current theme setting or OS theme
+pub fn refresh_icon(app: &AppHandle) {
+    if let (Some(tray), Some(icon)) = (app.tray_by_id(TRAY_ID), themed_icon(app)) {
+        let _ = tray.set_icon(Some(icon));
+    }
+}
+
+/// What the app is currently doing, mirrored in the tray tooltip
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrayState {
+    #[default]
+    Idle,
+    Recording,
+    Processing,
+}
+
+impl TrayState {
+    pub fn tooltip(self) -> &'static str {
+        match self {
+            TrayState::Idle => "AMA Agent",
+            TrayState::Recording => "AMA Agent - Recording",
+            TrayState::Processing => "AMA Agent - Processing",
+        }
+    }
+}
+
+/// The state's tooltip, naming the model that answers next and whether the
+/// passive buffer has the microphone open
+fn tooltip(app: &AppHandle, state: TrayState) -> String {
+    let mut tooltip = match llm::active_model(&config::get(app)) {
+        Some((_, model)) => format!("{} ({model})", state.tooltip()),
+        None => state.tooltip().to_string(),
+    };
+    // The microphone is open the whole time, so this is always spelled out
+    let passive = audio::passive_status(app);
+    if passive.enabled {
+        tooltip.push_str(&format!("\nMicrophone on: keeping the last {}s", passive.seconds));
+    }
+    tooltip
+}
+
+/// Put the tooltip back in line with the current state and model
+pub fn refresh_tooltip(app: &AppHandle) {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(tooltip(app, current_state(app))));
+    }
+}
+
+pub fn current_state(app: &AppHandle) -> TrayState {
+    *app.state::<Mutex<TrayState>>().lock().unwrap()
+}
+
+fn recent_label(transcript: &str) -> String {
+    let preview: String = transcript.chars().take(RECENT_LABEL_CHARS).collect();
+    if preview.len() < transcript.len() {
+        format!("{preview}…")
+    } else {
+        preview
+    }
+}
+
+/// Build the tray menu from current config, history and recording state
+fn build_menu(app: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let config = config::get(app);
+
+    let record_label = if current_state(app) == TrayState::Recording { "Stop Recording" } else { "Start Recording" };
+    let show_item = MenuItem::with_id(app, "show", "Show", true, None::<&str>)?;
+    let record_item = MenuItem::with_id(app, "record", record_label, true, None::<&str>)?;
+    let settings_item = MenuItem::with_id(app, "settings", "Settings…", true, None::<&str>)?;
+
+    let recent = history::recent(Some(RECENT_ITEMS)).unwrap_or_default();
+    let recent_menu = Submenu::with_id(app, "recent", "Recent", !recent.is_empty())?;
+    for entry in &recent {
+        let item = MenuItem::with_id(app, format!("recent:{}", entry.seq), recent_label(&entry.transcript), true, None::<&str>)?;
+        recent_menu.append(&item)?;
+    }
+
+    let sink_menu = Submenu::with_id(app, "sinks", "Send Results To", true)?;
+    for sink in ResultSink::ALL {
+        let checked = config.result_sink.contains(&sink);
+        let item = CheckMenuItem::with_id(app, format!("sink:{}", sink.name()), sink.label(), true, checked, None::<&str>)?;
+        sink_menu.append(&item)?;
+    }
+
+    let active_model = llm::active_model(&config);
+    let model_menu = Submenu::with_id(app, "models", "Model", active_model.is_some())?;
+    if let Some((provider, current)) = &active_model {
+        let mut models: Vec<&str> = provider.suggested_models().to_vec();
+        if !models.contains(&current.as_str()) {
+            models.insert(0, current);
+        }
+        for model in models {
+            let item = CheckMenuItem::with_id(app, format!("model:{model}"), model, true, model == current, None::<&str>)?;
+            model_menu.append(&item)?;
+        }
+    }
+
+    let passive = audio::passive_status(app);
+    let passive_label =
+        if passive.enabled { format!("Keep Last {}s of Audio", passive.seconds) } else { "Keep Last Seconds of Audio".into() };
+    let passive_item = CheckMenuItem::with_id(app, "passive", passive_label, true, passive.enabled, None::<&str>)?;
+    let always_on_top_item = CheckMenuItem::with_id(app, "always-on-top", "Always on Top", true, config.always_on_top, None::<&str>)?;
+    let separator = PredefinedMenuItem::separator(app)?;
+    let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+
+    Menu::with_items(
+        app,
+        &[
+            &show_item,
+            &record_item,
+            &recent_menu,
+            &sink_menu,
+            &model_menu,
+            &passive_item,
+            &always_on_top_item,
+            &settings_item,
+            &separator,
+            &quit_item,
+        ],
+    )
+}
+
+/// Replace the tray menu so it reflects the latest settings and history
+pub fn rebuild_menu(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    match build_menu(app) {
+        Ok(menu) => {
+            let _ = tray.set_menu(Some(menu));
+        }
+        Err(e) => tracing::error!("Failed to rebuild tray menu: {e}"),
+    }
+}
+
+fn copy_recent(app: &AppHandle, seq: &str) -> Result<(), String> {
+    let seq: u64 = seq.parse().map_err(|_| format!("Invalid history id \"{seq}\""))?;
+    let entry = history::recent(None)?
+        .into_iter()
+        .find(|entry| entry.seq == seq)
+        .ok_or("History entry no longer exists")?;
+    app.clipboard().write_text(entry.result).map_err(|e| e.to_string())
+}
+
+fn set_always_on_top(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    config::modify(app, |config| config.always_on_top = enabled)?;
+    if let Some(window) = app.get_webview_window("main") {
+        window.set_always_on_top(enabled).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Turn one sink on or off for this session; the last one can't be turned off
+fn toggle_sink(app: &AppHandle, name: &str) -> Result<(), String> {
+    let sink = ResultSink::from_name(name)?;
+    let mut sinks = config::get(app).result_sink;
+    if !sinks.remove(&sink) {
+        sinks.insert(sink);
+    }
+    if sinks.is_empty() {
+        // Put the checkmark back
+        rebuild_menu(app);
+        return Ok(());
+    }
+    sink::set(app, sinks, false)
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id.as_ref() {
+        "show" => window::show_main(app),
+        "record" => shortcut::on_toggle(app),
+        "settings" => window::navigate_to(app, "settings"),
+        "always-on-top" => {
+            let enabled = !config::get(app).always_on_top;
+            if let Err(e) = set_always_on_top(app, enabled) {
+                tracing::error!("Failed to toggle always on top: {e}");
+            }
+            rebuild_menu(app);
+        }
+        "passive" => {
+            let enabled = !audio::passive_status(app).enabled;
+            if let Err(e) = audio::set_passive_buffer(app.clone(), enabled, None) {
+                tracing::error!("Failed to switch the passive buffer: {e}");
+                rebuild_menu(app);
+            }
+        }
+        "quit" => {
+            app.exit(0);
+        }
+        id => {
+            if let Some(seq) = id.strip_prefix("recent:") {
+                if let Err(e) = copy_recent(app, seq) {
+                    tracing::error!("Failed to copy recent result: {e}");
+                }
+            } else if let Some(name) = id.strip_prefix("sink:") {
+                if let Err(e) = toggle_sink(app, name) {
+                    tracing::error!("Failed to switch result sink: {e}");
+                }
+            } else if let Some(model) = id.strip_prefix("model:") {
+                if let Err(e) = llm::set_model(app, model) {
+                    tracing::error!("Failed to switch model: {e}");
+                }
+            }
+        }
+    }
+}
+
+/// Build the tray icon with app icon
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    let menu = build_menu(app)?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID);
+    if let Some(icon) = themed_icon(app) {
+        builder = builder.icon(icon);
+    }
+    builder
+        .tooltip(tooltip(app, current_state(app)))
+        .menu(&menu)
+        .on_menu_event(on_menu_event)
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::DoubleClick { .. } = event {
+                window::show_main(tray.app_handle());
+                let _ = tray.app_handle().emit_event("window-shown", Empty {});
+            }
+        })
+        .build(app)?;
+
+    Ok(())
+}
+
+/// Add or remove the tray icon to match the `show_tray` setting
+pub fn set_visible(app: &AppHandle, visible: bool) -> tauri::Result<()> {
+    match (visible, app.tray_by_id(TRAY_ID).is_some()) {
+        (true, false) => create(app),
+        (false, true) => {
+            app.remove_tray_by_id(TRAY_ID);
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+pub fn set_state(app: &AppHandle, state: TrayState) {
+    if state != TrayState::Idle {
+        // A new recording or request supersedes the result on screen
+        autohide::clear(app);
+    }
+    let previous = std::mem::replace(&mut *app.state::<Mutex<TrayState>>().lock().unwrap(), state);
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(tooltip(app, state)));
+    }
+    if previous != state {
+        rebuild_menu(app);
+    }
+}
+
+/// Frontend reports recording/processing transitions so the tray stays in sync
+#[tauri::command]
+pub fn set_tray_state(app: AppHandle, state: TrayState) {
+    set_state(&app, state);
+}
+
+#[tauri::command]
+pub fn rebuild_tray_menu(app: AppHandle) {
+    rebuild_menu(&app);
+}
@@ -0,0 +1,99 @@
+use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+fn enigo() -> Result<Enigo, String> {
+    Enigo::new(&Settings::default()).map_err(|e| e.to_string())
+}
+
+fn type_with(enigo: &mut Enigo, text: &str, delay: Duration) -> Result<(), String> {
+    let mut buffer = [0u8; 4];
+    for c in text.chars() {
+        let result = match c {
+            '\r' => continue,
+            '\n' => enigo.key(Key::Return, Direction::Click),
+            '\t' => enigo.key(Key::Tab, Direction::Click),
+            c => enigo.text(c.encode_utf8(&mut buffer)),
+        };
+        result.map_err(|e| e.to_string())?;
+        if !delay.is_zero() {
+            thread::sleep(delay);
+        }
+    }
+    Ok(())
+}
+
+/// Type `text` into whatever has keyboard focus, one character at a time with
+/// `delay_ms` between keystrokes for apps that drop fast input. Newlines and
+/// tabs are pressed as keys; everything else, Unicode included, is entered
+/// as text so the keyboard layout doesn't matter.
+pub fn type_text(text: &str, delay_ms: u64) -> Result<(), String> {
+    type_with(&mut enigo()?, text, Duration::from_millis(delay_ms))
+}
+
+/// Press the platform's paste shortcut (Cmd+V on macOS, Ctrl+V elsewhere)
+pub fn paste() -> Result<(), String> {
+    let modifier = if cfg!(target_os = "macos") { Key::Meta } else { Key::Control };
+    let mut enigo = enigo()?;
+    enigo.key(modifier, Direction::Press).map_err(|e| e.to_string())?;
+    let result = enigo.key(Key::Unicode('v'), Direction::Click).map_err(|e| e.to_string());
+    enigo.key(modifier, Direction::Release).map_err(|e| e.to_string())?;
+    result
+}
+
+enum Keystrokes {
+    Text(String),
+    Backspace(usize),
+}
+
+/// Types on a thread of its own so the caller never waits on the keyboard,
+/// applying edits in the order they were sent. Dropping it ends the thread.
+pub struct Typist {
+    tx: mpsc::Sender<Keystrokes>,
+}
+
+impl Typist {
+    pub fn spawn(delay_ms: u64) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let delay = Duration::from_millis(delay_ms);
+        thread::spawn(move || {
+            let mut enigo = match enigo() {
+                Ok(enigo) => enigo,
+                Err(e) => {
+                    tracing::error!("Failed to start typing: {e}");
+                    return;
+                }
+            };
+            for keystrokes in rx {
+                let result = match keystrokes {
+                    Keystrokes::Text(text) => type_with(&mut enigo, &text, delay),
+                    Keystrokes::Backspace(count) => {
+                        (0..count).try_for_each(|_| enigo.key(Key::Backspace, Direction::Click).map_err(|e| e.to_string()))
+                    }
+                };
+                if let Err(e) = result {
+                    tracing::error!("Failed to type: {e}");
+                }
+            }
+        });
+        Self { tx }
+    }
+
+    pub fn type_text(&self, text: &str) {
+        if !text.is_empty() {
+            let _ = self.tx.send(Keystrokes::Text(text.to_string()));
+        }
+    }
+
+    /// Turn `typed` into `target` with as few keystrokes as possible: erase
+    /// back to where they differ, then type the rest of `target`
+    pub fn revise(&self, typed: &str, target: &str) {
+        let common = typed.chars().zip(target.chars()).take_while(|(a, b)| a == b).count();
+        let erase = typed.chars().count() - common;
+        if erase > 0 {
+            let _ = self.tx.send(Keystrokes::Backspace(erase));
+        }
+        self.type_text(&target.chars().skip(common).collect::<String>());
+    }
+}
@@ -0,0 +1,283 @@
+use futures_util::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::audio;
+use crate::error::AppError;
+use crate::events::EmitEvent;
+
+// Size of the pieces a progress-reporting body hands out, and the least time
+// between two `upload-progress` events
+const PROGRESS_CHUNK_BYTES: usize = 64 * 1024;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Container used for recordings sent to the transcription endpoint
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UploadFormat {
+    #[default]
+    Wav,
+    Opus,
+}
+
+// Opus only accepts a few input rates; 16 kHz is what Whisper resamples to anyway
+#[cfg_attr(not(feature = "opus"), allow(dead_code))]
+const OPUS_SAMPLE_RATE: u32 = 16_000;
+
+/// Describe a WAV decode failure in terms of what is wrong with the file
+fn wav_error(file_name: &str, e: hound::Error) -> AppError {
+    AppError::Audio(match e {
+        hound::Error::FormatError(reason) => format!("{file_name} is not a valid WAV file ({reason})"),
+        hound::Error::Unsupported | hound::Error::InvalidSampleFormat | hound::Error::TooWide => {
+            format!("{file_name} uses a WAV encoding that isn't supported")
+        }
+        // Decoding works on bytes in memory, so an I/O error can only mean they
+        // ran out; hound reports that as ErrorKind::Other, not UnexpectedEof
+        hound::Error::UnfinishedSample | hound::Error::IoError(_) => format!("{file_name} is truncated"),
+    })
+}
+
+/// Decode a WAV file to mono f32 samples
+fn decode_wav(wav: &[u8], file_name: &str) -> Result<(Vec<f32>, u32), AppError> {
+    let reader = hound::WavReader::new(Cursor::new(wav)).map_err(|e| wav_error(file_name, e))?;
+    let spec = reader.spec();
+    let interleaved: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.into_samples::<f32>().collect::<Result<_, _>>(),
+        hound::SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader.into_samples::<i32>().map(|s| s.map(|s| s as f32 / scale)).collect::<Result<_, _>>()
+        }
+    }
+    .map_err(|e| wav_error(file_name, e))?;
+
+    let channels = spec.channels.max(1) as usize;
+    let mono = interleaved.chunks(channels).map(|frame| frame.iter().sum::<f32>() / frame.len() as f32).collect();
+    Ok((mono, spec.sample_rate))
+}
+
+/// Linear resampling, good enough for speech going to a transcriber
+#[cfg_attr(not(feature = "opus"), allow(dead_code))]
+fn resample(samples: &[f32], from: u32, to: u32) -> Vec<f32> {
+    if from == to || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let ratio = from as f64 / to as f64;
+    let len = (samples.len() as f64 / ratio) as usize;
+    (0..len)
+        .map(|i| {
+            let pos = i as f64 * ratio;
+            let index = pos as usize;
+            let frac = (pos - index as f64) as f32;
+            let a = samples[index];
+            let b = samples.get(index + 1).copied().unwrap_or(a);
+            a + (b - a) * frac
+        })
+        .collect()
+}
+
+#[cfg(feature = "opus")]
+fn encode_opus(wav: &[u8]) -> Result<Vec<u8>, AppError> {
+    use ogg::writing::{PacketWriteEndInfo, PacketWriter};
+    use opus::{Application, Bitrate, Channels, Encoder};
+
+    // 20 ms frames, granule positions are always counted at 48 kHz
+    const FRAME_SAMPLES: usize = OPUS_SAMPLE_RATE as usize / 50;
+    const GRANULE_SCALE: u64 = 48_000 / OPUS_SAMPLE_RATE as u64;
+    const SERIAL: u32 = 1;
+
+    let audio_err = |e: opus::Error| AppError::Audio(e.to_string());
+    let io_err = |e: std::io::Error| AppError::Audio(e.to_string());
+
+    let (samples, sample_rate) = decode_wav(wav, "recording")?;
+    let samples = resample(&samples, sample_rate, OPUS_SAMPLE_RATE);
+
+    let mut encoder = Encoder::new(OPUS_SAMPLE_RATE, Channels::Mono, Application::Voip).map_err(audio_err)?;
+    encoder.set_bitrate(Bitrate::Bits(24_000)).map_err(audio_err)?;
+    let pre_skip = encoder.get_lookahead().map_err(audio_err)? as u64 * GRANULE_SCALE;
+
+    let mut head = b"OpusHead".to_vec();
+    head.push(1); // version
+    head.push(1); // channels
+    head.extend_from_slice(&(pre_skip as u16).to_le_bytes());
+    head.extend_from_slice(&sample_rate.to_le_bytes());
+    head.extend_from_slice(&0u16.to_le_bytes()); // output gain
+    head.push(0); // mapping family
+
+    let vendor = b"ama-agent";
+    let mut tags = b"OpusTags".to_vec();
+    tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    tags.extend_from_slice(vendor);
+    tags.extend_from_slice(&0u32.to_le_bytes()); // no user comments
+
+    let mut writer = PacketWriter::new(Vec::new());
+    writer.write_packet(head, SERIAL, PacketWriteEndInfo::EndPage, 0).map_err(io_err)?;
+    writer.write_packet(tags, SERIAL, PacketWriteEndInfo::EndPage, 0).map_err(io_err)?;
+
+    let frames: Vec<&[f32]> = samples.chunks(FRAME_SAMPLES).collect();
+    let mut granule = pre_skip;
+    for (i, chunk) in frames.iter().enumerate() {
+        let mut frame = chunk.to_vec();
+        frame.resize(FRAME_SAMPLES, 0.0);
+        let packet = encoder.encode_vec_float(&frame, 4000).map_err(audio_err)?;
+
+        granule += chunk.len() as u64 * GRANULE_SCALE;
+        let end = if i + 1 == frames.len() { PacketWriteEndInfo::EndStream } else { PacketWriteEndInfo::NormalPacket };
+        writer.write_packet(packet, SERIAL, end, granule).map_err(io_err)?;
+    }
+
+    Ok(writer.into_inner())
+}
+
+#[cfg(not(feature = "opus"))]
+fn encode_opus(_wav: &[u8]) -> Result<Vec<u8>, AppError> {
+    Err(AppError::Audio("built without Opus support".into()))
+}
+
+/// Catch empty and undecodable files before they're uploaded. Only WAV is
+/// decoded here; other containers are left for the endpoint to judge.
+pub fn validate(audio: &[u8], file_name: &str) -> Result<(), AppError> {
+    if audio.is_empty() {
+        return Err(AppError::Audio(format!("{file_name} is empty")));
+    }
+    match file_name.rsplit_once('.') {
+        Some((_, extension)) if extension.eq_ignore_ascii_case("wav") => decode_wav(audio, file_name).map(|_| ()),
+        _ => Ok(()),
+    }
+}
+
+/// Re-encode a WAV recording in the configured upload format. Anything that
+/// isn't WAV, or fails to encode, is passed through unchanged.
+pub fn prepare(format: UploadFormat, audio: Vec<u8>, file_name: &str) -> (Vec<u8>, String) {
+    let stem = match file_name.rsplit_once('.') {
+        Some((stem, extension)) if extension.eq_ignore_ascii_case("wav") => stem,
+        _ => return (audio, file_name.to_string()),
+    };
+    if format == UploadFormat::Wav {
+        return (audio, file_name.to_string());
+    }
+
+    match encode_opus(&audio) {
+        Ok(opus) => {
+            tracing::info!("Upload size: {} KB WAV -> {} KB Opus", audio.len() / 1024, opus.len() / 1024);
+            (opus, format!("{stem}.ogg"))
+        }
+        Err(e) => {
+            tracing::warn!("Opus encoding failed, uploading WAV: {e}");
+            (audio, file_name.to_string())
+        }
+    }
+}
+
+/// 16-bit little-endian PCM, the usual realtime wire format
+pub fn encode_pcm16(samples: &[f32]) -> Vec<u8> {
+    samples
+        .iter()
+        .flat_map(|s| ((s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes())
+        .collect()
+}
+
+/// Header for mono 16-bit WAV of unknown length. The size fields are left at
+/// their maximum, which decoders read as "until the end of the stream".
+fn streaming_wav_header(sample_rate: u32) -> Vec<u8> {
+    let mut header = Vec::with_capacity(44);
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&u32::MAX.to_le_bytes());
+    header.extend_from_slice(b"WAVEfmt ");
+    header.extend_from_slice(&16u32.to_le_bytes());
+    header.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    header.extend_from_slice(&1u16.to_le_bytes()); // channels
+    header.extend_from_slice(&sample_rate.to_le_bytes());
+    header.extend_from_slice(&(sample_rate * 2).to_le_bytes()); // byte rate
+    header.extend_from_slice(&2u16.to_le_bytes()); // block align
+    header.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&u32::MAX.to_le_bytes());
+    header
+}
+
+/// Payload of `upload-progress`
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UploadProgress {
+    sent_bytes: u64,
+    total_bytes: u64,
+}
+
+/// `audio` as a request body that emits `upload-progress` as the connection
+/// takes it. Counts are bytes handed to the connection, so they run slightly
+/// ahead of what has reached the server. The last piece is always reported.
+pub fn progress_body(app: AppHandle, audio: Vec<u8>) -> reqwest::Body {
+    let total_bytes = audio.len() as u64;
+    let mut sent_bytes = 0;
+    let mut last_event: Option<Instant> = None;
+    let chunks: Vec<Vec<u8>> = audio.chunks(PROGRESS_CHUNK_BYTES).map(<[u8]>::to_vec).collect();
+    let body = stream::iter(chunks).map(move |chunk| {
+        sent_bytes += chunk.len() as u64;
+        if sent_bytes == total_bytes || last_event.is_none_or(|at| at.elapsed() >= PROGRESS_INTERVAL) {
+            last_event = Some(Instant::now());
+            let _ = app.emit_event("upload-progress", UploadProgress { sent_bytes, total_bytes });
+        }
+        Ok::<_, std::io::Error>(chunk)
+    });
+    reqwest::Body::wrap_stream(body)
+}
+
+/// Request body that encodes captured chunks as WAV while the recording is
+/// still running. It ends when the capture drops its tap.
+pub fn wav_body(chunks: UnboundedReceiver<Vec<f32>>, sample_rate: u32, gain_db: f32) -> reqwest::Body {
+    let header = stream::once(async move { Ok::<_, std::io::Error>(streaming_wav_header(sample_rate)) });
+    let pcm = stream::unfold(chunks, move |mut chunks| async move {
+        let mut chunk = chunks.recv().await?;
+        audio::apply_gain(&mut chunk, gain_db);
+        Some((Ok(encode_pcm16(&chunk)), chunks))
+    });
+    reqwest::Body::wrap_stream(header.chain(pcm))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::WavBitDepth;
+
+    fn wav() -> Vec<u8> {
+        let samples: Vec<f32> = (0..1600).map(|i| (i as f32 * 0.05).sin() * 0.5).collect();
+        audio::encode_wav(&samples, 16_000, WavBitDepth::Pcm16).unwrap()
+    }
+
+    fn message(result: Result<(), AppError>) -> String {
+        match result {
+            Err(AppError::Audio(message)) => message,
+            other => panic!("expected an audio error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn accepts_a_complete_wav() {
+        assert!(validate(&wav(), "clip.wav").is_ok());
+        assert!(validate(&wav(), "CLIP.WAV").is_ok());
+    }
+
+    #[test]
+    fn rejects_a_truncated_wav() {
+        let wav = wav();
+        // Mid-sample, well inside the data chunk
+        assert_eq!(message(validate(&wav[..wav.len() / 2 + 1], "clip.wav")), "clip.wav is truncated");
+        // Inside the header
+        assert!(validate(&wav[..20], "clip.wav").is_err());
+    }
+
+    #[test]
+    fn rejects_text_renamed_to_wav() {
+        let text = b"Meeting notes\nThese are not audio samples.\n";
+        assert_eq!(message(validate(text, "notes.wav")), "notes.wav is not a valid WAV file (no RIFF tag found)");
+    }
+
+    #[test]
+    fn rejects_empty_files_and_leaves_other_containers_to_the_endpoint() {
+        assert_eq!(message(validate(&[], "clip.webm")), "clip.webm is empty");
+        assert!(validate(b"not checked here", "clip.webm").is_ok());
+    }
+}
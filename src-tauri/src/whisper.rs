@@ -0,0 +1,277 @@
+use reqwest::multipart::{Form, Part};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tokio_util::sync::CancellationToken;
+
+use crate::cancel;
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::events::EmitEvent;
+use crate::http::{self, HttpState};
+use crate::llm;
+use crate::replace;
+use crate::upload;
+
+// Extensions the transcription endpoint accepts
+pub const SUPPORTED_EXTENSIONS: &[&str] = &["flac", "m4a", "mp3", "mp4", "mpeg", "mpga", "oga", "ogg", "wav", "webm"];
+
+/// Backup transcription endpoint from `whisper_fallback`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WhisperFallback {
+    pub url: String,
+    // Empty = no Authorization header, as local servers usually want
+    pub api_key: String,
+    // Same model as the primary when unset
+    pub model: Option<String>,
+}
+
+/// A transcript with the endpoint's confidence in it, when it reported one
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    pub text: String,
+    // Mean per-segment probability (0.0-1.0), from verbose_json `avg_logprob`
+    pub confidence: Option<f32>,
+    // Detected language from verbose_json, as an ISO-639-1 code where known
+    pub language: Option<String>,
+}
+
+// verbose_json names the language ("english"); common ones are mapped to the
+// ISO-639-1 codes `transcription_language` uses, others are kept as named
+const LANGUAGE_CODES: [(&str, &str); 18] = [
+    ("arabic", "ar"),
+    ("catalan", "ca"),
+    ("chinese", "zh"),
+    ("dutch", "nl"),
+    ("english", "en"),
+    ("french", "fr"),
+    ("german", "de"),
+    ("hindi", "hi"),
+    ("italian", "it"),
+    ("japanese", "ja"),
+    ("korean", "ko"),
+    ("polish", "pl"),
+    ("portuguese", "pt"),
+    ("russian", "ru"),
+    ("spanish", "es"),
+    ("swedish", "sv"),
+    ("turkish", "tr"),
+    ("ukrainian", "uk"),
+];
+
+fn language(result: &serde_json::Value) -> Option<String> {
+    let name = result["language"].as_str()?.trim().to_lowercase();
+    if name.is_empty() {
+        return None;
+    }
+    let code = LANGUAGE_CODES.iter().find(|(known, _)| *known == name).map(|(_, code)| code.to_string());
+    Some(code.unwrap_or(name))
+}
+
+/// Average of each segment's token probability, when the response has segments
+fn confidence(result: &serde_json::Value) -> Option<f32> {
+    let logprobs: Vec<f64> = result["segments"].as_array()?.iter().filter_map(|segment| segment["avg_logprob"].as_f64()).collect();
+    if logprobs.is_empty() {
+        return None;
+    }
+    Some((logprobs.iter().map(|logprob| logprob.exp()).sum::<f64>() / logprobs.len() as f64) as f32)
+}
+
+/// Payload of `whisper-fallback-used`
+#[derive(Debug, Clone, Serialize)]
+struct FallbackUsed<'a> {
+    url: &'a str,
+    model: &'a str,
+}
+
+pub fn mime_type(extension: &str) -> &'static str {
+    match extension {
+        "flac" => "audio/flac",
+        "m4a" | "mp4" => "audio/mp4",
+        "mp3" | "mpeg" | "mpga" => "audio/mpeg",
+        "oga" | "ogg" => "audio/ogg",
+        "wav" => "audio/wav",
+        _ => "audio/webm",
+    }
+}
+
+/// Outline of a JSON value with the contents left out, e.g. `{"result": {"transcript": string}}`
+fn shape(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Object(map) => {
+            let fields: Vec<String> = map.iter().map(|(key, value)| format!("\"{key}\": {}", shape(value))).collect();
+            format!("{{{}}}", fields.join(", "))
+        }
+        serde_json::Value::Array(items) => format!("[{}]", items.first().map(shape).unwrap_or_default()),
+        serde_json::Value::String(_) => "string".into(),
+        serde_json::Value::Number(_) => "number".into(),
+        serde_json::Value::Bool(_) => "bool".into(),
+        serde_json::Value::Null => "null".into(),
+    }
+}
+
+/// Reject settings the transcription endpoint would refuse
+pub fn validate(config: &AppConfig) -> Result<(), String> {
+    if !config.whisper_response_path.is_empty() && !config.whisper_response_path.starts_with('/') {
+        return Err(format!("Transcript path \"{}\" must be a JSON pointer such as /text", config.whisper_response_path));
+    }
+    if config.whisper_fallback.as_ref().is_some_and(|fallback| fallback.url.trim().is_empty()) {
+        return Err("The fallback transcription endpoint needs a URL".into());
+    }
+    if !(0.0..=1.0).contains(&config.whisper_temperature) {
+        return Err(format!("Whisper temperature must be between 0.0 and 1.0, got {}", config.whisper_temperature));
+    }
+    if !(0.0..=1.0).contains(&config.min_transcript_confidence) {
+        return Err(format!("Minimum confidence must be between 0.0 and 1.0, got {}", config.min_transcript_confidence));
+    }
+    Ok(())
+}
+
+pub fn api_key(config: &AppConfig) -> Result<&str, AppError> {
+    let api_key = if config.whisper_api_key.is_empty() { &config.openai_api_key } else { &config.whisper_api_key };
+    if api_key.is_empty() {
+        return Err(AppError::Config("Whisper API key is not set".into()));
+    }
+    Ok(api_key)
+}
+
+/// The upload form around the audio part, with the model and options from `config`
+fn form(config: &AppConfig, part: Part) -> Form {
+    let mut form = Form::new()
+        .part("file", part)
+        .text("model", config.whisper_model.clone())
+        .text("temperature", config.whisper_temperature.to_string());
+    if !config.whisper_prompt.is_empty() {
+        form = form.text("prompt", config.whisper_prompt.clone());
+    }
+    if config.whisper_verbose_json {
+        form = form.text("response_format", "verbose_json");
+    }
+    if let Some(language) = config.transcription_language.as_deref().filter(|l| !l.is_empty() && *l != "auto") {
+        form = form.text("language", language.to_string());
+    }
+    form
+}
+
+/// Post `form` and read the transcript out of the response
+async fn send(http: &HttpState, config: &AppConfig, api_key: &str, form: Form, token: &CancellationToken) -> Result<Transcript, AppError> {
+    cancel::run(token, async {
+        let _permit = http.semaphore.acquire().await.map_err(|e| AppError::Network(e.to_string()))?;
+
+        let mut request = http.client().post(&config.whisper_url).timeout(http::whisper_timeout(config)).multipart(form);
+        if !api_key.is_empty() {
+            request = request.bearer_auth(api_key);
+        }
+        let response = request.send().await?;
+        http.record_request_id("Whisper", &response);
+
+        let status = response.status();
+        let body = http.read_body(config, "Whisper", response).await;
+        if !status.is_success() {
+            return Err(AppError::from_status(status, body.unwrap_or_default()));
+        }
+
+        let result: serde_json::Value = serde_json::from_str(&body?).map_err(|e| AppError::Provider {
+            status: status.as_u16(),
+            message: format!("The transcription response is not JSON: {e}"),
+        })?;
+        let text = result.pointer(&config.whisper_response_path).and_then(|text| text.as_str()).ok_or_else(|| {
+            AppError::Provider {
+                status: status.as_u16(),
+                message: format!("No transcript string at {} in a response shaped like {}", config.whisper_response_path, shape(&result)),
+            }
+        })?;
+        Ok(Transcript {
+            text: replace::apply_replacements(&config.replacements, text),
+            confidence: confidence(&result),
+            language: language(&result),
+        })
+    })
+    .await
+}
+
+/// Transcribe audio through the configured Whisper-compatible endpoint
+pub async fn transcribe(
+    http: &HttpState,
+    config: &AppConfig,
+    audio: Vec<u8>,
+    file_name: &str,
+    token: &CancellationToken,
+) -> Result<String, AppError> {
+    upload(http, config, api_key(config)?, audio, file_name, None, token).await.map(|transcript| transcript.text)
+}
+
+/// Upload one file. With `progress`, `upload-progress` events are emitted
+/// through it while the file is sent.
+async fn upload(
+    http: &HttpState,
+    config: &AppConfig,
+    api_key: &str,
+    audio: Vec<u8>,
+    file_name: &str,
+    progress: Option<&AppHandle>,
+    token: &CancellationToken,
+) -> Result<Transcript, AppError> {
+    upload::validate(&audio, file_name)?;
+    let (audio, file_name) = upload::prepare(config.upload_format, audio, file_name);
+    tracing::debug!("Uploading {file_name} ({} KB) to {}", audio.len() / 1024, config.whisper_url);
+    let extension = file_name.rsplit('.').next().unwrap_or_default().to_lowercase();
+    let part = match progress {
+        Some(app) => {
+            let length = audio.len() as u64;
+            Part::stream_with_length(upload::progress_body(app.clone(), audio), length)
+        }
+        None => Part::bytes(audio),
+    };
+    let part = part.file_name(file_name.clone()).mime_str(mime_type(&extension))?;
+    send(http, config, api_key, form(config, part), token).await
+}
+
+/// Like `transcribe`, retrying on `whisper_fallback` when the primary endpoint
+/// is unreachable or failing. Auth and config errors are returned as they are.
+/// Emits `whisper-fallback-used` when the fallback answers.
+pub async fn transcribe_with_fallback(
+    app: &AppHandle,
+    config: &AppConfig,
+    audio: Vec<u8>,
+    file_name: &str,
+    token: &CancellationToken,
+) -> Result<Transcript, AppError> {
+    let http = app.state::<HttpState>();
+    let Some(fallback) = &config.whisper_fallback else {
+        http::ensure_online(app, config, &config.whisper_url).await?;
+        return upload(&http, config, api_key(config)?, audio, file_name, Some(app), token).await;
+    };
+
+    let primary = async {
+        http::ensure_online(app, config, &config.whisper_url).await?;
+        upload(&http, config, api_key(config)?, audio.clone(), file_name, Some(app), token).await
+    };
+    let err = match primary.await {
+        Err(e) if llm::should_fall_back(&e) => e,
+        result => return result,
+    };
+    tracing::warn!("Transcription endpoint failed, trying fallback {}: {err}", fallback.url);
+    let fallback_config = AppConfig {
+        whisper_url: fallback.url.clone(),
+        whisper_model: fallback.model.clone().unwrap_or_else(|| config.whisper_model.clone()),
+        ..config.clone()
+    };
+    let transcript = upload(&http, &fallback_config, &fallback.api_key, audio, file_name, Some(app), token).await?;
+    let _ = app.emit_event("whisper-fallback-used", FallbackUsed { url: &fallback.url, model: &fallback_config.whisper_model });
+    Ok(transcript)
+}
+
+/// Transcribe a WAV body that is still being recorded, sent with chunked
+/// transfer encoding. The request slot is held for the whole recording.
+pub async fn transcribe_stream(
+    http: &HttpState,
+    config: &AppConfig,
+    wav: reqwest::Body,
+    token: &CancellationToken,
+) -> Result<Transcript, AppError> {
+    let api_key = api_key(config)?;
+    tracing::debug!("Streaming recording.wav to {}", config.whisper_url);
+    let part = Part::stream(wav).file_name("recording.wav").mime_str(mime_type("wav"))?;
+    send(http, config, api_key, form(config, part), token).await
+}
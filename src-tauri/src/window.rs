@@ -0,0 +1,262 @@
+use serde::Serialize;
+use tauri::{AppHandle, Manager, Monitor, PhysicalPosition, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
+
+use crate::events::{EmitEvent, Empty, Navigate};
+use crate::{audio, autohide, cancel, config, headless};
+
+const EDITOR_LABEL: &str = "editor";
+// The editor is for long transcripts, so it starts well above the popup's size
+const EDITOR_WIDTH: f64 = 900.0;
+const EDITOR_HEIGHT: f64 = 700.0;
+
+/// Hide the main window to the tray, aborting whatever it was doing apart from
+/// a native recording, which keeps going with the tray in Recording. Without a
+/// tray the window is minimized instead so it can still be reached.
+pub fn hide_main(app: &AppHandle) -> Result<(), String> {
+    if audio::is_recording(app) {
+        cancel::cancel_requests(app);
+    } else {
+        cancel::cancel_all(app.clone());
+    }
+    autohide::clear(app);
+    if let Some(window) = app.get_webview_window("main") {
+        window.emit_event("window-hidden", Empty {}).map_err(|e| e.to_string())?;
+        if config::get(app).show_tray {
+            window.hide().map_err(|e| e.to_string())?;
+        } else {
+            window.minimize().map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+/// The main window, recreated from its `tauri.conf.json` definition if it was
+/// destroyed while the process kept running. Headless runs never get one.
+pub fn main_window(app: &AppHandle) -> Option<WebviewWindow> {
+    if let Some(window) = app.get_webview_window("main") {
+        return Some(window);
+    }
+    if headless::active() {
+        return None;
+    }
+
+    let window_config = app.config().app.windows.iter().find(|w| w.label == "main")?.clone();
+    match WebviewWindowBuilder::from_config(app, &window_config).and_then(|builder| builder.build()) {
+        Ok(window) => {
+            tracing::warn!("Main window was missing and has been recreated");
+            let _ = app.emit_event("window-recreated", Empty {});
+            Some(window)
+        }
+        Err(e) => {
+            tracing::error!("Failed to recreate main window: {e}");
+            None
+        }
+    }
+}
+
+/// A connected monitor as listed by `list_monitors`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonitorInfo {
+    pub index: usize,
+    pub name: Option<String>,
+    pub primary: bool,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f64,
+}
+
+/// Connected monitors, for choosing `preferred_monitor`
+#[tauri::command]
+pub fn list_monitors(app: AppHandle) -> Result<Vec<MonitorInfo>, String> {
+    let primary = app.primary_monitor().map_err(|e| e.to_string())?;
+    let monitors = app.available_monitors().map_err(|e| e.to_string())?;
+    Ok(monitors
+        .into_iter()
+        .enumerate()
+        .map(|(index, monitor)| MonitorInfo {
+            index,
+            name: monitor.name().cloned(),
+            primary: primary.as_ref().is_some_and(|p| p.name() == monitor.name() && p.position() == monitor.position()),
+            x: monitor.position().x,
+            y: monitor.position().y,
+            width: monitor.size().width,
+            height: monitor.size().height,
+            scale_factor: monitor.scale_factor(),
+        })
+        .collect())
+}
+
+fn center_on(window: &WebviewWindow, monitor: &Monitor) {
+    let Ok(size) = window.outer_size() else {
+        let _ = window.center();
+        return;
+    };
+    let (origin, extent) = (monitor.position(), monitor.size());
+    let x = origin.x + (extent.width as i32 - size.width as i32).max(0) / 2;
+    let y = origin.y + (extent.height as i32 - size.height as i32).max(0) / 2;
+    let _ = window.set_position(PhysicalPosition::new(x, y));
+}
+
+/// Center the window on the primary monitor, or whichever monitor it is on
+/// when there is no primary
+fn center_on_primary(app: &AppHandle, window: &WebviewWindow) {
+    match app.primary_monitor() {
+        Ok(Some(monitor)) => center_on(window, &monitor),
+        _ => {
+            let _ = window.center();
+        }
+    }
+}
+
+/// Center the window on `preferred_monitor`. Returns false when none is set;
+/// a monitor that is no longer connected falls back to the primary one and
+/// the stale preference is cleared.
+fn place_on_preferred(app: &AppHandle, window: &WebviewWindow) -> bool {
+    let Some(preferred) = config::get(app).preferred_monitor else {
+        return false;
+    };
+    let monitors = app.available_monitors().unwrap_or_default();
+    let found = monitors
+        .iter()
+        .find(|monitor| monitor.name() == Some(&preferred))
+        .or_else(|| preferred.parse::<usize>().ok().and_then(|index| monitors.get(index)));
+    match found {
+        Some(monitor) => center_on(window, monitor),
+        None => {
+            tracing::warn!("Preferred monitor \"{preferred}\" is not connected; using the primary monitor and forgetting it");
+            if let Err(e) = config::modify(app, |config| config.preferred_monitor = None) {
+                tracing::error!("Failed to clear preferred monitor: {e}");
+            }
+            center_on_primary(app, window);
+        }
+    }
+    true
+}
+
+/// Bring the window back on screen if its last position was on a monitor
+/// that has since been disconnected
+fn ensure_on_screen(app: &AppHandle, window: &WebviewWindow) {
+    let (Ok(position), Ok(size), Ok(monitors)) = (window.outer_position(), window.outer_size(), app.available_monitors()) else {
+        return;
+    };
+    let (left, top) = (position.x, position.y);
+    let (right, bottom) = (left + size.width as i32, top + size.height as i32);
+    let visible = monitors.iter().any(|monitor| {
+        let (origin, extent) = (monitor.position(), monitor.size());
+        left < origin.x + extent.width as i32 && right > origin.x && top < origin.y + extent.height as i32 && bottom > origin.y
+    });
+    if !visible {
+        tracing::info!("Main window was off screen at {left},{top}; moving it to the primary monitor");
+        center_on_primary(app, window);
+    }
+}
+
+/// Open the editor window, or focus it when it is already open. Unlike the
+/// main window it is a regular window: closing it destroys it.
+#[tauri::command]
+pub fn open_editor_window(app: AppHandle) -> Result<(), String> {
+    if headless::active() {
+        return Err("No windows can be opened in headless mode".into());
+    }
+    let window = match app.get_webview_window(EDITOR_LABEL) {
+        Some(window) => window,
+        None => WebviewWindowBuilder::new(&app, EDITOR_LABEL, WebviewUrl::App("index.html?view=editor".into()))
+            .title("AMA Agent Editor")
+            .inner_size(EDITOR_WIDTH, EDITOR_HEIGHT)
+            .resizable(true)
+            .center()
+            .build()
+            .map_err(|e| e.to_string())?,
+    };
+    let _ = window.unminimize();
+    window.show().map_err(|e| e.to_string())?;
+    window.set_focus().map_err(|e| e.to_string())
+}
+
+/// Move the window to the middle of the primary monitor and show it, for when
+/// it ended up somewhere it can't be found
+#[tauri::command]
+pub fn recenter_window(app: AppHandle) {
+    if let Some(window) = main_window(&app) {
+        center_on_primary(&app, &window);
+    }
+    show_main(&app);
+}
+
+/// Show and focus the main window without touching recording state
+pub fn show_main(app: &AppHandle) {
+    if let Some(window) = main_window(app) {
+        if !place_on_preferred(app, &window) {
+            ensure_on_screen(app, &window);
+        }
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+/// Show the main window and tell the frontend to switch to `route`
+pub fn navigate_to(app: &AppHandle, route: &str) {
+    show_main(app);
+    let _ = app.emit_event("navigate", Navigate { route });
+}
+
+/// Bring up the window on a given view, e.g. `settings`, for links from
+/// notifications or other windows
+#[tauri::command]
+pub fn navigate(app: AppHandle, route: String) {
+    navigate_to(&app, &route);
+}
+
+// Gap between the pointer and the window's top-left corner when summoned
+const CURSOR_OFFSET: i32 = 16;
+
+/// Move the window next to the cursor, kept on the cursor's monitor.
+/// Returns false if the cursor or its monitor couldn't be found.
+fn move_to_cursor(app: &AppHandle, window: &WebviewWindow) -> bool {
+    let (Ok(cursor), Ok(monitors), Ok(size)) = (app.cursor_position(), app.available_monitors(), window.outer_size()) else {
+        return false;
+    };
+    let (x, y) = (cursor.x as i32, cursor.y as i32);
+    let Some(monitor) = monitors.iter().find(|monitor| {
+        let (origin, extent) = (monitor.position(), monitor.size());
+        (origin.x..origin.x + extent.width as i32).contains(&x) && (origin.y..origin.y + extent.height as i32).contains(&y)
+    }) else {
+        return false;
+    };
+
+    let (origin, extent) = (monitor.position(), monitor.size());
+    let max_x = (origin.x + extent.width as i32 - size.width as i32).max(origin.x);
+    let max_y = (origin.y + extent.height as i32 - size.height as i32).max(origin.y);
+    let position = PhysicalPosition::new((x + CURSOR_OFFSET).clamp(origin.x, max_x), (y + CURSOR_OFFSET).clamp(origin.y, max_y));
+    window.set_position(position).is_ok()
+}
+
+/// Show the window for the shortcut, placing it at the cursor when
+/// `summon_at_cursor` is on (centered if the cursor can't be located)
+pub fn summon_main(app: &AppHandle) {
+    let config = config::get(app);
+    if config.summon_at_cursor && config.preferred_monitor.is_none() {
+        if let Some(window) = main_window(app) {
+            if !move_to_cursor(app, &window) {
+                let _ = window.center();
+            }
+        }
+    }
+    show_main(app);
+}
+
+pub fn is_main_visible(app: &AppHandle) -> bool {
+    app.get_webview_window("main")
+        .and_then(|window| Some(window.is_visible().ok()? && !window.is_minimized().ok()?))
+        .unwrap_or(false)
+}
+
+pub fn is_main_focused(app: &AppHandle) -> bool {
+    app.get_webview_window("main")
+        .and_then(|window| window.is_focused().ok())
+        .unwrap_or(false)
+}
@@ -0,0 +1,36 @@
+// `xprop` queries shared by the X11 foreground and full-screen checks
+use std::process::Command;
+
+/// Output of `xprop` with `args`, when it ran and succeeded
+pub fn xprop(args: &[&str]) -> Option<String> {
+    let output = Command::new("xprop").args(args).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Id of the window the window manager reports as active, e.g. `0x3a00007`
+pub fn active_window() -> Option<String> {
+    parse_active_window(&xprop(&["-root", "_NET_ACTIVE_WINDOW"])?)
+}
+
+// _NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007
+fn parse_active_window(output: &str) -> Option<String> {
+    let id = output.split_whitespace().last().filter(|id| id.starts_with("0x") && *id != "0x0")?;
+    Some(id.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn active_window_id_is_parsed() {
+        assert_eq!(parse_active_window("_NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007\n").as_deref(), Some("0x3a00007"));
+    }
+
+    #[test]
+    fn no_active_window() {
+        assert_eq!(parse_active_window("_NET_ACTIVE_WINDOW(WINDOW): window id # 0x0\n"), None);
+        assert_eq!(parse_active_window("_NET_ACTIVE_WINDOW:  not found.\n"), None);
+        assert_eq!(parse_active_window(""), None);
+    }
+}